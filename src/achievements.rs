@@ -0,0 +1,236 @@
+use super::{
+    components, scores,
+    spawner::{FIREBALL_SCROLL_NAME, GOLEM_NAME, MAGIC_MAPPING_SCROLL_NAME},
+    GameLog,
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const ACHIEVEMENTS_FILE: &str = "./achievements.json";
+
+/// Something noteworthy the player did. Emitted by the code that makes it happen and checked
+/// against the achievement rules once per frame.
+pub enum GameEvent {
+    Kill {
+        victim: String,
+        // The item or spell that dealt the killing blow
+        with: Option<String>,
+    },
+    UsedItem {
+        item: String,
+    },
+    Ate {
+        rotten: bool,
+    },
+    Descended {
+        depth: i32,
+        invisible: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct GameEvents {
+    events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn emit(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+}
+
+enum Rule {
+    Kills { count: i32 },
+    Slay { monster: &'static str },
+    // Kills that all land in the same frame, which is as close as we get to "one action"
+    KillsAtOnce { with: &'static str, count: usize },
+    UseItem { item: &'static str },
+    EatRotten,
+    ReachDepth { depth: i32 },
+    ReachDepthWithoutEating { depth: i32 },
+    DescendInvisible,
+}
+
+impl Rule {
+    fn is_met(&self, events: &[GameEvent], stats: &components::RunStats) -> bool {
+        let reached = |target: i32| {
+            events
+                .iter()
+                .any(|e| matches!(e, GameEvent::Descended { depth, .. } if *depth >= target))
+        };
+
+        match self {
+            Rule::Kills { count } => {
+                events.iter().any(|e| matches!(e, GameEvent::Kill { .. }))
+                    && stats.kills.values().sum::<i32>() >= *count
+            }
+            Rule::Slay { monster } => events
+                .iter()
+                .any(|e| matches!(e, GameEvent::Kill { victim, .. } if victim == monster)),
+            Rule::KillsAtOnce { with, count } => events
+                .iter()
+                .filter(
+                    |e| matches!(e, GameEvent::Kill { with: Some(source), .. } if source == with),
+                )
+                .count()
+                >= *count,
+            Rule::UseItem { item } => events
+                .iter()
+                .any(|e| matches!(e, GameEvent::UsedItem { item: used } if used == item)),
+            Rule::EatRotten => events
+                .iter()
+                .any(|e| matches!(e, GameEvent::Ate { rotten: true })),
+            Rule::ReachDepth { depth } => reached(*depth),
+            Rule::ReachDepthWithoutEating { depth } => stats.meals_eaten == 0 && reached(*depth),
+            Rule::DescendInvisible => events.iter().any(|e| {
+                matches!(
+                    e,
+                    GameEvent::Descended {
+                        invisible: true,
+                        ..
+                    }
+                )
+            }),
+        }
+    }
+}
+
+pub struct Achievement {
+    pub name: &'static str,
+    pub description: &'static str,
+    rule: Rule,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 9] = [
+    Achievement {
+        name: "First Blood",
+        description: "Kill a monster",
+        rule: Rule::Kills { count: 1 },
+    },
+    Achievement {
+        name: "Exterminator",
+        description: "Kill 50 monsters in one run",
+        rule: Rule::Kills { count: 50 },
+    },
+    Achievement {
+        name: "Giant Slayer",
+        description: "Kill a Golem",
+        rule: Rule::Slay {
+            monster: GOLEM_NAME,
+        },
+    },
+    Achievement {
+        name: "Firestorm",
+        description: "Kill 5 monsters with one fireball",
+        rule: Rule::KillsAtOnce {
+            with: FIREBALL_SCROLL_NAME,
+            count: 5,
+        },
+    },
+    Achievement {
+        name: "Cartographer",
+        description: "Read a magic mapping scroll",
+        rule: Rule::UseItem {
+            item: MAGIC_MAPPING_SCROLL_NAME,
+        },
+    },
+    Achievement {
+        name: "Iron Stomach",
+        description: "Eat something rotten",
+        rule: Rule::EatRotten,
+    },
+    Achievement {
+        name: "Delver",
+        description: "Reach depth 5",
+        rule: Rule::ReachDepth { depth: 5 },
+    },
+    Achievement {
+        name: "Ascetic",
+        description: "Reach depth 10 without eating",
+        rule: Rule::ReachDepthWithoutEating { depth: 10 },
+    },
+    Achievement {
+        name: "Ghost",
+        description: "Descend while invisible",
+        rule: Rule::DescendInvisible,
+    },
+];
+
+/// Achievements earned in any run, by name, with the time they were first earned. Kept in
+/// `achievements.json` so they survive between games.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Unlocks {
+    pub unlocked: BTreeMap<String, u64>,
+}
+
+impl Unlocks {
+    pub fn load() -> Unlocks {
+        if !Path::new(ACHIEVEMENTS_FILE).exists() {
+            return Unlocks::default();
+        }
+
+        let data = fs::read_to_string(ACHIEVEMENTS_FILE).expect("Unable to read achievements file");
+        match serde_json::from_str(&data) {
+            Ok(unlocks) => unlocks,
+            Err(e) => {
+                rltk::console::log(format!("Ignoring broken {}: {}", ACHIEVEMENTS_FILE, e));
+                Unlocks::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let data = serde_json::to_string_pretty(self).expect("Unable to serialize achievements");
+        if let Err(e) = fs::write(ACHIEVEMENTS_FILE, data) {
+            rltk::console::log(format!("Unable to write {}: {}", ACHIEVEMENTS_FILE, e));
+        }
+    }
+
+    pub fn unlocked_on(&self, achievement: &Achievement) -> Option<String> {
+        self.unlocked
+            .get(achievement.name)
+            .map(|date| scores::format_date(*date))
+    }
+}
+
+/// Checks everything that happened since the last call against the achievements that haven't
+/// been earned yet.
+pub fn process_events(ecs: &World) {
+    let events = std::mem::take(&mut ecs.write_resource::<GameEvents>().events);
+    if events.is_empty() {
+        return;
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    let run_stats = ecs.read_storage::<components::RunStats>();
+    let stats = match run_stats.get(player_entity) {
+        Some(stats) => stats,
+        None => return,
+    };
+    let mut unlocks = ecs.write_resource::<Unlocks>();
+    let mut log = ecs.write_resource::<GameLog>();
+
+    let mut earned_any = false;
+    for achievement in ACHIEVEMENTS.iter() {
+        if unlocks.unlocked.contains_key(achievement.name)
+            || !achievement.rule.is_met(&events, stats)
+        {
+            continue;
+        }
+
+        unlocks
+            .unlocked
+            .insert(achievement.name.to_string(), scores::timestamp());
+        log.entries
+            .push(format!("Achievement unlocked: {}!", achievement.name));
+        earned_any = true;
+    }
+
+    if earned_any {
+        unlocks.save();
+    }
+}
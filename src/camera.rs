@@ -0,0 +1,97 @@
+use super::{components, map, Map, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use rltk::{Point, Rltk, RGB};
+use specs::prelude::*;
+
+const SHOW_BOUNDARIES: bool = true;
+
+/// Returns the (min_x, max_x, min_y, max_y) world coordinates visible on screen, with the
+/// player in the middle.
+pub fn get_screen_bounds(ecs: &World) -> (i32, i32, i32, i32) {
+    let player_pos = ecs.fetch::<Point>();
+    screen_bounds_centered_on(player_pos.x, player_pos.y)
+}
+
+fn screen_bounds_centered_on(x: i32, y: i32) -> (i32, i32, i32, i32) {
+    let min_x = x - (VIEWPORT_WIDTH / 2);
+    let max_x = min_x + VIEWPORT_WIDTH;
+    let min_y = y - (VIEWPORT_HEIGHT / 2);
+    let max_y = min_y + VIEWPORT_HEIGHT;
+
+    (min_x, max_x, min_y, max_y)
+}
+
+/// Converts a position on the screen to a position on the map
+pub fn screen_to_world(ecs: &World, screen_x: i32, screen_y: i32) -> Point {
+    let (min_x, _max_x, min_y, _max_y) = get_screen_bounds(ecs);
+    Point::new(screen_x + min_x, screen_y + min_y)
+}
+
+pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let (min_x, max_x, min_y, max_y) = get_screen_bounds(ecs);
+    render_map_window(&map, ctx, min_x, max_x, min_y, max_y);
+
+    let positions = ecs.read_storage::<components::Position>();
+    let renderables = ecs.read_storage::<components::Renderable>();
+    let invisibles = ecs.read_storage::<components::Invisible>();
+    let players = ecs.read_storage::<components::Player>();
+    let entities = ecs.entities();
+
+    let mut data = (&positions, &renderables, &entities)
+        .join()
+        .collect::<Vec<_>>();
+    data.sort_by(|&a, &b| b.1.layer.cmp(&a.1.layer));
+    for (pos, render, entity) in data.iter() {
+        if pos.x < min_x || pos.x >= max_x || pos.y < min_y || pos.y >= max_y {
+            continue;
+        }
+        if pos.x < 0 || pos.x >= map.width || pos.y < 0 || pos.y >= map.height {
+            continue;
+        }
+
+        let idx = map.xy_idx(pos.x, pos.y);
+        if !map.visible_tiles[idx] {
+            continue;
+        }
+
+        let screen_x = pos.x - min_x;
+        let screen_y = pos.y - min_y;
+        match invisibles.get(*entity) {
+            Some(_) => {
+                // We can still see ourselves, if faintly
+                if players.get(*entity).is_some() {
+                    ctx.set(screen_x, screen_y, rltk::GRAY65, render.bg, render.glyph)
+                }
+            }
+            None => ctx.set(screen_x, screen_y, render.fg, render.bg, render.glyph),
+        }
+    }
+}
+
+/// Renders a map (such as a mapgen snapshot) centered on its middle, ignoring the player.
+pub fn render_debug_map(map: &Map, ctx: &mut Rltk) {
+    let (min_x, max_x, min_y, max_y) = screen_bounds_centered_on(map.width / 2, map.height / 2);
+    render_map_window(map, ctx, min_x, max_x, min_y, max_y);
+}
+
+fn render_map_window(map: &Map, ctx: &mut Rltk, min_x: i32, max_x: i32, min_y: i32, max_y: i32) {
+    for (y, ty) in (min_y..max_y).enumerate() {
+        for (x, tx) in (min_x..max_x).enumerate() {
+            if tx >= 0 && tx < map.width && ty >= 0 && ty < map.height {
+                let idx = map.xy_idx(tx, ty);
+                if map.revealed_tiles[idx] {
+                    let (glyph, fg, bg) = map::tile_glyph(idx, map);
+                    ctx.set(x as i32, y as i32, fg, bg, glyph);
+                }
+            } else if SHOW_BOUNDARIES {
+                ctx.set(
+                    x as i32,
+                    y as i32,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    rltk::to_cp437('·'),
+                );
+            }
+        }
+    }
+}
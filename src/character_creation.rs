@@ -1,35 +1,633 @@
+use super::{
+    components::{Attributes, CombatStats, Mana, Name, Skills},
+    controls::{Action, ActionGroup, Controls},
+    spawner,
+};
 use rltk::{Rltk, VirtualKeyCode, RGB};
+use specs::prelude::*;
 
-pub fn create_character(ctx: &mut Rltk, items: Vec<String>) -> Option<String> {
+const MAX_NAME_LENGTH: usize = 20;
+
+pub const BASE_ATTRIBUTE: i32 = 10;
+const MAX_ATTRIBUTE: i32 = 16;
+const ATTRIBUTE_POINTS: i32 = 10;
+
+const ATTRIBUTE_NAMES: [(&str, &str); 4] = [
+    ("Strength", "Carry more and hit harder"),
+    ("Agility", "Dodge blows, spot and disarm traps"),
+    ("Toughness", "More hit points"),
+    ("Intellect", "More mana"),
+];
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CreationStep {
+    Name,
+    Class,
+    Attributes,
+    Summary,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum Class {
+    Scout,
+    Mentat,
+    Swordmaster,
+}
+
+impl Class {
+    const ALL: [Class; 3] = [Class::Scout, Class::Mentat, Class::Swordmaster];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Class::Scout => "Desert Scout",
+            Class::Mentat => "Mentat",
+            Class::Swordmaster => "Swordmaster",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Class::Scout => "Lives off the sand. Sharp eyes, light feet and a crysknife.",
+            Class::Mentat => "A human computer. Frail, but with a head full of spells.",
+            Class::Swordmaster => "Trained with blade and shield. Hard to kill, knows no magic.",
+        }
+    }
+
+    fn base_stats(&self) -> CombatStats {
+        let (max_hp, defense, power) = match self {
+            Class::Scout => (30, 1, 5),
+            Class::Mentat => (24, 1, 3),
+            Class::Swordmaster => (36, 2, 6),
+        };
+        CombatStats {
+            max_hp,
+            hp: max_hp,
+            defense,
+            power,
+        }
+    }
+
+    fn base_mana(&self) -> i32 {
+        match self {
+            Class::Scout => 8,
+            Class::Mentat => 20,
+            Class::Swordmaster => 0,
+        }
+    }
+
+    fn base_skills(&self) -> Skills {
+        match self {
+            Class::Scout => Skills {
+                perception: 3,
+                disarm: 3,
+            },
+            Class::Mentat => Skills {
+                perception: 2,
+                disarm: 1,
+            },
+            Class::Swordmaster => Skills {
+                perception: 1,
+                disarm: 0,
+            },
+        }
+    }
+
+    fn kit(&self) -> &'static [&'static str] {
+        match self {
+            Class::Scout => &[
+                "Dagger",
+                "Rations",
+                "Invisibility Timer",
+                "Torch",
+                "Water Flask",
+            ],
+            Class::Mentat => &["Confusion Wand", "Health Potion", "Torch", "Water Flask"],
+            Class::Swordmaster => &["Longsword", "Shield", "Rations", "Torch", "Water Flask"],
+        }
+    }
+
+    fn spells(&self) -> &'static [&'static str] {
+        match self {
+            Class::Scout => &["Sand Blast"],
+            Class::Mentat => &["Sand Blast", "Spice Sight"],
+            Class::Swordmaster => &[],
+        }
+    }
+
+    fn index(&self) -> usize {
+        Class::ALL.iter().position(|c| c == self).unwrap()
+    }
+}
+
+/// The character being put together, kept as a resource while the creation screens are up.
+pub struct CharacterSheet {
+    name: String,
+    class: Class,
+    attributes: Attributes,
+}
+
+impl Default for CharacterSheet {
+    fn default() -> CharacterSheet {
+        CharacterSheet {
+            name: String::new(),
+            class: Class::Scout,
+            attributes: Attributes {
+                strength: BASE_ATTRIBUTE,
+                agility: BASE_ATTRIBUTE,
+                toughness: BASE_ATTRIBUTE,
+                intellect: BASE_ATTRIBUTE,
+            },
+        }
+    }
+}
+
+impl CharacterSheet {
+    fn attribute_mut(&mut self, index: usize) -> &mut i32 {
+        match index {
+            0 => &mut self.attributes.strength,
+            1 => &mut self.attributes.agility,
+            2 => &mut self.attributes.toughness,
+            _ => &mut self.attributes.intellect,
+        }
+    }
+
+    fn attribute(&self, index: usize) -> i32 {
+        match index {
+            0 => self.attributes.strength,
+            1 => self.attributes.agility,
+            2 => self.attributes.toughness,
+            _ => self.attributes.intellect,
+        }
+    }
+
+    fn points_left(&self) -> i32 {
+        ATTRIBUTE_POINTS
+            - (0..ATTRIBUTE_NAMES.len())
+                .map(|i| self.attribute(i) - BASE_ATTRIBUTE)
+                .sum::<i32>()
+    }
+
+    /// Spends a point on an attribute, if there is one left and the attribute isn't maxed out.
+    fn raise_attribute(&mut self, index: usize) {
+        if self.points_left() > 0 && self.attribute(index) < MAX_ATTRIBUTE {
+            *self.attribute_mut(index) += 1;
+        }
+    }
+
+    /// Takes a point back, but never below where the attribute started.
+    fn lower_attribute(&mut self, index: usize) {
+        if self.attribute(index) > BASE_ATTRIBUTE {
+            *self.attribute_mut(index) -= 1;
+        }
+    }
+
+    fn combat_stats(&self) -> CombatStats {
+        let mut stats = self.class.base_stats();
+        stats.max_hp += (self.attributes.toughness - BASE_ATTRIBUTE) * 2;
+        stats.hp = stats.max_hp;
+        stats.power += bonus(self.attributes.strength);
+        stats.defense += bonus(self.attributes.agility);
+        stats
+    }
+
+    fn max_mana(&self) -> i32 {
+        self.class.base_mana() + (self.attributes.intellect - BASE_ATTRIBUTE) * 2
+    }
+
+    fn skills(&self) -> Skills {
+        let mut skills = self.class.base_skills();
+        skills.perception += bonus(self.attributes.agility);
+        skills.disarm += bonus(self.attributes.agility);
+        skills
+    }
+}
+
+// Every two points above the base is worth one point of the derived stat
+fn bonus(attribute: i32) -> i32 {
+    (attribute - BASE_ATTRIBUTE) / 2
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CharacterCreationResult {
+    NoResponse,
+    Cancel,
+    Updated {
+        step: CreationStep,
+        selection: usize,
+    },
+    Finished,
+}
+
+pub fn create_character(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    step: CreationStep,
+    selection: usize,
+) -> CharacterCreationResult {
+    let controls = ecs.fetch::<Controls>();
+    let mut sheet = ecs.fetch_mut::<CharacterSheet>();
+
+    let (number, title) = match step {
+        CreationStep::Name => (1, "Name"),
+        CreationStep::Class => (2, "Class"),
+        CreationStep::Attributes => (3, "Attributes"),
+        CreationStep::Summary => (4, "Summary"),
+    };
     ctx.print_color_centered(
-        15,
+        10,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Choose an item",
+        format!("Step {} of 4: {}", number, title),
+    );
+
+    let first_key = |action: Action| -> String {
+        controls
+            .describe_keys(action)
+            .split(", ")
+            .next()
+            .unwrap_or("")
+            .to_string()
+    };
+    let help = match step {
+        CreationStep::Name => format!(
+            "Type a name, {} to delete, {} to continue, {} to go back",
+            first_key(Action::MenuClear),
+            first_key(Action::MenuSelect),
+            first_key(Action::MenuCancel)
+        ),
+        CreationStep::Attributes => format!(
+            "{}/{} choose, {} add a point, {} take one back, {} to go back",
+            first_key(Action::MenuUp),
+            first_key(Action::MenuDown),
+            first_key(Action::MenuSelect),
+            first_key(Action::MenuClear),
+            first_key(Action::MenuCancel)
+        ),
+        _ => format!(
+            "{}/{} choose, {} to continue, {} to go back",
+            first_key(Action::MenuUp),
+            first_key(Action::MenuDown),
+            first_key(Action::MenuSelect),
+            first_key(Action::MenuCancel)
+        ),
+    };
+    ctx.print_color_centered(47, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), help);
+
+    match step {
+        CreationStep::Name => name_entry(ctx, &controls, &mut sheet),
+        CreationStep::Class => choose_class(ctx, &controls, &mut sheet, selection),
+        CreationStep::Attributes => buy_attributes(ctx, &controls, &mut sheet, selection),
+        CreationStep::Summary => summary(ctx, &controls, &sheet),
+    }
+}
+
+fn name_entry(
+    ctx: &mut Rltk,
+    controls: &Controls,
+    sheet: &mut CharacterSheet,
+) -> CharacterCreationResult {
+    ctx.print_color_centered(
+        15,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        "What is your name?",
+    );
+    ctx.print_color_centered(
+        17,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        format!("{}_", sheet.name),
+    );
+
+    let key = match ctx.key {
+        None => return CharacterCreationResult::NoResponse,
+        Some(key) => key,
+    };
+    match controls.action_for(key, ActionGroup::Menu) {
+        Some(Action::MenuCancel) => CharacterCreationResult::Cancel,
+        Some(Action::MenuSelect) if !sheet.name.trim().is_empty() => {
+            sheet.name = sheet.name.trim().to_string();
+            CharacterCreationResult::Updated {
+                step: CreationStep::Class,
+                selection: sheet.class.index(),
+            }
+        }
+        Some(Action::MenuClear) => {
+            sheet.name.pop();
+            CharacterCreationResult::NoResponse
+        }
+        // Anything else, even a key that moves menus around, is part of the name
+        _ => {
+            if let Some(c) = typed_char(key, ctx.shift) {
+                if sheet.name.len() < MAX_NAME_LENGTH {
+                    sheet.name.push(c);
+                }
+            }
+            CharacterCreationResult::NoResponse
+        }
+    }
+}
+
+fn typed_char(key: VirtualKeyCode, shift: bool) -> Option<char> {
+    if key == VirtualKeyCode::Space {
+        return Some(' ');
+    }
+    let letter = rltk::letter_to_option(key);
+    if letter < 0 {
+        return None;
+    }
+    let c = (b'a' + letter as u8) as char;
+    Some(if shift { c.to_ascii_uppercase() } else { c })
+}
+
+fn choose_class(
+    ctx: &mut Rltk,
+    controls: &Controls,
+    sheet: &mut CharacterSheet,
+    selection: usize,
+) -> CharacterCreationResult {
+    for (index, class) in Class::ALL.iter().enumerate() {
+        let fg = if index == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color_centered(15 + (index * 2), fg, RGB::named(rltk::BLACK), class.name());
+    }
+
+    let class = Class::ALL[selection];
+    let stats = class.base_stats();
+    ctx.print_color_centered(
+        23,
+        RGB::named(rltk::WHEAT),
+        RGB::named(rltk::BLACK),
+        class.description(),
+    );
+    ctx.print_color_centered(
+        25,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        format!(
+            "HP {}  Power {}  Defense {}  Mana {}",
+            stats.max_hp,
+            stats.power,
+            stats.defense,
+            class.base_mana()
+        ),
+    );
+    ctx.print_color_centered(
+        26,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        format!("Starts with: {}", describe_kit(class)),
+    );
+
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuCancel) => CharacterCreationResult::Updated {
+            step: CreationStep::Name,
+            selection: 0,
+        },
+        Some(Action::MenuUp) => CharacterCreationResult::Updated {
+            step: CreationStep::Class,
+            selection: (selection + Class::ALL.len() - 1) % Class::ALL.len(),
+        },
+        Some(Action::MenuDown) => CharacterCreationResult::Updated {
+            step: CreationStep::Class,
+            selection: (selection + 1) % Class::ALL.len(),
+        },
+        Some(Action::MenuSelect) => {
+            sheet.class = class;
+            CharacterCreationResult::Updated {
+                step: CreationStep::Attributes,
+                selection: 0,
+            }
+        }
+        _ => CharacterCreationResult::NoResponse,
+    }
+}
+
+fn buy_attributes(
+    ctx: &mut Rltk,
+    controls: &Controls,
+    sheet: &mut CharacterSheet,
+    selection: usize,
+) -> CharacterCreationResult {
+    // One row per attribute, then one to move on
+    let row_count = ATTRIBUTE_NAMES.len() + 1;
+
+    ctx.print_color_centered(
+        13,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        format!("Points left: {}", sheet.points_left()),
     );
+    for (index, (name, description)) in ATTRIBUTE_NAMES.iter().enumerate() {
+        let fg = if index == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        let y = 15 + (index as i32 * 2);
+        ctx.print_color(20, y, fg, RGB::named(rltk::BLACK), name);
+        ctx.print_color(
+            32,
+            y,
+            fg,
+            RGB::named(rltk::BLACK),
+            sheet.attribute(index).to_string(),
+        );
+        ctx.print_color(
+            36,
+            y,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            description,
+        );
+    }
+    let fg = if selection == ATTRIBUTE_NAMES.len() {
+        RGB::named(rltk::MAGENTA)
+    } else {
+        RGB::named(rltk::WHITE)
+    };
+    ctx.print_color(20, 24, fg, RGB::named(rltk::BLACK), "Continue");
+
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuCancel) => CharacterCreationResult::Updated {
+            step: CreationStep::Class,
+            selection: sheet.class.index(),
+        },
+        Some(Action::MenuUp) => CharacterCreationResult::Updated {
+            step: CreationStep::Attributes,
+            selection: (selection + row_count - 1) % row_count,
+        },
+        Some(Action::MenuDown) => CharacterCreationResult::Updated {
+            step: CreationStep::Attributes,
+            selection: (selection + 1) % row_count,
+        },
+        Some(Action::MenuSelect) if selection == ATTRIBUTE_NAMES.len() => {
+            CharacterCreationResult::Updated {
+                step: CreationStep::Summary,
+                selection: 0,
+            }
+        }
+        Some(Action::MenuSelect) => {
+            sheet.raise_attribute(selection);
+            CharacterCreationResult::NoResponse
+        }
+        Some(Action::MenuClear) if selection < ATTRIBUTE_NAMES.len() => {
+            sheet.lower_attribute(selection);
+            CharacterCreationResult::NoResponse
+        }
+        _ => CharacterCreationResult::NoResponse,
+    }
+}
 
-    for (index, item) in items.iter().enumerate() {
+fn summary(ctx: &mut Rltk, controls: &Controls, sheet: &CharacterSheet) -> CharacterCreationResult {
+    let stats = sheet.combat_stats();
+    let skills = sheet.skills();
+    let lines = [
+        format!("{}, {}", sheet.name, sheet.class.name()),
+        String::new(),
+        format!(
+            "HP {}  Power {}  Defense {}  Mana {}",
+            stats.max_hp,
+            stats.power,
+            stats.defense,
+            sheet.max_mana()
+        ),
+        format!(
+            "Strength {}  Agility {}  Toughness {}  Intellect {}",
+            sheet.attributes.strength,
+            sheet.attributes.agility,
+            sheet.attributes.toughness,
+            sheet.attributes.intellect
+        ),
+        format!("Perception {}  Disarm {}", skills.perception, skills.disarm),
+        String::new(),
+        format!("Starts with: {}", describe_kit(sheet.class)),
+    ];
+    for (index, line) in lines.iter().enumerate() {
         ctx.print_color_centered(
-            17 + (index * 2),
+            15 + index,
             RGB::named(rltk::WHITE),
             RGB::named(rltk::BLACK),
-            format!("{}: {}", index + 1, item),
+            line,
         );
     }
+    ctx.print_color_centered(
+        25,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        "Begin",
+    );
 
-    match ctx.key {
-        None => None,
-        Some(key) => match key {
-            VirtualKeyCode::Key1 => items.get(0).cloned(),
-            VirtualKeyCode::Key2 => items.get(1).cloned(),
-            VirtualKeyCode::Key3 => items.get(2).cloned(),
-            VirtualKeyCode::Key4 => items.get(3).cloned(),
-            VirtualKeyCode::Key5 => items.get(4).cloned(),
-            VirtualKeyCode::Key6 => items.get(5).cloned(),
-            VirtualKeyCode::Key7 => items.get(6).cloned(),
-            VirtualKeyCode::Key8 => items.get(7).cloned(),
-            VirtualKeyCode::Key9 => items.get(8).cloned(),
-            _ => None,
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuCancel) => CharacterCreationResult::Updated {
+            step: CreationStep::Attributes,
+            selection: ATTRIBUTE_NAMES.len(),
         },
+        Some(Action::MenuSelect) => CharacterCreationResult::Finished,
+        _ => CharacterCreationResult::NoResponse,
+    }
+}
+
+fn describe_kit(class: Class) -> String {
+    let mut kit: Vec<&str> = class.kit().to_vec();
+    kit.extend_from_slice(class.spells());
+    kit.join(", ")
+}
+
+/// Turns the finished sheet into the player: stats, attributes, and the class's starting kit.
+pub fn finish(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let class = {
+        let sheet = ecs.fetch::<CharacterSheet>();
+        ecs.write_storage::<Name>()
+            .insert(
+                player_entity,
+                Name {
+                    name: sheet.name.clone(),
+                },
+            )
+            .expect("Unable to insert name");
+        ecs.write_storage::<CombatStats>()
+            .insert(player_entity, sheet.combat_stats())
+            .expect("Unable to insert combat stats");
+        ecs.write_storage::<Mana>()
+            .insert(
+                player_entity,
+                Mana {
+                    max_mana: sheet.max_mana(),
+                    mana: sheet.max_mana(),
+                },
+            )
+            .expect("Unable to insert mana");
+        ecs.write_storage::<Skills>()
+            .insert(player_entity, sheet.skills())
+            .expect("Unable to insert skills");
+        ecs.write_storage::<Attributes>()
+            .insert(player_entity, sheet.attributes.clone())
+            .expect("Unable to insert attributes");
+        sheet.class
+    };
+
+    spawner::starting_kit(ecs, player_entity, class.kit(), class.spells());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRENGTH: usize = 0;
+    const AGILITY: usize = 1;
+    const TOUGHNESS: usize = 2;
+
+    #[test]
+    fn attributes_stop_at_the_cap() {
+        let mut sheet = CharacterSheet::default();
+        for _ in 0..ATTRIBUTE_POINTS {
+            sheet.raise_attribute(STRENGTH);
+        }
+        assert_eq!(sheet.attribute(STRENGTH), MAX_ATTRIBUTE);
+        assert_eq!(
+            sheet.points_left(),
+            ATTRIBUTE_POINTS - (MAX_ATTRIBUTE - BASE_ATTRIBUTE)
+        );
+    }
+
+    #[test]
+    fn points_run_out() {
+        let mut sheet = CharacterSheet::default();
+        for _ in 0..ATTRIBUTE_POINTS {
+            sheet.raise_attribute(STRENGTH);
+            sheet.raise_attribute(AGILITY);
+        }
+        assert_eq!(sheet.points_left(), 0);
+        sheet.raise_attribute(TOUGHNESS);
+        assert_eq!(sheet.attribute(TOUGHNESS), BASE_ATTRIBUTE);
+    }
+
+    #[test]
+    fn lowering_refunds_points_but_not_below_the_base() {
+        let mut sheet = CharacterSheet::default();
+        sheet.lower_attribute(AGILITY);
+        assert_eq!(sheet.attribute(AGILITY), BASE_ATTRIBUTE);
+        assert_eq!(sheet.points_left(), ATTRIBUTE_POINTS);
+
+        sheet.raise_attribute(AGILITY);
+        sheet.lower_attribute(AGILITY);
+        assert_eq!(sheet.points_left(), ATTRIBUTE_POINTS);
+    }
+
+    #[test]
+    fn every_two_points_give_a_bonus() {
+        let mut sheet = CharacterSheet::default();
+        sheet.raise_attribute(STRENGTH);
+        assert_eq!(sheet.combat_stats().power, Class::Scout.base_stats().power);
+        sheet.raise_attribute(STRENGTH);
+        assert_eq!(
+            sheet.combat_stats().power,
+            Class::Scout.base_stats().power + 1
+        );
     }
 }
@@ -0,0 +1,117 @@
+use super::{
+    components,
+    map::{self, Map},
+};
+use specs::prelude::*;
+
+const TURNS_PER_HOUR: i32 = 20;
+const MINUTES_PER_DAY: i32 = 24 * 60;
+// Every run starts on a bright morning
+const START_HOUR: i32 = 8;
+const SUNRISE_HOUR: i32 = 6;
+const SUNSET_HOUR: i32 = 19;
+// Starlight is enough to make out the dunes, barely
+const NIGHT_LIGHT: f32 = 0.2;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn description(&self) -> &'static str {
+        match self {
+            TimeOfDay::Dawn => "Dawn",
+            TimeOfDay::Day => "Day",
+            TimeOfDay::Dusk => "Dusk",
+            TimeOfDay::Night => "Night",
+        }
+    }
+}
+
+fn minute_of_day(turns: i32) -> i32 {
+    (START_HOUR * 60 + turns * 60 / TURNS_PER_HOUR) % MINUTES_PER_DAY
+}
+
+/// Dawn and dusk each last the hour the sun takes to come up or go down.
+pub fn time_of_day(turns: i32) -> TimeOfDay {
+    match minute_of_day(turns) / 60 {
+        SUNRISE_HOUR => TimeOfDay::Dawn,
+        SUNSET_HOUR => TimeOfDay::Dusk,
+        hour if hour > SUNRISE_HOUR && hour < SUNSET_HOUR => TimeOfDay::Day,
+        _ => TimeOfDay::Night,
+    }
+}
+
+pub fn clock_face(turns: i32) -> String {
+    let minute = minute_of_day(turns);
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// How bright the open sky is, fading between night and day through dawn and dusk.
+pub fn sky_light(turns: i32) -> f32 {
+    let through_the_hour = (minute_of_day(turns) % 60) as f32 / 60.0;
+    match time_of_day(turns) {
+        TimeOfDay::Day => map::DAYLIGHT,
+        TimeOfDay::Night => NIGHT_LIGHT,
+        TimeOfDay::Dawn => NIGHT_LIGHT + (map::DAYLIGHT - NIGHT_LIGHT) * through_the_hour,
+        TimeOfDay::Dusk => map::DAYLIGHT - (map::DAYLIGHT - NIGHT_LIGHT) * through_the_hour,
+    }
+}
+
+pub fn current_turn(ecs: &World) -> i32 {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.read_storage::<components::RunStats>()
+        .get(player_entity)
+        .map_or(0, |stats| stats.turns)
+}
+
+/// Night only counts on the surface; underground it's the same dark at any hour.
+pub fn is_night_outside(ecs: &World) -> bool {
+    ecs.fetch::<Map>().surface && time_of_day(current_turn(ecs)) == TimeOfDay::Night
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three minutes pass each turn, starting from 08:00
+    const DUSK_TURN: i32 = 220;
+    const NIGHT_TURN: i32 = 240;
+    const DAWN_TURN: i32 = 440;
+    const MORNING_TURN: i32 = 460;
+
+    #[test]
+    fn the_day_turns_through_dusk_and_dawn() {
+        assert!(time_of_day(0) == TimeOfDay::Day);
+        assert!(time_of_day(DUSK_TURN - 1) == TimeOfDay::Day);
+        assert!(time_of_day(DUSK_TURN) == TimeOfDay::Dusk);
+        assert!(time_of_day(NIGHT_TURN) == TimeOfDay::Night);
+        assert!(time_of_day(DAWN_TURN - 1) == TimeOfDay::Night);
+        assert!(time_of_day(DAWN_TURN) == TimeOfDay::Dawn);
+        assert!(time_of_day(MORNING_TURN) == TimeOfDay::Day);
+    }
+
+    #[test]
+    fn the_clock_wraps_at_midnight() {
+        assert_eq!(clock_face(0), "08:00");
+        assert_eq!(clock_face(NIGHT_TURN), "20:00");
+        assert_eq!(clock_face(DAWN_TURN), "06:00");
+        assert_eq!(clock_face(TURNS_PER_HOUR * 24), "08:00");
+    }
+
+    #[test]
+    fn the_sky_fades_between_day_and_night() {
+        assert_eq!(sky_light(0), map::DAYLIGHT);
+        assert_eq!(sky_light(NIGHT_TURN), NIGHT_LIGHT);
+        assert_eq!(sky_light(DUSK_TURN), map::DAYLIGHT);
+        assert_eq!(sky_light(DAWN_TURN), NIGHT_LIGHT);
+
+        let halfway = (map::DAYLIGHT + NIGHT_LIGHT) / 2.0;
+        assert!((sky_light(DUSK_TURN + TURNS_PER_HOUR / 2) - halfway).abs() < 0.001);
+        assert!((sky_light(DAWN_TURN + TURNS_PER_HOUR / 2) - halfway).abs() < 0.001);
+    }
+}
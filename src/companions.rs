@@ -0,0 +1,133 @@
+use super::{
+    components::{self, Companion, CompanionOrder, CompanionTarget},
+    map::Map,
+    spawner, GameLog, TileType,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+// Any more and they'd spend all their time blocking each other in corridors
+const MAX_COMPANIONS: usize = 2;
+
+// How far away the player can point out something to attack
+pub const COMMAND_RANGE: i32 = 8;
+
+pub fn has_companions(ecs: &World) -> bool {
+    ecs.read_storage::<Companion>().join().next().is_some()
+}
+
+pub fn can_recruit(ecs: &World) -> bool {
+    ecs.read_storage::<Companion>().join().count() < MAX_COMPANIONS
+}
+
+pub fn recruit(ecs: &mut World, npc: Entity) {
+    spawner::make_companion(ecs, npc);
+    let name = ecs
+        .read_storage::<components::Name>()
+        .get(npc)
+        .map_or("stranger".to_string(), |n| n.name.clone());
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("The {} joins you.", name));
+}
+
+/// Gives every companion the same order. Only attack orders come with a target.
+pub fn give_orders(ecs: &mut World, order: CompanionOrder, target: Option<Entity>) {
+    {
+        let entities = ecs.entities();
+        let mut companions = ecs.write_storage::<Companion>();
+        let mut targets = ecs.write_storage::<CompanionTarget>();
+        for (entity, companion) in (&entities, &mut companions).join() {
+            companion.order = order;
+            targets.remove(entity);
+            if let Some(target) = target {
+                targets
+                    .insert(entity, CompanionTarget { target })
+                    .expect("Unable to insert companion target");
+            }
+        }
+    }
+
+    let message = match (order, target) {
+        (CompanionOrder::Attack, Some(target)) => {
+            let name = ecs
+                .read_storage::<components::Name>()
+                .get(target)
+                .map_or("enemy".to_string(), |n| n.name.clone());
+            format!("Your companions go after the {}.", name)
+        }
+        (CompanionOrder::Stay, _) => "Your companions hold their ground.".to_string(),
+        _ => "Your companions fall in behind you.".to_string(),
+    };
+    ecs.fetch_mut::<GameLog>().entries.push(message);
+}
+
+/// Sends the companions after whatever hostile is standing at the chosen spot.
+pub fn order_attack(ecs: &mut World, target_pos: Point) {
+    let target = {
+        let map = ecs.fetch::<Map>();
+        let monsters = ecs.read_storage::<components::Monster>();
+        let idx = map.xy_idx(target_pos.x, target_pos.y);
+        map.tile_content[idx]
+            .iter()
+            .find(|entity| monsters.get(**entity).is_some())
+            .copied()
+    };
+
+    match target {
+        Some(target) => give_orders(ecs, CompanionOrder::Attack, Some(target)),
+        None => ecs
+            .fetch_mut::<GameLog>()
+            .entries
+            .push("There's nothing there to attack.".to_string()),
+    }
+}
+
+/// Brings the companions down the stairs after the player, standing wherever there's room
+/// around them. Whatever they were told to do on the last level no longer applies.
+pub fn follow_player(ecs: &mut World) {
+    let player_pos = *ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let mut companions = ecs.write_storage::<Companion>();
+    let mut targets = ecs.write_storage::<CompanionTarget>();
+    let mut positions = ecs.write_storage::<components::Position>();
+    let mut viewsheds = ecs.write_storage::<components::Viewshed>();
+    let blockers = ecs.read_storage::<components::BlocksTile>();
+
+    let mut occupied: Vec<Point> = (&entities, &positions, &blockers)
+        .join()
+        .filter(|(entity, _, _)| companions.get(*entity).is_none())
+        .map(|(_, pos, _)| Point::new(pos.x, pos.y))
+        .collect();
+    occupied.push(player_pos);
+
+    for (entity, companion, pos, viewshed) in
+        (&entities, &mut companions, &mut positions, &mut viewsheds).join()
+    {
+        companion.order = CompanionOrder::Follow;
+        targets.remove(entity);
+
+        let spot = (1..=5)
+            .flat_map(|radius| {
+                (-radius..=radius).flat_map(move |dy| {
+                    (-radius..=radius)
+                        .map(move |dx| Point::new(player_pos.x + dx, player_pos.y + dy))
+                })
+            })
+            .find(|spot| {
+                spot.x > 0
+                    && spot.x < map.width - 1
+                    && spot.y > 0
+                    && spot.y < map.height - 1
+                    && map.tiles[map.xy_idx(spot.x, spot.y)] != TileType::Wall
+                    && !occupied.contains(spot)
+            })
+            .unwrap_or(player_pos);
+
+        pos.x = spot.x;
+        pos.y = spot.y;
+        occupied.push(spot);
+        viewshed.dirty = true;
+    }
+}
@@ -5,6 +5,7 @@ use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{ConvertSaveload, Marker};
 use specs_derive::*;
+use std::collections::BTreeMap;
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Player {}
@@ -145,12 +146,6 @@ pub struct Invisible {
     pub turns: Option<i32>,
 }
 
-#[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct WantsBeInvisible {
-    pub entity: Entity,
-    pub turns: i32,
-}
-
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct AppliesInvisiblity {
     pub turns: i32,
@@ -220,9 +215,88 @@ pub struct HungerClock {
     pub total_nutrition: i32,
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+pub enum HydrationState {
+    Quenched,
+    Normal,
+    Thirsty,
+    Parched,
+}
+
+// How much water the body has left. The desert sun takes its share by day.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct HydrationClock {
+    pub state: HydrationState,
+    pub water: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct WaterFlask {
+    pub sips: i32,
+    pub max_sips: i32,
+}
+
+// A well or cistern. Wells never run dry; cisterns only hold so many drinks.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct WaterSource {
+    pub drinks: Option<i32>,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToDrink {
+    pub source: Entity,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum FoodKind {
+    Meat,
+    Plant,
+}
+
 #[derive(Component, ConvertSaveload, Clone, Debug)]
 pub struct Food {
     pub nutrition: i32,
+    pub kind: FoodKind,
+}
+
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum DietType {
+    Herbivore,
+    Carnivore,
+    Omnivore,
+}
+
+// What a creature is willing to eat when it goes looking for food
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Diet {
+    pub diet_type: DietType,
+}
+
+impl Diet {
+    pub fn can_eat(&self, food: &Food) -> bool {
+        match self.diet_type {
+            DietType::Herbivore => food.kind == FoodKind::Plant,
+            DietType::Carnivore => food.kind == FoodKind::Meat,
+            DietType::Omnivore => true,
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToEat {
+    pub food: Entity,
+}
+
+// Food that has a percentage chance of making whoever eats it sick
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Poisonous {
+    pub chance: i32,
+    pub turns: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct FoodPoisoning {
+    pub turns: i32,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -233,3 +307,175 @@ pub struct EntityMoved {}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct SingleActivation {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Mana {
+    pub max_mana: i32,
+    pub mana: i32,
+}
+
+// A spell is an entity carrying the usual effect components, plus what it costs to cast. Until
+// it is learned it sits in the backpack like any other item.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Spell {
+    pub mana_cost: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct KnownSpell {
+    pub owner: Entity,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToLearnSpell {
+    pub spell: Entity,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToCastSpell {
+    pub spell: Entity,
+    pub target: Option<Point>,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct TeleportsRandomly {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Trap {
+    pub spot_difficulty: i32,
+    pub disarm_difficulty: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Skills {
+    pub perception: i32,
+    pub disarm: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SoundsAlarm {
+    pub radius: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Alerted {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct DropsALevel {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Entangles {
+    pub turns: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Entangled {
+    pub turns: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct AutoTravel {
+    pub path: Vec<usize>,
+    pub last_hp: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct AutoExplore {
+    pub last_hp: i32,
+    pub last_hunger: Option<HungerState>,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Attributes {
+    pub strength: i32,
+    pub agility: i32,
+    pub toughness: i32,
+    pub intellect: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Weight {
+    pub kg: f32,
+}
+
+// Energy builds up by `speed` every turn, and acting spends it
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Initiative {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MyTurn {}
+
+// How the run has gone so far, kept on the player for the morgue file and the score table
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RunStats {
+    pub seed: u64,
+    pub turns: i32,
+    pub kills: BTreeMap<String, i32>,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub items_used: i32,
+    pub meals_eaten: i32,
+    pub tiles_explored: i32,
+    pub deepest_depth: i32,
+}
+
+// Someone to talk to rather than fight. What they say comes from the dialogue data, by name.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Npc {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestProgress {
+    pub name: String,
+    // For kill quests, how many had already been killed when the quest was taken
+    pub baseline: i32,
+    pub turned_in: bool,
+    pub announced: bool,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QuestLog {
+    pub quests: Vec<QuestProgress>,
+}
+
+// Lights up the area around it, or around whoever is carrying it
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct LightSource {
+    pub color: RGB,
+    pub range: i32,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum CompanionOrder {
+    Follow,
+    Stay,
+    Attack,
+}
+
+// Fights on the player's side, and goes wherever they go
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Companion {
+    pub order: CompanionOrder,
+}
+
+// Who a companion was ordered to attack
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct CompanionTarget {
+    pub target: Entity,
+}
+
+// Carrying this out of the boss's lair wins the game
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Artifact {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct LastDamagedBy {
+    pub cause: String,
+    pub source: Option<String>,
+    pub by_player: bool,
+}
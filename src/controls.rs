@@ -0,0 +1,370 @@
+use rltk::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const CONTROLS_FILE: &str = "./controls.json";
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    Wait,
+    PickUp,
+    Inventory,
+    Drop,
+    RemoveItem,
+    Descend,
+    SearchDisarm,
+    TravelToStairs,
+    AutoExplore,
+    ShowControls,
+    QuestLog,
+    CommandCompanions,
+    SaveAndQuit,
+    Spell1,
+    Spell2,
+    Spell3,
+    Spell4,
+    Spell5,
+    Spell6,
+    Spell7,
+    Spell8,
+    Spell9,
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+    MenuCancel,
+    MenuClear,
+}
+
+/// Keys only have to be unique within a group, so the arrow keys can both move the player and
+/// move a menu cursor.
+#[derive(PartialEq, Copy, Clone)]
+pub enum ActionGroup {
+    Game,
+    Menu,
+}
+
+impl Action {
+    pub const ALL: [Action; 35] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+        Action::MoveNorthWest,
+        Action::MoveNorthEast,
+        Action::MoveSouthWest,
+        Action::MoveSouthEast,
+        Action::Wait,
+        Action::PickUp,
+        Action::Inventory,
+        Action::Drop,
+        Action::RemoveItem,
+        Action::Descend,
+        Action::SearchDisarm,
+        Action::TravelToStairs,
+        Action::AutoExplore,
+        Action::ShowControls,
+        Action::QuestLog,
+        Action::CommandCompanions,
+        Action::SaveAndQuit,
+        Action::Spell1,
+        Action::Spell2,
+        Action::Spell3,
+        Action::Spell4,
+        Action::Spell5,
+        Action::Spell6,
+        Action::Spell7,
+        Action::Spell8,
+        Action::Spell9,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuSelect,
+        Action::MenuCancel,
+        Action::MenuClear,
+    ];
+
+    pub fn group(&self) -> ActionGroup {
+        match self {
+            Action::MenuUp
+            | Action::MenuDown
+            | Action::MenuSelect
+            | Action::MenuCancel
+            | Action::MenuClear => ActionGroup::Menu,
+            _ => ActionGroup::Game,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::MoveNorth => "Move north",
+            Action::MoveSouth => "Move south",
+            Action::MoveWest => "Move west",
+            Action::MoveEast => "Move east",
+            Action::MoveNorthWest => "Move north-west",
+            Action::MoveNorthEast => "Move north-east",
+            Action::MoveSouthWest => "Move south-west",
+            Action::MoveSouthEast => "Move south-east",
+            Action::Wait => "Wait a turn",
+            Action::PickUp => "Pick up",
+            Action::Inventory => "Inventory",
+            Action::Drop => "Drop item",
+            Action::RemoveItem => "Remove item",
+            Action::Descend => "Go down stairs",
+            Action::SearchDisarm => "Search / disarm",
+            Action::TravelToStairs => "Travel to stairs",
+            Action::AutoExplore => "Auto-explore",
+            Action::ShowControls => "Controls",
+            Action::QuestLog => "Quest log",
+            Action::CommandCompanions => "Command companions",
+            Action::SaveAndQuit => "Save and quit",
+            Action::Spell1 => "Cast spell 1",
+            Action::Spell2 => "Cast spell 2",
+            Action::Spell3 => "Cast spell 3",
+            Action::Spell4 => "Cast spell 4",
+            Action::Spell5 => "Cast spell 5",
+            Action::Spell6 => "Cast spell 6",
+            Action::Spell7 => "Cast spell 7",
+            Action::Spell8 => "Cast spell 8",
+            Action::Spell9 => "Cast spell 9",
+            Action::MenuUp => "Menu up",
+            Action::MenuDown => "Menu down",
+            Action::MenuSelect => "Menu select",
+            Action::MenuCancel => "Menu back",
+            Action::MenuClear => "Clear binding",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<VirtualKeyCode> {
+        use VirtualKeyCode::*;
+        match self {
+            Action::MoveNorth => vec![Up, Numpad8, K],
+            Action::MoveSouth => vec![Down, Numpad2, J],
+            Action::MoveWest => vec![Left, Numpad4, H],
+            Action::MoveEast => vec![Right, Numpad6, L],
+            Action::MoveNorthWest => vec![Numpad7, Y],
+            Action::MoveNorthEast => vec![Numpad9, U],
+            Action::MoveSouthWest => vec![Numpad1, B],
+            Action::MoveSouthEast => vec![Numpad3, N],
+            Action::Wait => vec![Space, Numpad5],
+            Action::PickUp => vec![G],
+            Action::Inventory => vec![I],
+            Action::Drop => vec![D],
+            Action::RemoveItem => vec![R],
+            Action::Descend => vec![Period],
+            Action::SearchDisarm => vec![S],
+            Action::TravelToStairs => vec![T],
+            Action::AutoExplore => vec![X],
+            Action::ShowControls => vec![F1],
+            Action::QuestLog => vec![Q],
+            Action::CommandCompanions => vec![C],
+            Action::SaveAndQuit => vec![Escape],
+            Action::Spell1 => vec![Key1],
+            Action::Spell2 => vec![Key2],
+            Action::Spell3 => vec![Key3],
+            Action::Spell4 => vec![Key4],
+            Action::Spell5 => vec![Key5],
+            Action::Spell6 => vec![Key6],
+            Action::Spell7 => vec![Key7],
+            Action::Spell8 => vec![Key8],
+            Action::Spell9 => vec![Key9],
+            Action::MenuUp => vec![Up, Numpad8, K],
+            Action::MenuDown => vec![Down, Numpad2, J],
+            Action::MenuSelect => vec![Return],
+            Action::MenuCancel => vec![Escape],
+            Action::MenuClear => vec![Back],
+        }
+    }
+}
+
+/// Maps each action to the keys that trigger it. Loaded from `controls.json` at startup, and
+/// written back whenever the player rebinds something.
+pub struct Controls {
+    bindings: BTreeMap<Action, Vec<VirtualKeyCode>>,
+}
+
+impl Controls {
+    pub fn defaults() -> Controls {
+        Controls {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_keys()))
+                .collect(),
+        }
+    }
+
+    pub fn load() -> Controls {
+        if !Path::new(CONTROLS_FILE).exists() {
+            let controls = Controls::defaults();
+            controls.save();
+            return controls;
+        }
+
+        let data = fs::read_to_string(CONTROLS_FILE).expect("Unable to read controls file");
+        let saved: BTreeMap<Action, Vec<String>> = match serde_json::from_str(&data) {
+            Ok(saved) => saved,
+            Err(e) => {
+                rltk::console::log(format!("Ignoring broken {}: {}", CONTROLS_FILE, e));
+                return Controls::defaults();
+            }
+        };
+
+        let mut controls = Controls {
+            bindings: BTreeMap::new(),
+        };
+        for (action, key_names) in saved.iter() {
+            controls.clear(*action);
+            for key_name in key_names.iter() {
+                match key_from_name(key_name) {
+                    None => rltk::console::log(format!("Unknown key {} in controls", key_name)),
+                    Some(key) => {
+                        if let Err(other) = controls.bind(*action, key) {
+                            rltk::console::log(format!(
+                                "{} is bound to both {:?} and {:?}, keeping {:?}",
+                                key_name, other, action, other
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Actions added since the file was written get whichever of their default keys are free
+        for action in Action::ALL.iter() {
+            if !saved.contains_key(action) {
+                controls.clear(*action);
+                for key in action.default_keys() {
+                    let _ = controls.bind(*action, key);
+                }
+            }
+        }
+
+        controls
+    }
+
+    pub fn save(&self) {
+        let saved: BTreeMap<Action, Vec<String>> = self
+            .bindings
+            .iter()
+            .map(|(action, keys)| (*action, keys.iter().map(|k| key_name(*k)).collect()))
+            .collect();
+        let data = serde_json::to_string_pretty(&saved).expect("Unable to serialize controls");
+        if let Err(e) = fs::write(CONTROLS_FILE, data) {
+            rltk::console::log(format!("Unable to write {}: {}", CONTROLS_FILE, e));
+        }
+    }
+
+    pub fn action_for(&self, key: VirtualKeyCode, group: ActionGroup) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(action, keys)| action.group() == group && keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// Which action a key press means inside menus, if any.
+    pub fn menu_action(&self, key: Option<VirtualKeyCode>) -> Option<Action> {
+        key.and_then(|key| self.action_for(key, ActionGroup::Menu))
+    }
+
+    /// Adds a key to an action, failing with the action that already uses it.
+    pub fn bind(&mut self, action: Action, key: VirtualKeyCode) -> Result<(), Action> {
+        match self.action_for(key, action.group()) {
+            Some(other) if other == action => Ok(()),
+            Some(other) => Err(other),
+            None => {
+                self.bindings.entry(action).or_default().push(key);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.bindings.insert(action, Vec::new());
+    }
+
+    pub fn describe_keys(&self, action: Action) -> String {
+        match self.bindings.get(&action) {
+            Some(keys) if !keys.is_empty() => keys
+                .iter()
+                .map(|k| key_name(*k))
+                .collect::<Vec<String>>()
+                .join(", "),
+            _ => "(unbound)".to_string(),
+        }
+    }
+}
+
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .find(|k| key_name(**k) == name)
+        .copied()
+}
+
+const BINDABLE_KEYS: [VirtualKeyCode; 84] = {
+    use VirtualKeyCode::*;
+    [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Key0, Key1,
+        Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, F1, F2, F3, F4, F5, F6, F7, F8, F9,
+        F10, F11, F12, Up, Down, Left, Right, Home, End, PageUp, PageDown, Insert, Delete, Escape,
+        Return, Space, Tab, Back, Period, Comma, Slash, Semicolon, Apostrophe, Backslash, Minus,
+        Equals, LBracket, RBracket, Grave,
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binding_a_taken_key_names_the_action_holding_it() {
+        let mut controls = Controls::defaults();
+        assert_eq!(
+            controls.bind(Action::Wait, VirtualKeyCode::K),
+            Err(Action::MoveNorth)
+        );
+        assert_eq!(controls.describe_keys(Action::Wait), "Space, Numpad5");
+    }
+
+    #[test]
+    fn rebinding_a_key_to_its_own_action_is_harmless() {
+        let mut controls = Controls::defaults();
+        assert_eq!(controls.bind(Action::PickUp, VirtualKeyCode::G), Ok(()));
+        assert_eq!(controls.describe_keys(Action::PickUp), "G");
+    }
+
+    #[test]
+    fn keys_only_clash_within_their_group() {
+        let mut controls = Controls::defaults();
+        assert_eq!(controls.bind(Action::MenuSelect, VirtualKeyCode::G), Ok(()));
+        assert_eq!(
+            controls.action_for(VirtualKeyCode::G, ActionGroup::Game),
+            Some(Action::PickUp)
+        );
+        assert_eq!(
+            controls.menu_action(Some(VirtualKeyCode::G)),
+            Some(Action::MenuSelect)
+        );
+    }
+
+    #[test]
+    fn cleared_keys_are_free_to_bind() {
+        let mut controls = Controls::defaults();
+        controls.clear(Action::PickUp);
+        assert_eq!(controls.describe_keys(Action::PickUp), "(unbound)");
+        assert_eq!(controls.bind(Action::Wait, VirtualKeyCode::G), Ok(()));
+    }
+}
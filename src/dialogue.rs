@@ -0,0 +1,194 @@
+use super::{
+    companions, components,
+    controls::{Action, ActionGroup, Controls},
+    quests::{self, QuestState},
+};
+use rltk::{Rltk, RGB};
+use serde::Deserialize;
+use specs::prelude::*;
+
+const DIALOGUE_DATA: &str = include_str!("../resources/dialogue.json");
+
+const BOX_X: i32 = 10;
+const BOX_WIDTH: i32 = 60;
+
+#[derive(Deserialize, Clone)]
+pub enum DialogueAction {
+    Goto(usize),
+    AcceptQuest(String),
+    CompleteQuest(String),
+    Recruit,
+    End,
+}
+
+#[derive(Deserialize)]
+pub struct DialogueOption {
+    pub text: String,
+    pub action: DialogueAction,
+}
+
+#[derive(Deserialize)]
+pub struct DialogueNode {
+    pub text: String,
+    pub options: Vec<DialogueOption>,
+}
+
+#[derive(Deserialize)]
+pub struct NpcDialogue {
+    pub npc: String,
+    pub nodes: Vec<DialogueNode>,
+}
+
+/// Every conversation in the game, keyed by the name of the NPC who has it. Read from
+/// `resources/dialogue.json`, which is built into the binary.
+pub struct Dialogues {
+    dialogues: Vec<NpcDialogue>,
+}
+
+impl Dialogues {
+    pub fn load() -> Dialogues {
+        Dialogues {
+            dialogues: serde_json::from_str(DIALOGUE_DATA).expect("Unable to parse dialogue data"),
+        }
+    }
+
+    fn for_npc(&self, npc_name: &str) -> Option<&NpcDialogue> {
+        self.dialogues.iter().find(|d| d.npc == npc_name)
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DialogueResult {
+    NoResponse,
+    Goto { node: usize },
+    Done,
+}
+
+/// Quest options only make sense at the right point in the quest, and nobody joins a full party
+fn is_available(ecs: &World, action: &DialogueAction) -> bool {
+    match action {
+        DialogueAction::Recruit => companions::can_recruit(ecs),
+        DialogueAction::AcceptQuest(quest) => {
+            quests::quest_state(ecs, quest) == QuestState::Available
+        }
+        DialogueAction::CompleteQuest(quest) => {
+            quests::quest_state(ecs, quest) == QuestState::Ready
+        }
+        _ => true,
+    }
+}
+
+/// The node's text, wrapped to fit the box, and whichever of its options are on offer right now.
+fn read_node(
+    ecs: &World,
+    npc_name: &str,
+    node: usize,
+) -> Option<(Vec<String>, Vec<(String, DialogueAction)>)> {
+    let dialogues = ecs.fetch::<Dialogues>();
+    let node = dialogues
+        .for_npc(npc_name)
+        .and_then(|dialogue| dialogue.nodes.get(node))?;
+    let options = node
+        .options
+        .iter()
+        .filter(|option| is_available(ecs, &option.action))
+        .map(|option| (option.text.clone(), option.action.clone()))
+        .collect();
+    Some((wrap(&node.text, (BOX_WIDTH - 4) as usize), options))
+}
+
+pub fn talk(ecs: &mut World, ctx: &mut Rltk, npc: Entity, node: usize) -> DialogueResult {
+    let npc_name = match ecs.read_storage::<components::Name>().get(npc) {
+        Some(name) => name.name.clone(),
+        None => return DialogueResult::Done,
+    };
+    // Read up front, so the quest helpers below are free to change the world
+    let (lines, options) = match read_node(ecs, &npc_name, node) {
+        Some(contents) => contents,
+        None => return DialogueResult::Done,
+    };
+
+    let height = (lines.len() + options.len() + 5) as i32;
+    let top = 25 - height / 2;
+    ctx.draw_box(
+        BOX_X,
+        top,
+        BOX_WIDTH,
+        height,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        BOX_X + 3,
+        top,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        &npc_name,
+    );
+
+    let mut y = top + 2;
+    for line in lines.iter() {
+        ctx.print(BOX_X + 2, y, line);
+        y += 1;
+    }
+    y += 1;
+    for (letter, (text, _)) in options.iter().enumerate() {
+        ctx.print_color(
+            BOX_X + 2,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            format!("({})", (b'a' + letter as u8) as char),
+        );
+        ctx.print(BOX_X + 6, y, text);
+        y += 1;
+    }
+
+    let key = match ctx.key {
+        None => return DialogueResult::NoResponse,
+        Some(key) => key,
+    };
+    if ecs.fetch::<Controls>().action_for(key, ActionGroup::Menu) == Some(Action::MenuCancel) {
+        return DialogueResult::Done;
+    }
+    let selection = rltk::letter_to_option(key);
+    if selection < 0 || selection as usize >= options.len() {
+        return DialogueResult::NoResponse;
+    }
+
+    match &options[selection as usize].1 {
+        DialogueAction::Goto(next) => DialogueResult::Goto { node: *next },
+        DialogueAction::End => DialogueResult::Done,
+        DialogueAction::AcceptQuest(quest) => {
+            quests::accept(ecs, quest);
+            DialogueResult::Goto { node }
+        }
+        DialogueAction::CompleteQuest(quest) => {
+            quests::turn_in(ecs, quest);
+            DialogueResult::Goto { node }
+        }
+        DialogueAction::Recruit => {
+            companions::recruit(ecs, npc);
+            DialogueResult::Done
+        }
+    }
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,132 @@
+use super::{
+    entity_name, log, player_should_know, EffectQueue, EffectSpawner, EffectType, Targets,
+};
+use crate::components;
+use rltk::RGB;
+use specs::prelude::*;
+
+pub fn inflict_damage(ecs: &mut World, effect: &EffectSpawner, target: Entity, amount: i32) {
+    if ecs
+        .read_storage::<components::CombatStats>()
+        .get(target)
+        .is_none()
+    {
+        return;
+    }
+
+    components::SufferDamage::new_damage(
+        &mut ecs.write_storage::<components::SufferDamage>(),
+        target,
+        amount,
+    );
+    let player_entity = *ecs.fetch::<Entity>();
+    let cause = match (effect.creator, effect.source) {
+        (Some(creator), _) => entity_name(ecs, creator),
+        (None, Some(source)) => entity_name(ecs, source),
+        (None, None) => "unknown causes".to_string(),
+    };
+    let source = effect.source.map(|source| entity_name(ecs, source));
+    ecs.write_storage::<components::LastDamagedBy>()
+        .insert(
+            target,
+            components::LastDamagedBy {
+                cause,
+                source,
+                by_player: effect.creator == Some(player_entity),
+            },
+        )
+        .expect("Unable to insert damage cause");
+    if effect.creator == Some(player_entity) && target != player_entity {
+        if let Some(stats) = ecs
+            .write_storage::<components::RunStats>()
+            .get_mut(player_entity)
+        {
+            stats.damage_dealt += amount;
+        }
+    }
+    ecs.write_resource::<EffectQueue>().add_effect(
+        None,
+        None,
+        EffectType::Particle {
+            glyph: rltk::to_cp437('‼'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            lifetime: 200.0,
+        },
+        Targets::Single { target },
+    );
+
+    if player_should_know(ecs, effect, target) {
+        let target_name = entity_name(ecs, target);
+        let entry = match (effect.creator, effect.source) {
+            (Some(creator), Some(source)) if creator == player_entity => format!(
+                "You use {} on {}, inflicting {} hp.",
+                entity_name(ecs, source),
+                target_name,
+                amount
+            ),
+            (Some(creator), Some(source)) => format!(
+                "The {} uses {} on {}, inflicting {} hp.",
+                entity_name(ecs, creator),
+                entity_name(ecs, source),
+                target_name,
+                amount
+            ),
+            (Some(creator), None) => format!(
+                "{} hits {}, for {} hp.",
+                entity_name(ecs, creator),
+                target_name,
+                amount
+            ),
+            (None, Some(source)) => format!(
+                "The {} hits {}, for {} hp.",
+                entity_name(ecs, source),
+                target_name,
+                amount
+            ),
+            (None, None) => format!("{} takes {} damage.", target_name, amount),
+        };
+        log(ecs, entry);
+    }
+}
+
+pub fn heal_damage(ecs: &mut World, effect: &EffectSpawner, target: Entity, amount: i32) {
+    {
+        let mut combat_stats = ecs.write_storage::<components::CombatStats>();
+        match combat_stats.get_mut(target) {
+            None => return,
+            Some(stats) => stats.hp = i32::min(stats.max_hp, stats.hp + amount),
+        }
+    }
+
+    ecs.write_resource::<EffectQueue>().add_effect(
+        None,
+        None,
+        EffectType::Particle {
+            glyph: rltk::to_cp437('♥'),
+            fg: RGB::named(rltk::GREEN),
+            bg: RGB::named(rltk::BLACK),
+            lifetime: 200.0,
+        },
+        Targets::Single { target },
+    );
+
+    if player_should_know(ecs, effect, target) {
+        let player_entity = *ecs.fetch::<Entity>();
+        let entry = match (effect.creator, effect.source) {
+            (Some(creator), Some(source)) if creator == player_entity => format!(
+                "You use the {}, healing {} hp.",
+                entity_name(ecs, source),
+                amount
+            ),
+            (Some(creator), Some(source)) => format!(
+                "The {} uses the {}, healing {} hp.",
+                entity_name(ecs, creator),
+                entity_name(ecs, source),
+                amount
+            ),
+            _ => format!("{} is healed for {} hp.", entity_name(ecs, target), amount),
+        };
+        log(ecs, entry);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{components, Map};
+use rltk::RGB;
+use specs::prelude::*;
+use std::collections::VecDeque;
+
+mod damage;
+mod movement;
+mod particles;
+mod status;
+mod triggers;
+
+pub enum EffectType {
+    Damage {
+        amount: i32,
+    },
+    Healing {
+        amount: i32,
+    },
+    Confusion {
+        turns: i32,
+    },
+    Invisibility {
+        turns: i32,
+    },
+    TeleportTo {
+        x: i32,
+        y: i32,
+    },
+    Entangle {
+        turns: i32,
+    },
+    Alarm {
+        radius: i32,
+    },
+    FallToNextLevel,
+    Particle {
+        glyph: rltk::FontCharType,
+        fg: RGB,
+        bg: RGB,
+        lifetime: f32,
+    },
+    ItemUse {
+        item: Entity,
+    },
+    TriggerFire {
+        trigger: Entity,
+    },
+}
+
+#[derive(Clone)]
+pub enum Targets {
+    Single { target: Entity },
+    Tile { tile_idx: usize },
+    Area { tiles: Vec<usize> },
+    Caster,
+}
+
+/// A single effect waiting to be applied. The creator is whoever made it happen (if anyone), and
+/// the source is the thing they used to do it, such as an item, spell or trap.
+pub struct EffectSpawner {
+    pub creator: Option<Entity>,
+    pub source: Option<Entity>,
+    pub effect_type: EffectType,
+    pub targets: Targets,
+}
+
+pub struct EffectQueue {
+    queue: VecDeque<EffectSpawner>,
+}
+
+impl EffectQueue {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> EffectQueue {
+        EffectQueue {
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn add_effect(
+        &mut self,
+        creator: Option<Entity>,
+        source: Option<Entity>,
+        effect_type: EffectType,
+        targets: Targets,
+    ) {
+        self.queue.push_back(EffectSpawner {
+            creator,
+            source,
+            effect_type,
+            targets,
+        });
+    }
+}
+
+/// Applies everything in the queue. Effects may enqueue further effects (using an item queues up
+/// its damage, for example), so we keep going until the queue runs dry.
+pub fn run_effects_queue(ecs: &mut World) {
+    loop {
+        let effect = ecs.write_resource::<EffectQueue>().queue.pop_front();
+        match effect {
+            None => break,
+            Some(effect) => target_applicator(ecs, &effect),
+        }
+    }
+}
+
+fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
+    match &effect.effect_type {
+        EffectType::ItemUse { item } => triggers::item_trigger(ecs, effect, *item),
+        EffectType::TriggerFire { trigger } => triggers::trap_trigger(ecs, effect, *trigger),
+        _ => match &effect.targets {
+            Targets::Single { target } => affect_entity(ecs, effect, *target),
+            Targets::Caster => {
+                if let Some(creator) = effect.creator {
+                    affect_entity(ecs, effect, creator);
+                }
+            }
+            Targets::Tile { tile_idx } => affect_tile(ecs, effect, *tile_idx),
+            Targets::Area { tiles } => {
+                for tile_idx in tiles.iter() {
+                    affect_tile(ecs, effect, *tile_idx);
+                }
+            }
+        },
+    }
+}
+
+fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: usize) {
+    if let EffectType::Particle { .. } = effect.effect_type {
+        particles::particle_at_tile(ecs, effect, tile_idx);
+        return;
+    }
+
+    let content: Vec<Entity> = ecs.fetch::<Map>().tile_content[tile_idx].clone();
+    for entity in content {
+        affect_entity(ecs, effect, entity);
+    }
+}
+
+fn affect_entity(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    match &effect.effect_type {
+        EffectType::Damage { amount } => damage::inflict_damage(ecs, effect, target, *amount),
+        EffectType::Healing { amount } => damage::heal_damage(ecs, effect, target, *amount),
+        EffectType::Confusion { turns } => status::add_confusion(ecs, effect, target, *turns),
+        EffectType::Invisibility { turns } => status::add_invisibility(ecs, effect, target, *turns),
+        EffectType::TeleportTo { x, y } => movement::teleport_to(ecs, effect, target, *x, *y),
+        EffectType::Entangle { turns } => status::add_entangle(ecs, effect, target, *turns),
+        EffectType::Alarm { radius } => status::sound_alarm(ecs, target, *radius),
+        EffectType::FallToNextLevel => movement::fall_to_next_level(ecs, target),
+        EffectType::Particle { .. } => {
+            let pos = ecs
+                .read_storage::<components::Position>()
+                .get(target)
+                .cloned();
+            if let Some(pos) = pos {
+                let idx = ecs.fetch::<Map>().xy_idx(pos.x, pos.y);
+                particles::particle_at_tile(ecs, effect, idx);
+            }
+        }
+        EffectType::ItemUse { .. } | EffectType::TriggerFire { .. } => {}
+    }
+}
+
+fn entity_name(ecs: &World, entity: Entity) -> String {
+    ecs.read_storage::<components::Name>()
+        .get(entity)
+        .map_or_else(|| "Something".to_string(), |n| n.name.clone())
+}
+
+/// Effects are only worth mentioning if the player caused them, suffered them, or saw them.
+fn player_should_know(ecs: &World, effect: &EffectSpawner, target: Entity) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    if effect.creator == Some(player_entity) || target == player_entity {
+        return true;
+    }
+
+    let map = ecs.fetch::<Map>();
+    let positions = ecs.read_storage::<components::Position>();
+    match positions.get(target) {
+        None => false,
+        Some(pos) => map.visible_tiles[map.xy_idx(pos.x, pos.y)],
+    }
+}
+
+fn log(ecs: &World, entry: String) {
+    ecs.fetch_mut::<crate::GameLog>().entries.push(entry);
+}
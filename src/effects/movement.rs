@@ -0,0 +1,69 @@
+use super::{entity_name, log, player_should_know, EffectSpawner};
+use crate::{components, Map, RunState, TileType};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+const MAX_TELEPORT_ATTEMPTS: i32 = 1000;
+
+pub fn teleport_to(ecs: &mut World, effect: &EffectSpawner, target: Entity, x: i32, y: i32) {
+    // Mention it while the target is still where the player can see it
+    let should_log = player_should_know(ecs, effect, target);
+
+    {
+        let mut positions = ecs.write_storage::<components::Position>();
+        match positions.get_mut(target) {
+            None => return,
+            Some(pos) => {
+                pos.x = x;
+                pos.y = y;
+            }
+        }
+    }
+
+    if let Some(viewshed) = ecs.write_storage::<components::Viewshed>().get_mut(target) {
+        viewshed.dirty = true;
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    if target == player_entity {
+        *ecs.write_resource::<Point>() = Point::new(x, y);
+        log(
+            ecs,
+            "The world lurches, and you find yourself somewhere else.".to_string(),
+        );
+    } else if should_log {
+        log(
+            ecs,
+            format!(
+                "The {} vanishes in a swirl of sand!",
+                entity_name(ecs, target)
+            ),
+        );
+    }
+}
+
+/// Picks a random open floor tile somewhere on the map, for when we need to send someone away.
+pub fn random_open_tile(map: &Map, rng: &mut RandomNumberGenerator) -> Option<(i32, i32)> {
+    for _attempt in 0..MAX_TELEPORT_ATTEMPTS {
+        let x = rng.roll_dice(1, map.width - 2);
+        let y = rng.roll_dice(1, map.height - 2);
+        let idx = map.xy_idx(x, y);
+        if map.tiles[idx] == TileType::Floor && !map.blocked[idx] {
+            return Some((x, y));
+        }
+    }
+
+    None
+}
+
+pub fn fall_to_next_level(ecs: &mut World, target: Entity) {
+    // Monsters are too wary to tumble in, so only the player ever falls
+    let player_entity = *ecs.fetch::<Entity>();
+    if target == player_entity {
+        log(
+            ecs,
+            "The floor gives way, and you plummet to the level below!".to_string(),
+        );
+        *ecs.write_resource::<RunState>() = RunState::NextLevel;
+    }
+}
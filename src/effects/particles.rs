@@ -0,0 +1,23 @@
+use super::{EffectSpawner, EffectType};
+use crate::{systems::particle_system::ParticleBuilder, Map};
+use specs::prelude::*;
+
+pub fn particle_at_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: usize) {
+    if let EffectType::Particle {
+        glyph,
+        fg,
+        bg,
+        lifetime,
+    } = effect.effect_type
+    {
+        let map_width = ecs.fetch::<Map>().width;
+        ecs.fetch_mut::<ParticleBuilder>().request(
+            tile_idx as i32 % map_width,
+            tile_idx as i32 / map_width,
+            fg,
+            bg,
+            glyph,
+            lifetime,
+        );
+    }
+}
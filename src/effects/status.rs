@@ -0,0 +1,144 @@
+use super::{
+    entity_name, log, player_should_know, EffectQueue, EffectSpawner, EffectType, Targets,
+};
+use crate::components;
+use rltk::RGB;
+use specs::prelude::*;
+
+pub fn add_confusion(ecs: &mut World, effect: &EffectSpawner, target: Entity, turns: i32) {
+    if ecs
+        .read_storage::<components::CombatStats>()
+        .get(target)
+        .is_none()
+    {
+        return;
+    }
+
+    ecs.write_storage::<components::Confusion>()
+        .insert(target, components::Confusion { turns })
+        .expect("Unable to insert status");
+    ecs.write_resource::<EffectQueue>().add_effect(
+        None,
+        None,
+        EffectType::Particle {
+            glyph: rltk::to_cp437('?'),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            lifetime: 400.0,
+        },
+        Targets::Single { target },
+    );
+
+    if player_should_know(ecs, effect, target) {
+        let player_entity = *ecs.fetch::<Entity>();
+        let target_name = entity_name(ecs, target);
+        let entry = match (effect.creator, effect.source) {
+            (Some(creator), Some(source)) if creator == player_entity => format!(
+                "You use {} on {}, confusing them.",
+                entity_name(ecs, source),
+                target_name
+            ),
+            (Some(creator), Some(source)) => format!(
+                "The {} uses {} on {}, confusing them.",
+                entity_name(ecs, creator),
+                entity_name(ecs, source),
+                target_name
+            ),
+            _ => format!("{} is confused.", target_name),
+        };
+        log(ecs, entry);
+    }
+}
+
+pub fn add_invisibility(ecs: &mut World, effect: &EffectSpawner, target: Entity, turns: i32) {
+    if ecs
+        .read_storage::<components::CombatStats>()
+        .get(target)
+        .is_none()
+    {
+        return;
+    }
+
+    // Work out whether anyone saw it happen before they disappear
+    let should_log = player_should_know(ecs, effect, target);
+
+    ecs.write_storage::<components::Invisible>()
+        .insert(target, components::Invisible { turns: Some(turns) })
+        .expect("Failed to insert invisiblity");
+
+    if should_log {
+        let player_entity = *ecs.fetch::<Entity>();
+        let entry = match effect.source {
+            Some(source) if target == player_entity => format!(
+                "You use {}, and become invisible for {} turns.",
+                entity_name(ecs, source),
+                turns
+            ),
+            _ if target == player_entity => {
+                format!("You become invisible for {} turns.", turns)
+            }
+            _ => format!("The {} vanishes from sight!", entity_name(ecs, target)),
+        };
+        log(ecs, entry);
+    }
+}
+
+pub fn add_entangle(ecs: &mut World, effect: &EffectSpawner, target: Entity, turns: i32) {
+    if ecs
+        .read_storage::<components::CombatStats>()
+        .get(target)
+        .is_none()
+    {
+        return;
+    }
+
+    ecs.write_storage::<components::Entangled>()
+        .insert(target, components::Entangled { turns })
+        .expect("Unable to insert status");
+
+    if player_should_know(ecs, effect, target) {
+        let player_entity = *ecs.fetch::<Entity>();
+        let entry = if target == player_entity {
+            "The sand drags at your feet, holding you in place!".to_string()
+        } else {
+            format!(
+                "The {} is caught fast in the sand.",
+                entity_name(ecs, target)
+            )
+        };
+        log(ecs, entry);
+    }
+}
+
+/// Brings every monster within earshot running to wherever the target is standing.
+pub fn sound_alarm(ecs: &mut World, target: Entity, radius: i32) {
+    let origin = match ecs.read_storage::<components::Position>().get(target) {
+        None => return,
+        Some(pos) => rltk::Point::new(pos.x, pos.y),
+    };
+
+    {
+        let entities = ecs.entities();
+        let monsters = ecs.read_storage::<components::Monster>();
+        let positions = ecs.read_storage::<components::Position>();
+        let mut alerted = ecs.write_storage::<components::Alerted>();
+        for (entity, _monster, pos) in (&entities, &monsters, &positions).join() {
+            let distance =
+                rltk::DistanceAlg::Pythagoras.distance2d(origin, rltk::Point::new(pos.x, pos.y));
+            if distance <= radius as f32 {
+                alerted
+                    .insert(
+                        entity,
+                        components::Alerted {
+                            x: origin.x,
+                            y: origin.y,
+                        },
+                    )
+                    .expect("Unable to insert alert");
+            }
+        }
+    }
+
+    // Loud enough to hear from anywhere on the level
+    log(ecs, "A shrill alarm echoes through the halls!".to_string());
+}
@@ -0,0 +1,250 @@
+use super::{entity_name, log, movement, EffectQueue, EffectSpawner, EffectType, Targets};
+use crate::achievements::{GameEvent, GameEvents};
+use crate::{components, Map, RunState};
+use rltk::{RandomNumberGenerator, RGB};
+use specs::prelude::*;
+
+pub fn item_trigger(ecs: &mut World, effect: &EffectSpawner, item: Entity) {
+    apply_source_components(ecs, effect.creator, item, &effect.targets);
+
+    let player_entity = *ecs.fetch::<Entity>();
+    if effect.creator == Some(player_entity) {
+        if let Some(stats) = ecs
+            .write_storage::<components::RunStats>()
+            .get_mut(player_entity)
+        {
+            stats.items_used += 1;
+        }
+        let item_name = entity_name(ecs, item);
+        ecs.write_resource::<GameEvents>()
+            .emit(GameEvent::UsedItem { item: item_name });
+    }
+
+    // Food sticks around until it has actually been eaten
+    let is_consumed = ecs
+        .read_storage::<components::Consumable>()
+        .get(item)
+        .is_some()
+        && ecs.read_storage::<components::Food>().get(item).is_none();
+    if is_consumed {
+        ecs.entities().delete(item).expect("Delete failed");
+    }
+
+    let cooldown = ecs
+        .read_storage::<components::Cooldown>()
+        .get(item)
+        .map(|c| c.turns);
+    if let Some(turns) = cooldown {
+        ecs.write_storage::<components::ActiveCooldown>()
+            .insert(
+                item,
+                components::ActiveCooldown {
+                    turns_remaining: turns,
+                },
+            )
+            .expect("Failed to insert active cooldown");
+    }
+}
+
+pub fn trap_trigger(ecs: &mut World, effect: &EffectSpawner, trap: Entity) {
+    apply_source_components(ecs, None, trap, &effect.targets);
+
+    // Some traps only go off once
+    let is_single_activation = ecs
+        .read_storage::<components::SingleActivation>()
+        .get(trap)
+        .is_some();
+    if is_single_activation {
+        ecs.entities().delete(trap).expect("Unable to delete trap");
+        return;
+    }
+
+    // The rest rearm themselves after a while
+    let cooldown = ecs
+        .read_storage::<components::Cooldown>()
+        .get(trap)
+        .map(|c| c.turns);
+    if let Some(turns) = cooldown {
+        ecs.write_storage::<components::ActiveCooldown>()
+            .insert(
+                trap,
+                components::ActiveCooldown {
+                    turns_remaining: turns,
+                },
+            )
+            .expect("Failed to insert active cooldown");
+    }
+}
+
+/// Turns the effect components on an item, spell or trap into queued effects.
+fn apply_source_components(
+    ecs: &mut World,
+    creator: Option<Entity>,
+    source: Entity,
+    targets: &Targets,
+) {
+    let teleport_destination = if ecs
+        .read_storage::<components::TeleportsRandomly>()
+        .get(source)
+        .is_some()
+    {
+        let map = ecs.fetch::<Map>();
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        movement::random_open_tile(&map, &mut rng)
+    } else {
+        None
+    };
+
+    {
+        let mut queue = ecs.write_resource::<EffectQueue>();
+
+        if let Targets::Area { .. } = targets {
+            if ecs
+                .read_storage::<components::AreaOfEffect>()
+                .get(source)
+                .is_some()
+            {
+                queue.add_effect(
+                    creator,
+                    Some(source),
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('░'),
+                        fg: RGB::named(rltk::ORANGE),
+                        bg: RGB::named(rltk::BLACK),
+                        lifetime: 200.0,
+                    },
+                    targets.clone(),
+                );
+            }
+        }
+
+        if let Some(healing) = ecs
+            .read_storage::<components::ProvidesHealing>()
+            .get(source)
+        {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Healing {
+                    amount: healing.heal_amount,
+                },
+                targets.clone(),
+            );
+        }
+
+        if let Some(damage) = ecs.read_storage::<components::InflictsDamage>().get(source) {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Damage {
+                    amount: damage.damage,
+                },
+                targets.clone(),
+            );
+        }
+
+        if let Some(confusion) = ecs
+            .read_storage::<components::CausesConfusion>()
+            .get(source)
+        {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Confusion {
+                    turns: confusion.turns,
+                },
+                targets.clone(),
+            );
+        }
+
+        if let Some(invisibility) = ecs
+            .read_storage::<components::AppliesInvisiblity>()
+            .get(source)
+        {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Invisibility {
+                    turns: invisibility.turns,
+                },
+                targets.clone(),
+            );
+        }
+
+        if let Some((x, y)) = teleport_destination {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::TeleportTo { x, y },
+                targets.clone(),
+            );
+        }
+
+        if let Some(entangles) = ecs.read_storage::<components::Entangles>().get(source) {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Entangle {
+                    turns: entangles.turns,
+                },
+                targets.clone(),
+            );
+        }
+
+        if let Some(alarm) = ecs.read_storage::<components::SoundsAlarm>().get(source) {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::Alarm {
+                    radius: alarm.radius,
+                },
+                targets.clone(),
+            );
+        }
+
+        if ecs
+            .read_storage::<components::DropsALevel>()
+            .get(source)
+            .is_some()
+        {
+            queue.add_effect(
+                creator,
+                Some(source),
+                EffectType::FallToNextLevel,
+                targets.clone(),
+            );
+        }
+    }
+
+    // Eating is handled by the eating system, so that monsters can share it
+    if ecs.read_storage::<components::Food>().get(source).is_some() {
+        if let (Targets::Caster, Some(eater)) = (targets, creator) {
+            ecs.write_storage::<components::WantsToEat>()
+                .insert(eater, components::WantsToEat { food: source })
+                .expect("Unable to insert want to eat");
+        }
+    }
+
+    if ecs
+        .read_storage::<components::WaterFlask>()
+        .get(source)
+        .is_some()
+    {
+        if let (Targets::Caster, Some(drinker)) = (targets, creator) {
+            ecs.write_storage::<components::WantsToDrink>()
+                .insert(drinker, components::WantsToDrink { source })
+                .expect("Unable to insert want to drink");
+        }
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    if creator == Some(player_entity)
+        && ecs
+            .read_storage::<components::MagicMapper>()
+            .get(source)
+            .is_some()
+    {
+        log(ecs, "The map is revealed to you!".to_string());
+        *ecs.write_resource::<RunState>() = RunState::MagicMapReveal { row: 0 };
+    }
+}
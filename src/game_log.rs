@@ -1,3 +1,13 @@
 pub struct GameLog {
     pub entries: Vec<String>,
+    pub verbose: bool,
+}
+
+impl GameLog {
+    /// Background chatter, such as monsters picking things up, that only shows in a verbose log.
+    pub fn detail(&mut self, entry: String) {
+        if self.verbose {
+            self.entries.push(entry);
+        }
+    }
 }
@@ -1,12 +1,21 @@
-use super::{components, GameLog, Map, MAP_HEIGHT, MAX_X, MIN_X};
+use super::{
+    achievements::{Unlocks, ACHIEVEMENTS},
+    camera, clock, components,
+    controls::{key_name, Action, ActionGroup, Controls},
+    morgue,
+    scores::{self, HallOfFame, ScoreOrder},
+    settings::{Setting, Settings},
+    systems::{inventory_system, spell_system},
+    GameLog, Map, VIEWPORT_HEIGHT, VIEWPORT_WIDTH,
+};
 use rltk::{Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.draw_box(
-        MIN_X,
-        MAP_HEIGHT,
-        MAX_X,
+        0,
+        VIEWPORT_HEIGHT,
+        VIEWPORT_WIDTH - 1,
         6,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
@@ -21,14 +30,14 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         let health = format!(" HP: {} / {} ", stats.hp, stats.max_hp);
         ctx.print_color(
             2,
-            MAP_HEIGHT,
+            VIEWPORT_HEIGHT,
             RGB::named(rltk::YELLOW),
             RGB::named(rltk::BLACK),
             format!("Depth: {}", map.depth),
         );
         ctx.print_color(
             12,
-            MAP_HEIGHT,
+            VIEWPORT_HEIGHT,
             RGB::named(rltk::YELLOW),
             RGB::named(rltk::BLACK),
             &health,
@@ -36,8 +45,8 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
         ctx.draw_bar_horizontal(
             28,
-            MAP_HEIGHT,
-            MAP_HEIGHT + 3,
+            VIEWPORT_HEIGHT,
+            VIEWPORT_HEIGHT + 3,
             stats.hp,
             stats.max_hp,
             RGB::named(rltk::RED),
@@ -70,9 +79,40 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         }
     }
 
+    let hydration = ecs.read_storage::<components::HydrationClock>();
+    for (_player, hydration_clock) in (&players, &hydration).join() {
+        match hydration_clock.state {
+            components::HydrationState::Quenched => ctx.print_color(
+                61,
+                42,
+                RGB::named(rltk::GREEN),
+                RGB::named(rltk::BLACK),
+                "Quenched",
+            ),
+            components::HydrationState::Normal => {}
+            components::HydrationState::Thirsty => ctx.print_color(
+                61,
+                42,
+                RGB::named(rltk::ORANGE),
+                RGB::named(rltk::BLACK),
+                "Thirsty",
+            ),
+            components::HydrationState::Parched => ctx.print_color(
+                61,
+                42,
+                RGB::named(rltk::RED),
+                RGB::named(rltk::BLACK),
+                "Parched",
+            ),
+        }
+    }
+
+    draw_companions(ecs, ctx);
+    draw_clock(ecs, ctx);
+
     let log = ecs.fetch::<GameLog>();
 
-    let mut y = MAP_HEIGHT + 1;
+    let mut y = VIEWPORT_HEIGHT + 1;
     for s in log.entries.iter().rev() {
         if y < 49 {
             ctx.print(2, y, s);
@@ -85,15 +125,104 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::MAGENTA));
 
     show_statuses(ecs, ctx);
+    draw_spell_hotbar(ecs, ctx);
     draw_tooltips(ecs, ctx);
 }
 
+/// The player's mana and known spells, tucked into the top right corner of the map.
+fn draw_spell_hotbar(ecs: &World, ctx: &mut Rltk) {
+    let player = ecs.fetch::<Entity>();
+    let mana = ecs.read_storage::<components::Mana>();
+    let spells = ecs.read_storage::<components::Spell>();
+    let names = ecs.read_storage::<components::Name>();
+
+    let pool = match mana.get(*player) {
+        None => return,
+        Some(pool) => pool,
+    };
+
+    let mut entries: Vec<(String, RGB)> = vec![(
+        format!("Mana: {}/{}", pool.mana, pool.max_mana),
+        RGB::named(rltk::CYAN),
+    )];
+    for (i, spell) in spell_system::known_spells(ecs, *player).iter().enumerate() {
+        let mana_cost = spells.get(*spell).unwrap().mana_cost;
+        let color = if mana_cost <= pool.mana {
+            RGB::named(rltk::WHITE)
+        } else {
+            RGB::named(rltk::GREY)
+        };
+        entries.push((
+            format!(
+                "{}:{} ({})",
+                i + 1,
+                names.get(*spell).unwrap().name,
+                mana_cost
+            ),
+            color,
+        ));
+    }
+
+    let width: usize = entries.iter().map(|(text, _)| text.len() + 1).sum();
+    let mut x = VIEWPORT_WIDTH - width as i32;
+    for (text, color) in entries.iter() {
+        ctx.print_color(x, 0, *color, RGB::named(rltk::BLACK), text);
+        x += text.len() as i32 + 1;
+    }
+}
+
+// Bottom right of the log box, brightest in the middle of the day
+fn draw_clock(ecs: &World, ctx: &mut Rltk) {
+    let turns = clock::current_turn(ecs);
+    let time_of_day = clock::time_of_day(turns);
+    let fg = match time_of_day {
+        clock::TimeOfDay::Day => RGB::named(rltk::YELLOW),
+        clock::TimeOfDay::Dawn | clock::TimeOfDay::Dusk => RGB::named(rltk::ORANGE),
+        clock::TimeOfDay::Night => RGB::named(rltk::LIGHT_BLUE),
+    };
+    let text = format!(
+        " {} {} ",
+        time_of_day.description(),
+        clock::clock_face(turns)
+    );
+    ctx.print_color(
+        VIEWPORT_WIDTH - 2 - text.len() as i32,
+        VIEWPORT_HEIGHT + 6,
+        fg,
+        RGB::named(rltk::BLACK),
+        &text,
+    );
+}
+
+// Along the bottom edge of the log box, so they don't crowd the player's own health
+fn draw_companions(ecs: &World, ctx: &mut Rltk) {
+    let companions = ecs.read_storage::<components::Companion>();
+    let names = ecs.read_storage::<components::Name>();
+    let combat_stats = ecs.read_storage::<components::CombatStats>();
+
+    let mut x = 2;
+    for (_companion, name, stats) in (&companions, &names, &combat_stats).join() {
+        let text = format!(" {}: {}/{} ", name.name, stats.hp, stats.max_hp);
+        let fg = if stats.hp * 3 < stats.max_hp {
+            RGB::named(rltk::RED)
+        } else {
+            RGB::named(rltk::GREEN)
+        };
+        ctx.print_color(x, VIEWPORT_HEIGHT + 6, fg, RGB::named(rltk::BLACK), &text);
+        x += text.len() as i32 + 1;
+    }
+}
+
 fn show_statuses(ecs: &World, ctx: &mut Rltk) {
     let player = ecs.fetch::<Entity>();
     let invisible = ecs.read_storage::<components::Invisible>();
     let is_invisible = invisible.get(*player);
+    let food_poisoning = ecs.read_storage::<components::FoodPoisoning>();
+    let is_poisoned = food_poisoning.get(*player);
+    let entangled = ecs.read_storage::<components::Entangled>();
+    let is_stuck = entangled.get(*player);
 
-    let cur_x = 2;
+    let mut cur_x = 2;
 
     if let Some(invisibility) = is_invisible {
         let invisible_string = format!("Invisible ({})", invisibility.turns.unwrap_or(0));
@@ -104,6 +233,41 @@ fn show_statuses(ecs: &World, ctx: &mut Rltk) {
             RGB::named(rltk::BLACK),
             invisible_string.to_string(),
         );
+        cur_x += invisible_string.len() as i32 + 1;
+    }
+
+    if let Some(poisoned) = is_poisoned {
+        let poisoned_string = format!("Food Poisoning ({})", poisoned.turns);
+        ctx.print_color(
+            cur_x,
+            49,
+            RGB::named(rltk::GREEN),
+            RGB::named(rltk::BLACK),
+            poisoned_string.to_string(),
+        );
+        cur_x += poisoned_string.len() as i32 + 1;
+    }
+
+    if let Some(stuck) = is_stuck {
+        let stuck_string = format!("Stuck ({})", stuck.turns);
+        ctx.print_color(
+            cur_x,
+            49,
+            RGB::named(rltk::WHEAT),
+            RGB::named(rltk::BLACK),
+            stuck_string.to_string(),
+        );
+        cur_x += stuck_string.len() as i32 + 1;
+    }
+
+    if inventory_system::is_overburdened(ecs, *player) {
+        ctx.print_color(
+            cur_x,
+            49,
+            RGB::named(rltk::ORANGE),
+            RGB::named(rltk::BLACK),
+            "Overburdened",
+        );
     }
 }
 
@@ -114,13 +278,22 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     let invisible = ecs.read_storage::<components::Invisible>();
 
     let mouse_pos = ctx.mouse_pos();
-    if mouse_pos.0 >= map.width || mouse_pos.1 >= map.height {
+    if mouse_pos.0 >= VIEWPORT_WIDTH || mouse_pos.1 >= VIEWPORT_HEIGHT {
+        return;
+    }
+    let mouse_map_pos = camera::screen_to_world(ecs, mouse_pos.0, mouse_pos.1);
+    if mouse_map_pos.x < 0
+        || mouse_map_pos.x >= map.width
+        || mouse_map_pos.y < 0
+        || mouse_map_pos.y >= map.height
+    {
         return;
     }
     let mut tooltip: Vec<String> = Vec::new();
     for (name, position, _) in (&names, &positions, !&invisible).join() {
         let idx = map.xy_idx(position.x, position.y);
-        if position.x == mouse_pos.0 && position.y == mouse_pos.1 && map.visible_tiles[idx] {
+        if position.x == mouse_map_pos.x && position.y == mouse_map_pos.y && map.visible_tiles[idx]
+        {
             tooltip.push(name.name.to_string());
         }
     }
@@ -200,6 +373,57 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     }
 }
 
+/// Groups the player's backpack for display, stacking identical consumables so the list stays
+/// short. Each stack is represented by one of its items.
+fn inventory_stacks(ecs: &World) -> Vec<(Entity, String, usize)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let names = ecs.read_storage::<components::Name>();
+    let backpack = ecs.read_storage::<components::InInventory>();
+    let consumables = ecs.read_storage::<components::Consumable>();
+
+    let mut stacks: Vec<(Entity, String, usize)> = Vec::new();
+    for (entity, _pack, name) in (&entities, &backpack, &names)
+        .join()
+        .filter(|item| item.1.owner == *player_entity)
+    {
+        let existing = stacks.iter_mut().find(|(other, other_name, _)| {
+            consumables.get(entity).is_some()
+                && consumables.get(*other).is_some()
+                && *other_name == name.name
+        });
+        match existing {
+            Some(stack) => stack.2 += 1,
+            None => stacks.push((entity, name.name.clone(), 1)),
+        }
+    }
+
+    stacks
+}
+
+fn inventory_weight_string(ecs: &World) -> String {
+    let player_entity = ecs.fetch::<Entity>();
+    let carried = inventory_system::carried_weight(ecs, *player_entity);
+    match ecs
+        .read_storage::<components::Attributes>()
+        .get(*player_entity)
+    {
+        None => format!("{:.1} kg", carried),
+        Some(attributes) => format!(
+            "{:.1}/{:.0} kg",
+            carried,
+            inventory_system::carry_capacity(attributes)
+        ),
+    }
+}
+
+fn cancel_hint(ecs: &World) -> String {
+    format!(
+        "{} to cancel",
+        ecs.fetch::<Controls>().describe_keys(Action::MenuCancel)
+    )
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum ItemMenuResult {
     Cancel,
@@ -208,17 +432,12 @@ pub enum ItemMenuResult {
 }
 
 pub fn show_inventory(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = ecs.fetch::<Entity>();
-    let names = ecs.read_storage::<components::Name>();
-    let backpack = ecs.read_storage::<components::InInventory>();
+    let stacks = inventory_stacks(ecs);
     let renderables = ecs.read_storage::<components::Renderable>();
-    let entities = ecs.entities();
     let active_cooldowns = ecs.read_storage::<components::ActiveCooldown>();
+    let flasks = ecs.read_storage::<components::WaterFlask>();
 
-    let inventory = (&backpack, &names, &renderables)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count();
+    let count = stacks.len();
 
     let mut y = (25 - (count / 2)) as i32;
     ctx.draw_box(
@@ -236,21 +455,27 @@ pub fn show_inventory(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
         RGB::named(rltk::BLACK),
         "Inventory",
     );
+    let load_string = inventory_weight_string(ecs);
+    ctx.print_color(
+        53 - load_string.len() as i32,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        load_string,
+    );
     ctx.print_color(
         18,
         y + count as i32 + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "ESCAPE to cancel",
+        cancel_hint(ecs),
     );
 
     let mut equippable: Vec<Entity> = Vec::new();
     let mut j = 0;
 
-    for (entity, _pack, name, render) in (&entities, &backpack, &names, &renderables)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
-    {
+    for (entity, name, stack_size) in stacks.iter() {
+        let entity = *entity;
         ctx.set(
             17,
             y,
@@ -273,8 +498,14 @@ pub fn show_inventory(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
             rltk::to_cp437(')'),
         );
 
-        let name_string = &name.name.to_string();
-        ctx.set(21, y, render.fg, render.bg, render.glyph);
+        let name_string = &if *stack_size > 1 {
+            format!("{} x{}", name, stack_size)
+        } else {
+            name.to_string()
+        };
+        if let Some(render) = renderables.get(entity) {
+            ctx.set(21, y, render.fg, render.bg, render.glyph);
+        }
         ctx.print(23, y, name_string);
 
         let has_active_cooldown = active_cooldowns.get(entity);
@@ -286,6 +517,13 @@ pub fn show_inventory(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
                 format!("({})", cooldown.turns_remaining),
             ),
         }
+        if let Some(flask) = flasks.get(entity) {
+            ctx.print(
+                23 + name_string.len() + 1,
+                y,
+                format!("[{}/{}]", flask.sips, flask.max_sips),
+            );
+        }
 
         equippable.push(entity);
         y += 1;
@@ -294,8 +532,8 @@ pub fn show_inventory(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
 
     match ctx.key {
         None => (ItemMenuResult::NoResponse, None),
-        Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+        Some(key) => match ecs.fetch::<Controls>().action_for(key, ActionGroup::Menu) {
+            Some(Action::MenuCancel) => (ItemMenuResult::Cancel, None),
             _ => {
                 let selection = rltk::letter_to_option(key);
                 if selection > -1 && selection < count as i32 {
@@ -342,7 +580,7 @@ pub fn remove_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Opt
         y + count as i32 + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "ESCAPE to cancel",
+        cancel_hint(ecs),
     );
 
     let mut equippable: Vec<Entity> = Vec::new();
@@ -381,8 +619,8 @@ pub fn remove_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Opt
 
     match ctx.key {
         None => (ItemMenuResult::NoResponse, None),
-        Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+        Some(key) => match ecs.fetch::<Controls>().action_for(key, ActionGroup::Menu) {
+            Some(Action::MenuCancel) => (ItemMenuResult::Cancel, None),
             _ => {
                 let selection = rltk::letter_to_option(key);
                 if selection > -1 && selection < count as i32 {
@@ -398,17 +636,12 @@ pub fn remove_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Opt
 }
 
 pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    let player_entity = ecs.fetch::<Entity>();
-    let names = ecs.read_storage::<components::Name>();
-    let backpack = ecs.read_storage::<components::InInventory>();
-    let entities = ecs.entities();
+    let stacks = inventory_stacks(ecs);
     let renderables = ecs.read_storage::<components::Renderable>();
     let active_cooldowns = ecs.read_storage::<components::ActiveCooldown>();
+    let flasks = ecs.read_storage::<components::WaterFlask>();
 
-    let inventory = (&backpack, &names, &renderables)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count();
+    let count = stacks.len();
 
     let mut y = (25 - (count / 2)) as i32;
     ctx.draw_box(
@@ -431,15 +664,13 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
         y + count as i32 + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "ESCAPE to cancel",
+        cancel_hint(ecs),
     );
 
     let mut equippable: Vec<Entity> = Vec::new();
     let mut j = 0;
-    for (entity, _pack, name, render) in (&entities, &backpack, &names, &renderables)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
-    {
+    for (entity, name, stack_size) in stacks.iter() {
+        let entity = *entity;
         ctx.set(
             17,
             y,
@@ -462,8 +693,14 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
             rltk::to_cp437(')'),
         );
 
-        let name_string = &name.name.to_string();
-        ctx.set(21, y, render.fg, render.bg, render.glyph);
+        let name_string = &if *stack_size > 1 {
+            format!("{} x{}", name, stack_size)
+        } else {
+            name.to_string()
+        };
+        if let Some(render) = renderables.get(entity) {
+            ctx.set(21, y, render.fg, render.bg, render.glyph);
+        }
         ctx.print(23, y, name_string);
 
         let has_active_cooldown = active_cooldowns.get(entity);
@@ -475,6 +712,13 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
                 format!("({})", cooldown.turns_remaining),
             ),
         }
+        if let Some(flask) = flasks.get(entity) {
+            ctx.print(
+                23 + name_string.len() + 1,
+                y,
+                format!("[{}/{}]", flask.sips, flask.max_sips),
+            );
+        }
 
         equippable.push(entity);
         y += 1;
@@ -483,8 +727,8 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
 
     match ctx.key {
         None => (ItemMenuResult::NoResponse, None),
-        Some(key) => match key {
-            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+        Some(key) => match ecs.fetch::<Controls>().action_for(key, ActionGroup::Menu) {
+            Some(Action::MenuCancel) => (ItemMenuResult::Cancel, None),
             _ => {
                 let selection = rltk::letter_to_option(key);
                 if selection > -1 && selection < count as i32 {
@@ -499,6 +743,88 @@ pub fn drop_item_menu(ecs: &mut World, ctx: &mut Rltk) -> (ItemMenuResult, Optio
     }
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum CompanionOrdersResult {
+    NoResponse,
+    Cancel,
+    Selected { order: components::CompanionOrder },
+}
+
+const COMPANION_ORDERS: [(components::CompanionOrder, &str); 3] = [
+    (components::CompanionOrder::Follow, "Follow me"),
+    (components::CompanionOrder::Stay, "Stay here"),
+    (components::CompanionOrder::Attack, "Attack..."),
+];
+
+pub fn companion_orders_menu(ecs: &World, ctx: &mut Rltk) -> CompanionOrdersResult {
+    let count = COMPANION_ORDERS.len();
+    let mut y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        31,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Orders",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        cancel_hint(ecs),
+    );
+
+    for (j, (_, description)) in COMPANION_ORDERS.iter().enumerate() {
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print(21, y, description);
+        y += 1;
+    }
+
+    match ctx.key {
+        None => CompanionOrdersResult::NoResponse,
+        Some(key) => match ecs.fetch::<Controls>().action_for(key, ActionGroup::Menu) {
+            Some(Action::MenuCancel) => CompanionOrdersResult::Cancel,
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    return CompanionOrdersResult::Selected {
+                        order: COMPANION_ORDERS[selection as usize].0,
+                    };
+                }
+                CompanionOrdersResult::NoResponse
+            }
+        },
+    }
+}
+
 pub fn ranged_target(
     ecs: &mut World,
     ctx: &mut Rltk,
@@ -517,6 +843,7 @@ pub fn ranged_target(
     );
 
     // Highlight available target cells
+    let (min_x, _max_x, min_y, _max_y) = camera::get_screen_bounds(ecs);
     let mut available_cells = Vec::new();
     let visible = viewsheds.get(*player_entity);
     if let Some(visible) = visible {
@@ -524,7 +851,7 @@ pub fn ranged_target(
         for idx in visible.visible_tiles.iter() {
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
             if distance <= range as f32 {
-                ctx.set_bg(idx.x, idx.y, RGB::named(rltk::BLUE));
+                ctx.set_bg(idx.x - min_x, idx.y - min_y, RGB::named(rltk::BLUE));
                 available_cells.push(idx);
             }
         }
@@ -532,21 +859,23 @@ pub fn ranged_target(
         return (ItemMenuResult::Cancel, None);
     }
 
+    if ecs.fetch::<Controls>().menu_action(ctx.key) == Some(Action::MenuCancel) {
+        return (ItemMenuResult::Cancel, None);
+    }
+
     // Draw mouse cursor
     let mouse_pos = ctx.mouse_pos();
+    let mouse_map_pos = camera::screen_to_world(ecs, mouse_pos.0, mouse_pos.1);
     let mut valid_target = false;
     for idx in available_cells.iter() {
-        if idx.x == mouse_pos.0 && idx.y == mouse_pos.1 {
+        if idx.x == mouse_map_pos.x && idx.y == mouse_map_pos.y {
             valid_target = true;
         }
     }
     if valid_target {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::CYAN));
         if ctx.left_click {
-            return (
-                ItemMenuResult::Selected,
-                Some(Point::new(mouse_pos.0, mouse_pos.1)),
-            );
+            return (ItemMenuResult::Selected, Some(mouse_map_pos));
         }
     } else {
         ctx.set_bg(mouse_pos.0, mouse_pos.1, RGB::named(rltk::RED));
@@ -558,26 +887,448 @@ pub fn ranged_target(
     (ItemMenuResult::NoResponse, None)
 }
 
-pub fn show_dead_screen(ctx: &mut Rltk) -> Option<bool> {
+pub fn show_end_screen(ecs: &World, ctx: &mut Rltk, outcome: morgue::Outcome) -> Option<bool> {
     ctx.cls();
+    let title = match outcome {
+        morgue::Outcome::Died => "YOU ARE DEAD",
+        morgue::Outcome::Won => "VICTORY!",
+    };
+    ctx.print_color_centered(10, RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK), title);
+
+    let mut y = 13;
+    for line in morgue::run_summary(ecs, outcome).iter() {
+        ctx.print_color(2, y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), line);
+        y += 1;
+    }
+
     ctx.print_color_centered(
-        15,
-        RGB::named(rltk::YELLOW),
+        y + 1,
+        RGB::named(rltk::GREY),
         RGB::named(rltk::BLACK),
-        "YOU ARE DEAD",
+        format!("The full story is in {}", morgue::morgue_path(ecs)),
     );
+
+    let controls = ecs.fetch::<Controls>();
+    let select_key = controls
+        .describe_keys(Action::MenuSelect)
+        .split(", ")
+        .next()
+        .unwrap_or("")
+        .to_string();
     ctx.print_color_centered(
-        17,
+        y + 3,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
-        "Press Enter to Continue",
+        format!("Press {} to Continue", select_key),
     );
 
-    match ctx.key {
-        None => None,
-        Some(key) => match key {
-            VirtualKeyCode::Return => Some(true),
-            _ => None,
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuSelect) => Some(true),
+        _ => None,
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ControlsMenuResult {
+    NoResponse,
+    Cancel,
+    Updated {
+        selection: usize,
+        waiting_for_key: bool,
+        conflict: Option<(Action, VirtualKeyCode)>,
+    },
+}
+
+/// Lists every action with its keys, and lets the player add or clear bindings. The row after
+/// the last action resets everything to the defaults.
+pub fn controls_menu(
+    ecs: &mut World,
+    ctx: &mut Rltk,
+    selection: usize,
+    waiting_for_key: bool,
+    conflict: Option<(Action, VirtualKeyCode)>,
+) -> ControlsMenuResult {
+    let mut controls = ecs.fetch_mut::<Controls>();
+    let row_count = Action::ALL.len() + 1;
+
+    ctx.print_color_centered(
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Controls",
+    );
+
+    let mut y = 5;
+    for (index, action) in Action::ALL.iter().enumerate() {
+        let fg = if index == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color(10, y, fg, RGB::named(rltk::BLACK), action.description());
+        ctx.print_color(
+            32,
+            y,
+            fg,
+            RGB::named(rltk::BLACK),
+            controls.describe_keys(*action),
+        );
+        y += 1;
+    }
+    let fg = if selection == Action::ALL.len() {
+        RGB::named(rltk::MAGENTA)
+    } else {
+        RGB::named(rltk::WHITE)
+    };
+    ctx.print_color(10, y + 1, fg, RGB::named(rltk::BLACK), "Reset to defaults");
+
+    let first_key = |action: Action| -> String {
+        controls
+            .describe_keys(action)
+            .split(", ")
+            .next()
+            .unwrap_or("")
+            .to_string()
+    };
+    if waiting_for_key {
+        ctx.print_color_centered(
+            45,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            format!(
+                "Press a key for {}, or {} to cancel",
+                Action::ALL[selection].description(),
+                first_key(Action::MenuCancel)
+            ),
+        );
+    } else if let Some((other, key)) = conflict {
+        ctx.print_color_centered(
+            45,
+            RGB::named(rltk::RED),
+            RGB::named(rltk::BLACK),
+            format!(
+                "{} is already used for {}; clear it there first",
+                key_name(key),
+                other.description()
+            ),
+        );
+    }
+    ctx.print_color_centered(
+        47,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{}/{} choose, {} add a key, {} clear, {} back",
+            first_key(Action::MenuUp),
+            first_key(Action::MenuDown),
+            first_key(Action::MenuSelect),
+            first_key(Action::MenuClear),
+            first_key(Action::MenuCancel)
+        ),
+    );
+
+    let key = match ctx.key {
+        None => return ControlsMenuResult::NoResponse,
+        Some(key) => key,
+    };
+
+    if waiting_for_key {
+        let action = Action::ALL[selection];
+        let conflict = if controls.action_for(key, ActionGroup::Menu) == Some(Action::MenuCancel) {
+            None
+        } else {
+            match controls.bind(action, key) {
+                Ok(()) => {
+                    controls.save();
+                    None
+                }
+                Err(other) => Some((other, key)),
+            }
+        };
+        return ControlsMenuResult::Updated {
+            selection,
+            waiting_for_key: false,
+            conflict,
+        };
+    }
+
+    match controls.action_for(key, ActionGroup::Menu) {
+        Some(Action::MenuCancel) => ControlsMenuResult::Cancel,
+        Some(Action::MenuUp) => ControlsMenuResult::Updated {
+            selection: (selection + row_count - 1) % row_count,
+            waiting_for_key: false,
+            conflict: None,
+        },
+        Some(Action::MenuDown) => ControlsMenuResult::Updated {
+            selection: (selection + 1) % row_count,
+            waiting_for_key: false,
+            conflict: None,
+        },
+        Some(Action::MenuSelect) if selection == Action::ALL.len() => {
+            *controls = Controls::defaults();
+            controls.save();
+            ControlsMenuResult::Updated {
+                selection,
+                waiting_for_key: false,
+                conflict: None,
+            }
+        }
+        Some(Action::MenuSelect) => ControlsMenuResult::Updated {
+            selection,
+            waiting_for_key: true,
+            conflict: None,
+        },
+        // Menu keys can't be cleared, or there would be no way to get back out of here
+        Some(Action::MenuClear) if selection < Action::ALL.len() => {
+            let action = Action::ALL[selection];
+            if action.group() == ActionGroup::Game {
+                controls.clear(action);
+                controls.save();
+            }
+            ControlsMenuResult::Updated {
+                selection,
+                waiting_for_key: false,
+                conflict: None,
+            }
+        }
+        _ => ControlsMenuResult::NoResponse,
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SettingsMenuResult {
+    NoResponse,
+    Cancel,
+    Updated { selection: usize },
+}
+
+pub fn settings_menu(ecs: &mut World, ctx: &mut Rltk, selection: usize) -> SettingsMenuResult {
+    let controls = ecs.fetch::<Controls>();
+    let mut settings = ecs.fetch_mut::<Settings>();
+    let row_count = Setting::ALL.len();
+
+    ctx.print_color_centered(
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Settings",
+    );
+
+    let mut y = 5;
+    for (index, setting) in Setting::ALL.iter().enumerate() {
+        let fg = if index == selection {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color(10, y, fg, RGB::named(rltk::BLACK), setting.description());
+        ctx.print_color(
+            40,
+            y,
+            fg,
+            RGB::named(rltk::BLACK),
+            settings.describe(*setting),
+        );
+        y += 1;
+    }
+
+    let first_key = |action: Action| -> String {
+        controls
+            .describe_keys(action)
+            .split(", ")
+            .next()
+            .unwrap_or("")
+            .to_string()
+    };
+    ctx.print_color_centered(
+        47,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{}/{} choose, {} change, {} back",
+            first_key(Action::MenuUp),
+            first_key(Action::MenuDown),
+            first_key(Action::MenuSelect),
+            first_key(Action::MenuCancel)
+        ),
+    );
+
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuCancel) => SettingsMenuResult::Cancel,
+        Some(Action::MenuUp) => SettingsMenuResult::Updated {
+            selection: (selection + row_count - 1) % row_count,
+        },
+        Some(Action::MenuDown) => SettingsMenuResult::Updated {
+            selection: (selection + 1) % row_count,
         },
+        Some(Action::MenuSelect) => {
+            settings.toggle(Setting::ALL[selection]);
+            settings.save();
+            SettingsMenuResult::Updated { selection }
+        }
+        _ => SettingsMenuResult::NoResponse,
     }
 }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum HallOfFameResult {
+    NoResponse,
+    Cancel,
+    Reordered { order: ScoreOrder },
+}
+
+// As many runs as fit between the title and the key help
+const HALL_OF_FAME_ROWS: usize = 36;
+
+pub fn hall_of_fame(ecs: &mut World, ctx: &mut Rltk, order: ScoreOrder) -> HallOfFameResult {
+    let controls = ecs.fetch::<Controls>();
+    let mut hall_of_fame = ecs.fetch_mut::<HallOfFame>();
+
+    ctx.print_color_centered(
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("Hall of Fame (by {})", order.description()),
+    );
+
+    if hall_of_fame.entries.is_empty() {
+        ctx.print_color_centered(
+            6,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            "No runs recorded yet.",
+        );
+    } else {
+        ctx.print_color(
+            1,
+            5,
+            RGB::named(rltk::CYAN),
+            RGB::named(rltk::BLACK),
+            format!(
+                "{:>3} {:>6} {:<16} {:>5} {:>6} {:>5} {:<10} {}",
+                "#", "Score", "Name", "Depth", "Turns", "Kills", "Date", "Fate"
+            ),
+        );
+        for (rank, entry) in hall_of_fame
+            .entries
+            .iter()
+            .take(HALL_OF_FAME_ROWS)
+            .enumerate()
+        {
+            ctx.print_color(
+                1,
+                6 + rank as i32,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                format!(
+                    "{:>3} {:>6} {:<16} {:>5} {:>6} {:>5} {:<10} {}",
+                    rank + 1,
+                    entry.score,
+                    entry.name,
+                    entry.depth,
+                    entry.turns,
+                    entry.kills,
+                    scores::format_date(entry.date),
+                    entry.fate
+                ),
+            );
+        }
+    }
+
+    let first_key = |action: Action| -> String {
+        controls
+            .describe_keys(action)
+            .split(", ")
+            .next()
+            .unwrap_or("")
+            .to_string()
+    };
+    ctx.print_color_centered(
+        47,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!(
+            "{} sort by {}, {} back",
+            first_key(Action::MenuSelect),
+            order.next().description(),
+            first_key(Action::MenuCancel)
+        ),
+    );
+
+    match controls.menu_action(ctx.key) {
+        Some(Action::MenuCancel) => HallOfFameResult::Cancel,
+        Some(Action::MenuSelect) => {
+            let order = order.next();
+            hall_of_fame.sort(order);
+            HallOfFameResult::Reordered { order }
+        }
+        _ => HallOfFameResult::NoResponse,
+    }
+}
+
+/// Lists every achievement, with the date it was first earned. Returns true once the player
+/// wants to go back.
+pub fn show_achievements(ecs: &World, ctx: &mut Rltk) -> bool {
+    let controls = ecs.fetch::<Controls>();
+    let unlocks = ecs.fetch::<Unlocks>();
+
+    ctx.print_color_centered(
+        2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!(
+            "Achievements ({}/{})",
+            unlocks.unlocked.len(),
+            ACHIEVEMENTS.len()
+        ),
+    );
+
+    let mut y = 5;
+    for achievement in ACHIEVEMENTS.iter() {
+        match unlocks.unlocked_on(achievement) {
+            Some(date) => {
+                ctx.print_color(5, y, RGB::named(rltk::GREEN), RGB::named(rltk::BLACK), "*");
+                ctx.print_color(
+                    7,
+                    y,
+                    RGB::named(rltk::WHITE),
+                    RGB::named(rltk::BLACK),
+                    achievement.name,
+                );
+                ctx.print_color(60, y, RGB::named(rltk::GREY), RGB::named(rltk::BLACK), date);
+            }
+            None => {
+                ctx.print_color(
+                    7,
+                    y,
+                    RGB::named(rltk::GREY),
+                    RGB::named(rltk::BLACK),
+                    achievement.name,
+                );
+            }
+        }
+        ctx.print_color(
+            25,
+            y,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            achievement.description,
+        );
+        y += 2;
+    }
+
+    let back_key = controls
+        .describe_keys(Action::MenuCancel)
+        .split(", ")
+        .next()
+        .unwrap_or("")
+        .to_string();
+    ctx.print_color_centered(
+        47,
+        RGB::named(rltk::GREY),
+        RGB::named(rltk::BLACK),
+        format!("{} back", back_key),
+    );
+
+    controls.menu_action(ctx.key) == Some(Action::MenuCancel)
+}
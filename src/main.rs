@@ -4,6 +4,7 @@ use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
 extern crate serde;
 
 mod components;
+mod effects;
 mod map;
 pub use map::*;
 mod player;
@@ -12,31 +13,40 @@ mod rect;
 pub use rect::Rect;
 mod game_log;
 pub use game_log::GameLog;
+mod achievements;
+mod camera;
 mod character_creation;
+mod clock;
+mod companions;
+mod controls;
+mod dialogue;
 mod gui;
 mod map_builders;
 mod menu;
+mod morgue;
+mod quests;
 mod random_table;
+mod scores;
+mod settings;
 mod spawner;
 mod systems;
+mod travel;
 
-pub const MAP_WIDTH: i32 = 80;
-pub const MAP_HEIGHT: i32 = 43;
-pub const MAP_COUNT: i32 = MAP_WIDTH * MAP_HEIGHT;
+pub const VIEWPORT_WIDTH: i32 = 80;
+pub const VIEWPORT_HEIGHT: i32 = 43;
 
-pub const MIN_X: i32 = 0;
-pub const MAX_X: i32 = MAP_WIDTH - 1;
-pub const MIN_Y: i32 = 0;
-pub const MAX_Y: i32 = MAP_HEIGHT - 1;
-
-const SHOW_MAPGEN_VISUALIZER: bool = true;
+// The size of a regular level; some levels are bigger than the screen and scroll with the player
+pub const MAP_WIDTH: i32 = VIEWPORT_WIDTH;
+pub const MAP_HEIGHT: i32 = VIEWPORT_HEIGHT;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
     AwaitingInput,
     PreRun,
     PlayerTurn,
+    Ticking,
     MonsterTurn,
+    NewTurn,
     ShowInventory,
     ShowRemoveItem,
     ShowDropItem,
@@ -49,12 +59,36 @@ pub enum RunState {
     },
     SaveGame,
     Dead,
-    CharacterCreation,
+    Victory,
+    CharacterCreation {
+        step: character_creation::CreationStep,
+        selection: usize,
+    },
     NextLevel,
     MagicMapReveal {
         row: i32,
     },
     MapGeneration,
+    ShowControls {
+        selection: usize,
+        waiting_for_key: bool,
+        conflict: Option<(controls::Action, rltk::VirtualKeyCode)>,
+        return_to_menu: bool,
+    },
+    ShowSettings {
+        selection: usize,
+    },
+    ShowHallOfFame {
+        order: scores::ScoreOrder,
+    },
+    ShowAchievements,
+    Talking {
+        npc: Entity,
+        node: usize,
+    },
+    ShowQuestLog,
+    ShowCompanionOrders,
+    ShowCompanionTarget,
 }
 
 struct State {
@@ -63,6 +97,7 @@ struct State {
     mapgen_history: Vec<Map>,
     mapgen_index: usize,
     mapgen_timer: f32,
+    applied_settings: Option<settings::Settings>,
 }
 
 impl State {
@@ -73,23 +108,40 @@ impl State {
     fn new_game(&mut self) {
         systems::saveload_system::delete_save();
         self.ecs.delete_all();
+        self.ecs
+            .insert(character_creation::CharacterSheet::default());
         self.add_new_world_details();
     }
 
     fn add_new_world_details(&mut self) {
         self.ecs
             .insert(SimpleMarkerAllocator::<components::SerializeMe>::new());
-        self.ecs.insert(Map::new(1));
+        self.ecs.insert(Map::new(1, MAP_WIDTH, MAP_HEIGHT));
         self.ecs.insert(Point::new(0, 0));
-        self.ecs.insert(rltk::RandomNumberGenerator::new());
+        // Kept in the run stats so the morgue file can say which seed the run used
+        let seed = rltk::RandomNumberGenerator::new().next_u64();
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
         let player_entity = spawner::player(&mut self.ecs, 0, 0);
+        self.ecs
+            .write_storage::<components::RunStats>()
+            .insert(
+                player_entity,
+                components::RunStats {
+                    seed,
+                    ..Default::default()
+                },
+            )
+            .expect("Unable to insert run stats");
         self.ecs.insert(player_entity);
         self.ecs.insert(RunState::MapGeneration);
+        let verbose = self.ecs.fetch::<settings::Settings>().verbose_log;
         self.ecs.insert(game_log::GameLog {
             entries: vec!["Welcome to Rusty Roguelike".to_string()],
+            verbose,
         });
         self.ecs
             .insert(systems::particle_system::ParticleBuilder::new());
+        self.ecs.insert(effects::EffectQueue::new());
 
         self.generate_world_map(1);
     }
@@ -99,6 +151,8 @@ impl State {
         let player = self.ecs.read_storage::<components::Player>();
         let inventory = self.ecs.read_storage::<components::InInventory>();
         let equipped = self.ecs.read_storage::<components::Equipped>();
+        let known_spells = self.ecs.read_storage::<components::KnownSpell>();
+        let companions = self.ecs.read_storage::<components::Companion>();
         let player_entity = self.ecs.fetch::<Entity>();
 
         let mut to_delete: Vec<Entity> = Vec::new();
@@ -127,6 +181,19 @@ impl State {
                 }
             }
 
+            // Companions come along
+            if companions.get(entity).is_some() {
+                should_delete = false;
+            }
+
+            // Don't delete the player's spells
+            let known = known_spells.get(entity);
+            if let Some(known) = known {
+                if known.owner == *player_entity {
+                    should_delete = false;
+                }
+            }
+
             if should_delete {
                 to_delete.push(entity);
             }
@@ -135,6 +202,15 @@ impl State {
         to_delete
     }
 
+    fn player_has_artifact(&self) -> bool {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let backpack = self.ecs.read_storage::<components::InInventory>();
+        let artifacts = self.ecs.read_storage::<components::Artifact>();
+        (&backpack, &artifacts)
+            .join()
+            .any(|(carried, _)| carried.owner == player_entity)
+    }
+
     fn goto_next_level(&mut self) {
         // Delete entities that aren't the player or his/her equipment
         let to_delete = self.entities_to_remove_on_level_change();
@@ -151,6 +227,20 @@ impl State {
             current_depth = worldmap_resource.depth;
         }
         self.generate_world_map(current_depth + 1);
+        companions::follow_player(&mut self.ecs);
+
+        let player_entity = *self.ecs.fetch::<Entity>();
+        let invisible = self
+            .ecs
+            .read_storage::<components::Invisible>()
+            .get(player_entity)
+            .is_some();
+        self.ecs.write_resource::<achievements::GameEvents>().emit(
+            achievements::GameEvent::Descended {
+                depth: current_depth + 1,
+                invisible,
+            },
+        );
 
         // Notify the player and give them some health
         let player_entity = self.ecs.fetch::<Entity>();
@@ -158,6 +248,11 @@ impl State {
         gamelog
             .entries
             .push("You descend to the next level, and take a moment to heal.".to_string());
+        if current_depth + 1 == map_builders::FINAL_DEPTH {
+            gamelog
+                .entries
+                .push("The sand trembles underfoot. Something vast is waiting ahead.".to_string());
+        }
         let mut player_health_store = self.ecs.write_storage::<components::CombatStats>();
         let player_health = player_health_store.get_mut(*player_entity);
         if let Some(player_health) = player_health {
@@ -186,13 +281,36 @@ impl State {
         self.generate_world_map(1);
     }
 
+    /// Pushes any changed settings out to the context, so they take effect on the next frame.
+    fn apply_settings(&mut self, ctx: &mut Rltk) {
+        let settings = self.ecs.fetch::<settings::Settings>().clone();
+        if self.applied_settings.as_ref() == Some(&settings) {
+            return;
+        }
+
+        ctx.with_post_scanlines(settings.scanlines);
+        ctx.set_active_font(settings.font.index(), true);
+        self.ecs.fetch_mut::<game_log::GameLog>().verbose = settings.verbose_log;
+        self.applied_settings = Some(settings);
+    }
+
     fn generate_world_map(&mut self, new_depth: i32) {
         self.mapgen_index = 0;
         self.mapgen_timer = 0.0;
         self.mapgen_history.clear();
 
-        let mut builder = map_builders::random_builder(new_depth);
-        builder.build_map();
+        let record_history = self
+            .ecs
+            .fetch::<settings::Settings>()
+            .show_mapgen_visualizer;
+        let seed = self
+            .ecs
+            .read_storage::<components::RunStats>()
+            .get(*self.ecs.fetch::<Entity>())
+            .map_or(0, |stats| stats.seed);
+        let mut level_rng = map_builders::level_rng(seed, new_depth);
+        let mut builder = map_builders::random_builder(new_depth, &mut level_rng);
+        builder.build_map(&mut level_rng, record_history);
         self.mapgen_history = builder.get_snapshot_history();
 
         let player_start;
@@ -202,8 +320,14 @@ impl State {
             player_start = builder.get_starting_position();
         }
 
-        // Spawn bad guys
+        // Spawn bad guys. Spawning rolls from the world's generator, so lend it the level's one
+        // while it runs, and keep play's own rolls out of it.
+        let play_rng = std::mem::replace(
+            &mut *self.ecs.write_resource::<rltk::RandomNumberGenerator>(),
+            level_rng,
+        );
         builder.spawn_entities(&mut self.ecs);
+        *self.ecs.write_resource::<rltk::RandomNumberGenerator>() = play_rng;
 
         // Place the player and update resources
         let (player_x, player_y) = (player_start.x, player_start.y);
@@ -216,6 +340,13 @@ impl State {
             player_pos_comp.x = player_x;
             player_pos_comp.y = player_y;
         }
+        if let Some(stats) = self
+            .ecs
+            .write_storage::<components::RunStats>()
+            .get_mut(*player_entity)
+        {
+            stats.deepest_depth = i32::max(stats.deepest_depth, new_depth);
+        }
 
         // Mark the player's visibility as dirty
         let mut viewshed_components = self.ecs.write_storage::<components::Viewshed>();
@@ -229,64 +360,43 @@ impl State {
 impl GameState for State {
     fn tick(&mut self, context: &mut Rltk) {
         let mut run_state = *self.ecs.fetch::<RunState>();
+        self.apply_settings(context);
         context.cls();
         systems::particle_system::cull_dead_particles(&mut self.ecs, context);
 
         match run_state {
-            RunState::MainMenu { .. } | RunState::CharacterCreation => {}
+            RunState::MainMenu { .. }
+            | RunState::CharacterCreation { .. }
+            | RunState::ShowControls { .. }
+            | RunState::ShowSettings { .. }
+            | RunState::ShowHallOfFame { .. }
+            | RunState::ShowAchievements => {}
             _ => {
-                draw_map(&self.ecs.fetch::<Map>(), context);
+                camera::render_camera(&self.ecs, context);
+                gui::draw_ui(&self.ecs, context);
 
-                {
-                    let positions = self.ecs.read_storage::<components::Position>();
-                    let renderables = self.ecs.read_storage::<components::Renderable>();
-                    let invisibles = self.ecs.read_storage::<components::Invisible>();
-                    let players = self.ecs.read_storage::<components::Player>();
-                    let entities = self.ecs.entities();
-                    let map = self.ecs.fetch::<Map>();
-
-                    let mut data = (&positions, &renderables, &entities)
-                        .join()
-                        .collect::<Vec<_>>();
-                    data.sort_by(|&a, &b| b.1.layer.cmp(&a.1.layer));
-                    for (pos, render, entity) in data.iter() {
-                        let is_invisible = invisibles.get(*entity);
-                        match is_invisible {
-                            Some(_) => {
-                                let is_player = players.get(*entity);
-                                match is_player {
-                                    Some(_) => {
-                                        let idx = map.xy_idx(pos.x, pos.y);
-                                        if map.visible_tiles[idx] {
-                                            context.set(
-                                                pos.x,
-                                                pos.y,
-                                                rltk::GRAY65,
-                                                render.bg,
-                                                render.glyph,
-                                            )
-                                        }
-                                    }
-                                    None => {}
-                                }
-                            }
-                            None => {
-                                let idx = map.xy_idx(pos.x, pos.y);
-                                if map.visible_tiles[idx] {
-                                    context.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
-                                }
-                            }
-                        }
-                    }
-
-                    gui::draw_ui(&self.ecs, context);
-                }
                 {
                     let is_player_dead = systems::damage_system::delete_the_dead(&mut self.ecs);
                     match is_player_dead {
                         None => {}
-                        Some(_) => run_state = RunState::Dead,
+                        Some(_) => {
+                            if run_state != RunState::Dead {
+                                morgue::write_morgue(&self.ecs, morgue::Outcome::Died);
+                                scores::record_score(&self.ecs, morgue::Outcome::Died);
+                            }
+                            run_state = RunState::Dead;
+                        }
                     }
+                    if run_state != RunState::Dead
+                        && run_state != RunState::Victory
+                        && self.player_has_artifact()
+                    {
+                        morgue::write_morgue(&self.ecs, morgue::Outcome::Won);
+                        scores::record_score(&self.ecs, morgue::Outcome::Won);
+                        run_state = RunState::Victory;
+                    }
+                    achievements::process_events(&self.ecs);
+                    quests::announce_progress(&self.ecs);
                     gui::draw_ui(&self.ecs, context);
                 }
             }
@@ -294,23 +404,30 @@ impl GameState for State {
 
         match run_state {
             RunState::MapGeneration => {
-                if !SHOW_MAPGEN_VISUALIZER {
+                // Builders only keep snapshots when the visualizer is switched on
+                if self.mapgen_history.is_empty() {
                     run_state = self.mapgen_next_state.unwrap();
-                }
-                context.cls();
-                map::draw_map(&self.mapgen_history[self.mapgen_index], context);
-
-                self.mapgen_timer += context.frame_time_ms;
-                if self.mapgen_timer > 300.0 {
-                    self.mapgen_timer = 0.0;
-                    self.mapgen_index += 1;
-                    if self.mapgen_index >= self.mapgen_history.len() {
-                        run_state = self.mapgen_next_state.unwrap();
+                } else {
+                    context.cls();
+                    camera::render_debug_map(&self.mapgen_history[self.mapgen_index], context);
+
+                    self.mapgen_timer += context.frame_time_ms;
+                    if self.mapgen_timer > 300.0 {
+                        self.mapgen_timer = 0.0;
+                        self.mapgen_index += 1;
+                        if self.mapgen_index >= self.mapgen_history.len() {
+                            run_state = self.mapgen_next_state.unwrap();
+                        }
                     }
                 }
             }
-            RunState::Dead => {
-                let return_to_menu = gui::show_dead_screen(context);
+            RunState::Dead | RunState::Victory => {
+                let outcome = if run_state == RunState::Victory {
+                    morgue::Outcome::Won
+                } else {
+                    morgue::Outcome::Died
+                };
+                let return_to_menu = gui::show_end_screen(&self.ecs, context, outcome);
                 match return_to_menu {
                     None => {}
                     Some(_) => {
@@ -323,12 +440,12 @@ impl GameState for State {
             }
             RunState::MagicMapReveal { row } => {
                 let mut map = self.ecs.fetch_mut::<Map>();
-                for x in 0..MAP_WIDTH {
-                    let idx = map.xy_idx(x as i32, row);
+                for x in 0..map.width {
+                    let idx = map.xy_idx(x, row);
                     map.revealed_tiles[idx] = true;
                 }
-                if row == MAP_HEIGHT - 1 {
-                    run_state = RunState::MonsterTurn;
+                if row >= map.height - 1 {
+                    run_state = RunState::Ticking;
                 } else {
                     run_state = RunState::MagicMapReveal { row: row + 1 };
                 }
@@ -348,60 +465,132 @@ impl GameState for State {
                     menu::MainMenuResult::Selected { selected } => match selected {
                         menu::MainMenuSelection::NewGame => {
                             self.new_game();
-                            run_state = RunState::CharacterCreation;
+                            run_state = RunState::CharacterCreation {
+                                step: character_creation::CreationStep::Name,
+                                selection: 0,
+                            };
                         }
                         menu::MainMenuSelection::LoadGame => {
                             systems::saveload_system::load_game(&mut self.ecs);
                             run_state = RunState::AwaitingInput;
                             systems::saveload_system::delete_save();
                         }
+                        menu::MainMenuSelection::HallOfFame => {
+                            let mut hall_of_fame = scores::HallOfFame::load();
+                            hall_of_fame.sort(scores::ScoreOrder::Score);
+                            self.ecs.insert(hall_of_fame);
+                            run_state = RunState::ShowHallOfFame {
+                                order: scores::ScoreOrder::Score,
+                            };
+                        }
+                        menu::MainMenuSelection::Achievements => {
+                            run_state = RunState::ShowAchievements;
+                        }
+                        menu::MainMenuSelection::Controls => {
+                            run_state = RunState::ShowControls {
+                                selection: 0,
+                                waiting_for_key: false,
+                                conflict: None,
+                                return_to_menu: true,
+                            };
+                        }
+                        menu::MainMenuSelection::Settings => {
+                            run_state = RunState::ShowSettings { selection: 0 };
+                        }
                         menu::MainMenuSelection::Quit => {
                             ::std::process::exit(0);
                         }
                     },
                 }
             }
-            RunState::CharacterCreation => {
-                let selected_item = character_creation::create_character(
+            RunState::CharacterCreation { step, selection } => {
+                let result =
+                    character_creation::create_character(&mut self.ecs, context, step, selection);
+                match result {
+                    character_creation::CharacterCreationResult::NoResponse => {}
+                    character_creation::CharacterCreationResult::Cancel => {
+                        run_state = RunState::MainMenu {
+                            menu_selection: menu::MainMenuSelection::NewGame,
+                        };
+                    }
+                    character_creation::CharacterCreationResult::Updated { step, selection } => {
+                        run_state = RunState::CharacterCreation { step, selection };
+                    }
+                    character_creation::CharacterCreationResult::Finished => {
+                        character_creation::finish(&mut self.ecs);
+                        run_state = RunState::PreRun;
+                    }
+                }
+            }
+            RunState::ShowControls {
+                selection,
+                waiting_for_key,
+                conflict,
+                return_to_menu,
+            } => {
+                let result = gui::controls_menu(
+                    &mut self.ecs,
                     context,
-                    vec![
-                        "Invisibility Timer".to_string(),
-                        "Confusion Wand".to_string(),
-                    ],
+                    selection,
+                    waiting_for_key,
+                    conflict,
                 );
-
-                let mut item_to_add: Option<Entity> = None;
-                match selected_item {
-                    None => {}
-                    Some(item_name) => {
-                        match item_name.as_ref() {
-                            "Invisibility Timer" => {
-                                item_to_add = Some(spawner::invisibility_timer(&mut self.ecs));
-                            }
-                            "Confusion Wand" => {
-                                item_to_add = Some(spawner::confusion_wand(&mut self.ecs));
-                            }
-                            _ => {}
+                match result {
+                    gui::ControlsMenuResult::NoResponse => {}
+                    gui::ControlsMenuResult::Cancel => {
+                        if return_to_menu {
+                            run_state = RunState::MainMenu {
+                                menu_selection: menu::MainMenuSelection::Controls,
+                            };
+                        } else {
+                            run_state = RunState::AwaitingInput;
+                        }
+                    }
+                    gui::ControlsMenuResult::Updated {
+                        selection,
+                        waiting_for_key,
+                        conflict,
+                    } => {
+                        run_state = RunState::ShowControls {
+                            selection,
+                            waiting_for_key,
+                            conflict,
+                            return_to_menu,
                         };
                     }
                 }
-
-                let player_entity = self.ecs.fetch::<Entity>();
-                match item_to_add {
-                    None => {}
-                    Some(item) => {
-                        self.ecs
-                            .write_storage::<components::InInventory>()
-                            .insert(
-                                item,
-                                components::InInventory {
-                                    owner: *player_entity,
-                                },
-                            )
-                            .expect("Could not insert in inventory");
-
-                        run_state = RunState::PreRun;
+            }
+            RunState::ShowSettings { selection } => {
+                match gui::settings_menu(&mut self.ecs, context, selection) {
+                    gui::SettingsMenuResult::NoResponse => {}
+                    gui::SettingsMenuResult::Cancel => {
+                        run_state = RunState::MainMenu {
+                            menu_selection: menu::MainMenuSelection::Settings,
+                        };
+                    }
+                    gui::SettingsMenuResult::Updated { selection } => {
+                        run_state = RunState::ShowSettings { selection };
+                    }
+                }
+            }
+            RunState::ShowHallOfFame { order } => {
+                match gui::hall_of_fame(&mut self.ecs, context, order) {
+                    gui::HallOfFameResult::NoResponse => {}
+                    gui::HallOfFameResult::Cancel => {
+                        run_state = RunState::MainMenu {
+                            menu_selection: menu::MainMenuSelection::HallOfFame,
+                        };
                     }
+                    gui::HallOfFameResult::Reordered { order } => {
+                        run_state = RunState::ShowHallOfFame { order };
+                    }
+                }
+            }
+            RunState::ShowAchievements => {
+                if gui::show_achievements(&self.ecs, context) {
+                    run_state = RunState::MainMenu {
+                        menu_selection: menu::MainMenuSelection::Achievements,
+                    };
                 }
             }
             RunState::SaveGame => {
@@ -417,6 +606,45 @@ impl GameState for State {
             RunState::AwaitingInput => {
                 run_state = player_input(self, context);
             }
+            RunState::Talking { npc, node } => {
+                match dialogue::talk(&mut self.ecs, context, npc, node) {
+                    dialogue::DialogueResult::NoResponse => {}
+                    dialogue::DialogueResult::Goto { node } => {
+                        run_state = RunState::Talking { npc, node };
+                    }
+                    dialogue::DialogueResult::Done => run_state = RunState::AwaitingInput,
+                }
+            }
+            RunState::ShowQuestLog => {
+                if quests::show_quest_log(&self.ecs, context) {
+                    run_state = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowCompanionOrders => match gui::companion_orders_menu(&self.ecs, context) {
+                gui::CompanionOrdersResult::NoResponse => {}
+                gui::CompanionOrdersResult::Cancel => run_state = RunState::AwaitingInput,
+                gui::CompanionOrdersResult::Selected {
+                    order: components::CompanionOrder::Attack,
+                } => run_state = RunState::ShowCompanionTarget,
+                gui::CompanionOrdersResult::Selected { order } => {
+                    companions::give_orders(&mut self.ecs, order, None);
+                    run_state = RunState::AwaitingInput;
+                }
+            },
+            RunState::ShowCompanionTarget => {
+                let (result, target) =
+                    gui::ranged_target(&mut self.ecs, context, companions::COMMAND_RANGE);
+                match result {
+                    gui::ItemMenuResult::Cancel => run_state = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        if let Some(target) = target {
+                            companions::order_attack(&mut self.ecs, target);
+                        }
+                        run_state = RunState::AwaitingInput;
+                    }
+                }
+            }
             RunState::PlayerTurn => {
                 self.run_systems();
 
@@ -424,12 +652,19 @@ impl GameState for State {
                     RunState::MagicMapReveal { .. } => {
                         run_state = RunState::MagicMapReveal { row: 0 }
                     }
-                    _ => run_state = RunState::MonsterTurn,
+                    // Fell through a pit trap
+                    RunState::NextLevel => run_state = RunState::NextLevel,
+                    _ => run_state = RunState::Ticking,
                 }
             }
-            RunState::MonsterTurn => {
+            RunState::Ticking => {
+                // Decides who goes next, and starts a new turn if nobody has the energy to
+                systems::run_initiative(&mut self.ecs);
+                run_state = *self.ecs.fetch::<RunState>();
+            }
+            RunState::MonsterTurn | RunState::NewTurn => {
                 self.run_systems();
-                run_state = RunState::AwaitingInput;
+                run_state = RunState::Ticking;
             }
             RunState::ShowTargeting { range, item } => {
                 let (item_menu_result, target_position) =
@@ -438,16 +673,36 @@ impl GameState for State {
                     gui::ItemMenuResult::Cancel => run_state = RunState::AwaitingInput,
                     gui::ItemMenuResult::NoResponse => {}
                     gui::ItemMenuResult::Selected => {
-                        let mut intent = self.ecs.write_storage::<components::WantsToUseItem>();
-                        intent
-                            .insert(
-                                *self.ecs.fetch::<Entity>(),
-                                components::WantsToUseItem {
-                                    item,
-                                    target: target_position,
-                                },
-                            )
-                            .expect("Unable to insert intent");
+                        let player_entity = *self.ecs.fetch::<Entity>();
+                        let is_spell = self
+                            .ecs
+                            .read_storage::<components::Spell>()
+                            .get(item)
+                            .is_some();
+                        if is_spell {
+                            let mut intent =
+                                self.ecs.write_storage::<components::WantsToCastSpell>();
+                            intent
+                                .insert(
+                                    player_entity,
+                                    components::WantsToCastSpell {
+                                        spell: item,
+                                        target: target_position,
+                                    },
+                                )
+                                .expect("Unable to insert intent");
+                        } else {
+                            let mut intent = self.ecs.write_storage::<components::WantsToUseItem>();
+                            intent
+                                .insert(
+                                    player_entity,
+                                    components::WantsToUseItem {
+                                        item,
+                                        target: target_position,
+                                    },
+                                )
+                                .expect("Unable to insert intent");
+                        }
                         run_state = RunState::PlayerTurn;
                     }
                 }
@@ -497,9 +752,21 @@ impl GameState for State {
                     gui::ItemMenuResult::Selected => {
                         let item_entity = entity_result.unwrap();
 
+                        let is_spell = self.ecs.read_storage::<components::Spell>();
                         let is_ranged = self.ecs.read_storage::<components::Ranged>();
                         let is_item_ranged = is_ranged.get(item_entity);
-                        if let Some(is_item_ranged) = is_item_ranged {
+                        if is_spell.get(item_entity).is_some() {
+                            // Reading an unlearned spell teaches it
+                            let mut intent =
+                                self.ecs.write_storage::<components::WantsToLearnSpell>();
+                            intent
+                                .insert(
+                                    *self.ecs.fetch::<Entity>(),
+                                    components::WantsToLearnSpell { spell: item_entity },
+                                )
+                                .expect("Unable to insert intent");
+                            run_state = RunState::PlayerTurn;
+                        } else if let Some(is_item_ranged) = is_item_ranged {
                             run_state = RunState::ShowTargeting {
                                 range: is_item_ranged.range,
                                 item: item_entity,
@@ -532,11 +799,15 @@ impl GameState for State {
 fn main() -> rltk::BError {
     use rltk::RltkBuilder;
 
-    let mut context = RltkBuilder::simple80x50()
+    let settings = settings::Settings::load();
+
+    // Both fonts are loaded up front so the settings screen can switch between them
+    let context = RltkBuilder::simple80x50()
+        .with_font(settings::Font::Vga8x16.file(), 8, 16)
         .with_fitscreen(true)
+        .with_fullscreen(settings.fullscreen)
         .with_title("Roguelike Tutorial")
         .build()?;
-    context.with_post_scanlines(true);
 
     let mut gs = State {
         ecs: World::new(),
@@ -546,6 +817,7 @@ fn main() -> rltk::BError {
         mapgen_index: 0,
         mapgen_history: Vec::new(),
         mapgen_timer: 0.0,
+        applied_settings: None,
     };
 
     gs.ecs.register::<components::Position>();
@@ -572,7 +844,6 @@ fn main() -> rltk::BError {
     gs.ecs.register::<SimpleMarker<components::SerializeMe>>();
     gs.ecs.register::<components::SerializationHelper>();
     gs.ecs.register::<components::Invisible>();
-    gs.ecs.register::<components::WantsBeInvisible>();
     gs.ecs.register::<components::AppliesInvisiblity>();
     gs.ecs.register::<components::Cooldown>();
     gs.ecs.register::<components::ActiveCooldown>();
@@ -586,10 +857,51 @@ fn main() -> rltk::BError {
     gs.ecs.register::<components::MagicMapper>();
     gs.ecs.register::<components::HungerClock>();
     gs.ecs.register::<components::Food>();
+    gs.ecs.register::<components::Diet>();
+    gs.ecs.register::<components::WantsToEat>();
+    gs.ecs.register::<components::Poisonous>();
+    gs.ecs.register::<components::FoodPoisoning>();
+    gs.ecs.register::<components::Mana>();
+    gs.ecs.register::<components::Spell>();
+    gs.ecs.register::<components::KnownSpell>();
+    gs.ecs.register::<components::WantsToLearnSpell>();
+    gs.ecs.register::<components::WantsToCastSpell>();
+    gs.ecs.register::<components::TeleportsRandomly>();
+    gs.ecs.register::<components::Trap>();
+    gs.ecs.register::<components::Skills>();
+    gs.ecs.register::<components::SoundsAlarm>();
+    gs.ecs.register::<components::Alerted>();
+    gs.ecs.register::<components::DropsALevel>();
+    gs.ecs.register::<components::Entangles>();
+    gs.ecs.register::<components::Entangled>();
+    gs.ecs.register::<components::AutoTravel>();
+    gs.ecs.register::<components::AutoExplore>();
+    gs.ecs.register::<components::Attributes>();
+    gs.ecs.register::<components::Weight>();
+    gs.ecs.register::<components::Initiative>();
+    gs.ecs.register::<components::MyTurn>();
+    gs.ecs.register::<components::RunStats>();
+    gs.ecs.register::<components::Artifact>();
+    gs.ecs.register::<components::Npc>();
+    gs.ecs.register::<components::QuestLog>();
+    gs.ecs.register::<components::Companion>();
+    gs.ecs.register::<components::CompanionTarget>();
+    gs.ecs.register::<components::LightSource>();
+    gs.ecs.register::<components::HydrationClock>();
+    gs.ecs.register::<components::WaterFlask>();
+    gs.ecs.register::<components::WaterSource>();
+    gs.ecs.register::<components::WantsToDrink>();
+    gs.ecs.register::<components::LastDamagedBy>();
     gs.ecs.register::<components::EntryTrigger>();
     gs.ecs.register::<components::EntityMoved>();
     gs.ecs.register::<components::SingleActivation>();
 
+    gs.ecs.insert(controls::Controls::load());
+    gs.ecs.insert(settings);
+    gs.ecs.insert(achievements::Unlocks::load());
+    gs.ecs.insert(achievements::GameEvents::default());
+    gs.ecs.insert(dialogue::Dialogues::load());
+    gs.ecs.insert(quests::QuestBook::load());
     gs.add_new_world_details();
 
     rltk::main_loop(context, gs)
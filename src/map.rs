@@ -1,18 +1,23 @@
-use super::{MAP_HEIGHT, MAP_WIDTH, MAX_X};
-use rltk::{Algorithm2D, BaseMap, Point, Rltk, RGB};
+use rltk::{Algorithm2D, BaseMap, Point, RGB};
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use std::collections::HashSet;
 
-const MAP_COUNT: usize = (MAP_WIDTH * MAP_HEIGHT) as usize;
-
-#[derive(PartialEq, Copy, Clone, Deserialize, Serialize)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Deserialize, Serialize)]
 pub enum TileType {
     Wall,
     Floor,
     DownStairs,
 }
 
+// How bright a level is before any light sources are counted
+pub const DUNGEON_LIGHT: f32 = 0.6;
+pub const DAYLIGHT: f32 = 1.0;
+pub const PITCH_BLACK: f32 = 0.0;
+
+// Anything dimmer than this can't be made out
+const MIN_LIGHT: f32 = 0.1;
+
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
     pub tiles: Vec<TileType>,
@@ -23,25 +28,37 @@ pub struct Map {
     pub blocked: Vec<bool>,
     pub bloodstains: HashSet<usize>,
     pub depth: i32,
+    pub ambient_light: f32,
+    // Out under the open sky, where the time of day matters
+    pub surface: bool,
 
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
     pub tile_content: Vec<Vec<Entity>>,
+
+    // Rebuilt every turn by the lighting system
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub light: Vec<RGB>,
 }
 
 impl Map {
     /// Generates an empty map, consisting entirely of solid walls
-    pub fn new(new_depth: i32) -> Map {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> Map {
+        let map_count = (width * height) as usize;
         Map {
-            tiles: vec![TileType::Wall; MAP_COUNT],
-            width: MAP_WIDTH as i32,
-            height: MAP_HEIGHT as i32,
-            revealed_tiles: vec![false; MAP_COUNT],
-            visible_tiles: vec![false; MAP_COUNT],
-            blocked: vec![false; MAP_COUNT],
-            tile_content: vec![Vec::new(); MAP_COUNT],
+            tiles: vec![TileType::Wall; map_count],
+            width,
+            height,
+            revealed_tiles: vec![false; map_count],
+            visible_tiles: vec![false; map_count],
+            blocked: vec![false; map_count],
+            tile_content: vec![Vec::new(); map_count],
             depth: new_depth,
             bloodstains: HashSet::new(),
+            ambient_light: DUNGEON_LIGHT,
+            surface: false,
+            light: vec![RGB::from_f32(DUNGEON_LIGHT, DUNGEON_LIGHT, DUNGEON_LIGHT); map_count],
         }
     }
 
@@ -55,6 +72,17 @@ impl Map {
         }
     }
 
+    /// Fills the light map with the level's ambient light, before any light sources are added.
+    pub fn reset_light(&mut self) {
+        let ambient = RGB::from_f32(self.ambient_light, self.ambient_light, self.ambient_light);
+        self.light = vec![ambient; (self.width * self.height) as usize];
+    }
+
+    pub fn is_lit(&self, idx: usize) -> bool {
+        let light = self.light[idx];
+        light.r.max(light.g).max(light.b) >= MIN_LIGHT
+    }
+
     pub fn clear_content_index(&mut self) {
         for content in self.tile_content.iter_mut() {
             content.clear();
@@ -126,46 +154,40 @@ impl Algorithm2D for Map {
     }
 }
 
-pub fn draw_map(map: &Map, ctx: &mut Rltk) {
-    let mut y = 0;
-    let mut x = 0;
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        // Render a tile depending upon the tile type
-        if map.revealed_tiles[idx] {
-            let glyph;
-            let mut fg;
-            let mut bg = RGB::from_f32(0., 0., 0.);
-            match tile {
-                TileType::Floor => {
-                    glyph = rltk::to_cp437('.');
-                    fg = RGB::from_f32(0.0, 0.5, 0.5);
-                }
-                TileType::Wall => {
-                    glyph = wall_glyph(&map, x, y);
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-                TileType::DownStairs => {
-                    glyph = rltk::to_cp437('>');
-                    fg = RGB::from_f32(0., 1.0, 0.);
-                }
-            }
-            if map.bloodstains.contains(&idx) {
-                bg = RGB::from_f32(0.75, 0., 0.);
-            }
-            if !map.visible_tiles[idx] {
-                fg = fg.to_greyscale();
-                bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
-            }
-            ctx.set(x, y, fg, bg, glyph);
+/// Works out how a revealed tile should be drawn
+pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
+    let x = idx as i32 % map.width;
+    let y = idx as i32 / map.width;
+
+    let glyph;
+    let mut fg;
+    let mut bg = RGB::from_f32(0., 0., 0.);
+    match map.tiles[idx] {
+        TileType::Floor => {
+            glyph = rltk::to_cp437('.');
+            fg = RGB::from_f32(0.0, 0.5, 0.5);
         }
-
-        // Move the coordinates
-        x += 1;
-        if x > MAX_X {
-            x = 0;
-            y += 1;
+        TileType::Wall => {
+            glyph = wall_glyph(map, x, y);
+            fg = RGB::from_f32(0., 1.0, 0.);
+        }
+        TileType::DownStairs => {
+            glyph = rltk::to_cp437('>');
+            fg = RGB::from_f32(0., 1.0, 0.);
         }
     }
+    if map.bloodstains.contains(&idx) {
+        bg = RGB::from_f32(0.75, 0., 0.);
+    }
+    if map.visible_tiles[idx] {
+        fg = fg * map.light[idx];
+        bg = bg * map.light[idx];
+    } else {
+        fg = fg.to_greyscale();
+        bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
+    }
+
+    (glyph, fg, bg)
 }
 
 fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
@@ -0,0 +1,64 @@
+use super::MapBuilder;
+use crate::{
+    components::Position,
+    map::{self, Map},
+};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+/// Wraps another builder to change how brightly lit the finished level is, such as turning a
+/// cave pitch black or opening a desert up to the sky.
+pub struct AmbientLightBuilder {
+    builder: Box<dyn MapBuilder>,
+    ambient_light: f32,
+    surface: bool,
+}
+
+impl AmbientLightBuilder {
+    pub fn new(builder: Box<dyn MapBuilder>, ambient_light: f32) -> AmbientLightBuilder {
+        AmbientLightBuilder {
+            builder,
+            ambient_light,
+            surface: false,
+        }
+    }
+
+    /// Lit by the sun, or the stars, depending on the time of day.
+    pub fn surface(builder: Box<dyn MapBuilder>) -> AmbientLightBuilder {
+        AmbientLightBuilder {
+            builder,
+            ambient_light: map::DAYLIGHT,
+            surface: true,
+        }
+    }
+}
+
+impl MapBuilder for AmbientLightBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.builder.build_map(rng, record_history);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        self.builder.spawn_entities(ecs);
+    }
+
+    fn get_map(&self) -> Map {
+        let mut map = self.builder.get_map();
+        map.ambient_light = self.ambient_light;
+        map.surface = self.surface;
+        map.reset_light();
+        map
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.builder.get_starting_position()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.builder.get_snapshot_history()
+    }
+
+    fn take_snapshot(&mut self) {
+        self.builder.take_snapshot();
+    }
+}
@@ -0,0 +1,113 @@
+use super::{common, MapBuilder};
+use crate::{components::Position, map::Map, spawner, Rect, TileType};
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+// The antechamber gives the player a moment before the fight starts
+const ANTECHAMBER_WIDTH: i32 = 8;
+const ANTECHAMBER_HEIGHT: i32 = 6;
+const PILLARS: i32 = 6;
+
+/// The last level: a small antechamber, a passage, and a great round hall where the boss waits.
+/// There are no stairs down.
+pub struct BossArenaBuilder {
+    map: Map,
+    starting_position: Position,
+    boss_position: Point,
+    history: Vec<Map>,
+    record_history: bool,
+}
+
+impl BossArenaBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> BossArenaBuilder {
+        BossArenaBuilder {
+            map: Map::new(new_depth, width, height),
+            starting_position: Position { x: 0, y: 0 },
+            boss_position: Point::new(0, 0),
+            history: Vec::new(),
+            record_history: false,
+        }
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        let mid_y = self.map.height / 2;
+
+        let antechamber = Rect::new(
+            2,
+            mid_y - ANTECHAMBER_HEIGHT / 2,
+            ANTECHAMBER_WIDTH,
+            ANTECHAMBER_HEIGHT,
+        );
+        common::apply_room_to_map(&mut self.map, &antechamber);
+        self.take_snapshot();
+
+        let radius = i32::min(self.map.height / 2 - 2, self.map.width / 4);
+        let center = Point::new(self.map.width - radius - 3, mid_y);
+        for y in center.y - radius..=center.y + radius {
+            for x in center.x - radius..=center.x + radius {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(center, Point::new(x, y));
+                if distance <= radius as f32 {
+                    let idx = self.map.xy_idx(x, y);
+                    self.map.tiles[idx] = TileType::Floor;
+                }
+            }
+            self.take_snapshot();
+        }
+
+        let (start_x, start_y) = antechamber.center();
+        common::apply_horizontal_tunnel(&mut self.map, start_x, center.x, mid_y);
+        self.take_snapshot();
+
+        // A few pillars to duck behind, kept clear of the middle and the way in
+        for _ in 0..PILLARS {
+            let angle = rng.range(0, 360) as f32 * std::f32::consts::PI / 180.0;
+            let distance = rng.range(radius / 3, radius - 1) as f32;
+            let x = center.x + (angle.cos() * distance) as i32;
+            let y = center.y + (angle.sin() * distance) as i32;
+            if y != mid_y {
+                let idx = self.map.xy_idx(x, y);
+                self.map.tiles[idx] = TileType::Wall;
+                self.take_snapshot();
+            }
+        }
+
+        self.starting_position = Position {
+            x: start_x,
+            y: start_y,
+        };
+        self.boss_position = center;
+    }
+}
+
+impl MapBuilder for BossArenaBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        spawner::boss(ecs, self.boss_position.x, self.boss_position.y);
+    }
+
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        if self.record_history {
+            let mut snapshot = self.map.clone();
+            for v in snapshot.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+            self.history.push(snapshot);
+        }
+    }
+}
@@ -1,5 +1,9 @@
-use super::{common, MapBuilder};
-use crate::{components::Position, spawner, Map, Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use super::{
+    common,
+    room_connections::{self, CorridorStyle, RoomConnection},
+    MapBuilder,
+};
+use crate::{components::Position, spawner, Map, Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::World;
 
@@ -9,24 +13,24 @@ pub struct BspDungeonBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    record_history: bool,
     rects: Vec<Rect>,
 }
 
 impl BspDungeonBuilder {
-    pub fn new(new_depth: i32) -> BspDungeonBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> BspDungeonBuilder {
         BspDungeonBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
             rects: Vec::new(),
         }
     }
 
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         self.rects.clear();
         self.rects
             .push(Rect::new(2, 2, self.map.width - 5, self.map.height - 5)); // Start with a single map-sized rectangle
@@ -37,8 +41,8 @@ impl BspDungeonBuilder {
         // room in there, we place it and add it to the rooms list.
         let mut n_rooms = 0;
         while n_rooms < 240 {
-            let rect = self.get_random_rect(&mut rng);
-            let candidate = self.get_random_sub_rect(rect, &mut rng);
+            let rect = self.get_random_rect(rng);
+            let candidate = self.get_random_sub_rect(rect, rng);
 
             if self.is_possible(candidate) {
                 common::apply_room_to_map(&mut self.map, &candidate);
@@ -57,17 +61,18 @@ impl BspDungeonBuilder {
 
         self.rooms.sort_by(|a, b| a.x1.cmp(&b.x1));
 
-        // Now we want corridors
-        for i in 0..self.rooms.len() - 1 {
-            let room = self.rooms[i];
-            let next_room = self.rooms[i + 1];
-            let start_x = room.x1 + (rng.roll_dice(1, i32::abs(room.x1 - room.x2)) - 1);
-            let start_y = room.y1 + (rng.roll_dice(1, i32::abs(room.y1 - room.y2)) - 1);
-            let end_x =
-                next_room.x1 + (rng.roll_dice(1, i32::abs(next_room.x1 - next_room.x2)) - 1);
-            let end_y =
-                next_room.y1 + (rng.roll_dice(1, i32::abs(next_room.y1 - next_room.y2)) - 1);
-            self.draw_corridor(start_x, start_y, end_x, end_y);
+        // Now we want corridors, using a different strategy each time we build
+        let connection = RoomConnection::random(rng);
+        let corridor_style = CorridorStyle::random(rng);
+        let corridors = room_connections::connect_rooms(&self.rooms, connection, rng);
+        for (from, to) in corridors.iter() {
+            room_connections::dig_corridor(
+                &mut self.map,
+                rng,
+                corridor_style,
+                self.rooms[*from].center(),
+                self.rooms[*to].center(),
+            );
             self.take_snapshot();
         }
 
@@ -77,26 +82,6 @@ impl BspDungeonBuilder {
         self.map.tiles[stairs_idx] = TileType::DownStairs;
     }
 
-    fn draw_corridor(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
-        let mut x = x1;
-        let mut y = y1;
-
-        while x != x2 || y != y2 {
-            if x < x2 {
-                x += 1;
-            } else if x > x2 {
-                x -= 1;
-            } else if y < y2 {
-                y += 1;
-            } else if y > y2 {
-                y -= 1;
-            }
-
-            let idx = self.map.xy_idx(x, y);
-            self.map.tiles[idx] = TileType::Floor;
-        }
-    }
-
     fn add_subrects(&mut self, rect: Rect) {
         let width = i32::abs(rect.x1 - rect.x2);
         let height = i32::abs(rect.y1 - rect.y2);
@@ -198,8 +183,9 @@ impl MapBuilder for BspDungeonBuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -209,7 +195,7 @@ impl MapBuilder for BspDungeonBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -1,5 +1,5 @@
 use super::MapBuilder;
-use crate::{components::Position, map::TileType, spawner, Map, Rect, SHOW_MAPGEN_VISUALIZER};
+use crate::{components::Position, map::TileType, spawner, Map, Rect};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
@@ -11,29 +11,29 @@ pub struct BspInteriorBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    record_history: bool,
     rects: Vec<Rect>,
 }
 
 impl BspInteriorBuilder {
-    pub fn new(new_depth: i32) -> BspInteriorBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> BspInteriorBuilder {
         BspInteriorBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
             rects: Vec::new(),
         }
     }
 
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         self.rects.clear();
         self.rects
             .push(Rect::new(1, 1, self.map.width - 2, self.map.height - 2)); // Start with a single map-sized rectangle
         let first_room = self.rects[0];
-        self.add_subrects(first_room, &mut rng); // Divide the first room
+        self.add_subrects(first_room, rng); // Divide the first room
 
         let rooms = self.rects.clone();
         for r in rooms.iter() {
@@ -152,8 +152,9 @@ impl MapBuilder for BspInteriorBuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build()
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng)
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -163,7 +164,7 @@ impl MapBuilder for BspInteriorBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
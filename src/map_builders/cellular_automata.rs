@@ -1,31 +1,31 @@
 use super::{common, MapBuilder};
-use crate::{components::Position, spawner, Map, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::{components::Position, spawner, Map, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 pub struct CellularAutomataBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
     history: Vec<Map>,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl CellularAutomataBuilder {
-    pub fn new(new_depth: i32) -> CellularAutomataBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> CellularAutomataBuilder {
         CellularAutomataBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
         }
     }
 
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         // First we completely randomize the map, setting 55% of it to be floor.
         for y in 1..self.map.height - 1 {
             for x in 1..self.map.width - 1 {
@@ -85,20 +85,13 @@ impl CellularAutomataBuilder {
             self.take_snapshot();
         }
 
-        // Find a starting point; start at the middle and walk left until we find an open tile
+        // Find a starting point as close to the middle as we can
+        let start_idx = common::find_floor_nearest_center(&self.map)
+            .expect("Cellular automata map has no floor to start on");
         self.starting_position = Position {
-            x: self.map.width / 2,
-            y: self.map.height / 2,
+            x: start_idx as i32 % self.map.width,
+            y: start_idx as i32 / self.map.width,
         };
-        let mut start_idx = self
-            .map
-            .xy_idx(self.starting_position.x, self.starting_position.y);
-        while self.map.tiles[start_idx] != TileType::Floor {
-            self.starting_position.x -= 1;
-            start_idx = self
-                .map
-                .xy_idx(self.starting_position.x, self.starting_position.y);
-        }
 
         // Find all tiles we can reach from the starting point
         let exit_tile =
@@ -110,7 +103,7 @@ impl CellularAutomataBuilder {
         self.take_snapshot();
 
         // Now we build a noise map for use in spawning entities later
-        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, &mut rng);
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
     }
 }
 
@@ -127,8 +120,9 @@ impl MapBuilder for CellularAutomataBuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -138,7 +132,7 @@ impl MapBuilder for CellularAutomataBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -1,7 +1,7 @@
 use crate::{map::Map, Rect, TileType};
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::BTreeMap,
 };
 
 pub fn apply_room_to_map(map: &mut Map, room: &Rect) {
@@ -64,8 +64,8 @@ pub fn remove_unreachable_areas_returning_most_distant(map: &mut Map, start_idx:
 pub fn generate_voronoi_spawn_regions(
     map: &Map,
     rng: &mut rltk::RandomNumberGenerator,
-) -> HashMap<i32, Vec<usize>> {
-    let mut noise_areas: HashMap<i32, Vec<usize>> = HashMap::new();
+) -> BTreeMap<i32, Vec<usize>> {
+    let mut noise_areas: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
     let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
     noise.set_noise_type(rltk::NoiseType::Cellular);
     noise.set_frequency(0.08);
@@ -89,3 +89,17 @@ pub fn generate_voronoi_spawn_regions(
 
     noise_areas
 }
+
+pub fn find_floor_nearest_center(map: &Map) -> Option<usize> {
+    let center = rltk::Point::new(map.width / 2, map.height / 2);
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| **tile == TileType::Floor)
+        .map(|(idx, _)| {
+            let pos = rltk::Point::new(idx as i32 % map.width, idx as i32 / map.width);
+            (idx, rltk::DistanceAlg::Pythagoras.distance2d(center, pos))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(idx, _)| idx)
+}
@@ -1,8 +1,8 @@
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::{components::Position, spawner, Map, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::{components::Position, spawner, Map, TileType};
 
 use super::{common, MapBuilder};
 
@@ -27,7 +27,8 @@ pub struct DLABuilder {
     starting_position: Position,
     depth: i32,
     history: Vec<Map>,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
     algorithm: DLAAlgorithm,
     brush_size: i32,
     symmetry: DLASymmetry,
@@ -47,8 +48,9 @@ impl MapBuilder for DLABuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -58,7 +60,7 @@ impl MapBuilder for DLABuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -70,13 +72,14 @@ impl MapBuilder for DLABuilder {
 
 impl DLABuilder {
     #[allow(dead_code)]
-    pub fn new(new_depth: i32) -> DLABuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> DLABuilder {
         DLABuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::WalkInwards,
             brush_size: 2,
             symmetry: DLASymmetry::None,
@@ -84,13 +87,14 @@ impl DLABuilder {
         }
     }
 
-    pub fn walk_inwards(new_depth: i32) -> DLABuilder {
+    pub fn walk_inwards(new_depth: i32, width: i32, height: i32) -> DLABuilder {
         DLABuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::WalkInwards,
             brush_size: 1,
             symmetry: DLASymmetry::None,
@@ -98,13 +102,14 @@ impl DLABuilder {
         }
     }
 
-    pub fn walk_outwards(new_depth: i32) -> DLABuilder {
+    pub fn walk_outwards(new_depth: i32, width: i32, height: i32) -> DLABuilder {
         DLABuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::WalkOutwards,
             brush_size: 2,
             symmetry: DLASymmetry::None,
@@ -112,13 +117,14 @@ impl DLABuilder {
         }
     }
 
-    pub fn central_attractor(new_depth: i32) -> DLABuilder {
+    pub fn central_attractor(new_depth: i32, width: i32, height: i32) -> DLABuilder {
         DLABuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::CentralAttractor,
             brush_size: 2,
             symmetry: DLASymmetry::None,
@@ -126,13 +132,14 @@ impl DLABuilder {
         }
     }
 
-    pub fn insectoid(new_depth: i32) -> DLABuilder {
+    pub fn insectoid(new_depth: i32, width: i32, height: i32) -> DLABuilder {
         DLABuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             algorithm: DLAAlgorithm::CentralAttractor,
             brush_size: 2,
             symmetry: DLASymmetry::Horizontal,
@@ -141,9 +148,7 @@ impl DLABuilder {
     }
 
     #[allow(clippy::map_entry)]
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         // Carve a starting seed
         self.starting_position = Position {
             x: self.map.width / 2,
@@ -285,7 +290,7 @@ impl DLABuilder {
         self.take_snapshot();
 
         // Now we build a noise map for use in spawning entities later
-        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, &mut rng);
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
     }
 
     fn paint(&mut self, x: i32, y: i32) {
@@ -1,8 +1,8 @@
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::{components::Position, spawner, Map, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::{components::Position, spawner, Map, TileType};
 
 use super::{common, MapBuilder};
 
@@ -23,7 +23,8 @@ pub struct DrunkardsWalkBuilder {
     starting_position: Position,
     depth: i32,
     history: Vec<Map>,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
     settings: DrunkardSettings,
 }
 
@@ -40,8 +41,9 @@ impl MapBuilder for DrunkardsWalkBuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -51,7 +53,7 @@ impl MapBuilder for DrunkardsWalkBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -62,20 +64,28 @@ impl MapBuilder for DrunkardsWalkBuilder {
 }
 
 impl DrunkardsWalkBuilder {
-    pub fn new(new_depth: i32, settings: DrunkardSettings) -> DrunkardsWalkBuilder {
+    pub fn new(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        settings: DrunkardSettings,
+    ) -> DrunkardsWalkBuilder {
         DrunkardsWalkBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
             settings,
         }
     }
 
-    pub fn open_area(new_depth: i32) -> DrunkardsWalkBuilder {
+    pub fn open_area(new_depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
         DrunkardsWalkBuilder::new(
             new_depth,
+            width,
+            height,
             DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::StartingPoint,
                 drunken_lifetime: 400,
@@ -84,9 +94,11 @@ impl DrunkardsWalkBuilder {
         )
     }
 
-    pub fn open_halls(new_depth: i32) -> DrunkardsWalkBuilder {
+    pub fn open_halls(new_depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
         DrunkardsWalkBuilder::new(
             new_depth,
+            width,
+            height,
             DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 400,
@@ -95,9 +107,11 @@ impl DrunkardsWalkBuilder {
         )
     }
 
-    pub fn winding_passages(new_depth: i32) -> DrunkardsWalkBuilder {
+    pub fn winding_passages(new_depth: i32, width: i32, height: i32) -> DrunkardsWalkBuilder {
         DrunkardsWalkBuilder::new(
             new_depth,
+            width,
+            height,
             DrunkardSettings {
                 spawn_mode: DrunkSpawnMode::Random,
                 drunken_lifetime: 100,
@@ -106,9 +120,7 @@ impl DrunkardsWalkBuilder {
         )
     }
 
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         // Set a central starting point
         self.starting_position = Position {
             x: self.map.width / 2,
@@ -221,6 +233,6 @@ impl DrunkardsWalkBuilder {
         self.take_snapshot();
 
         // Now we build a noise map for use in spawning entities later
-        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, &mut rng);
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
     }
 }
@@ -1,8 +1,8 @@
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use crate::{components::Position, spawner, Map, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::{components::Position, spawner, Map, TileType};
 
 use super::{common, MapBuilder};
 
@@ -11,7 +11,8 @@ pub struct MazeBuilder {
     starting_position: Position,
     depth: i32,
     history: Vec<Map>,
-    noise_areas: HashMap<i32, Vec<usize>>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
 }
 
 impl MapBuilder for MazeBuilder {
@@ -27,8 +28,9 @@ impl MapBuilder for MazeBuilder {
         self.history.clone()
     }
 
-    fn build_map(&mut self) {
-        self.build();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -38,7 +40,7 @@ impl MapBuilder for MazeBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -49,26 +51,21 @@ impl MapBuilder for MazeBuilder {
 }
 
 impl MazeBuilder {
-    pub fn new(new_depth: i32) -> MazeBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> MazeBuilder {
         MazeBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             history: Vec::new(),
-            noise_areas: HashMap::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
         }
     }
 
     #[allow(clippy::map_entry)]
-    fn build(&mut self) {
-        let mut rng = RandomNumberGenerator::new();
-
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
         // Maze gen
-        let mut maze = Grid::new(
-            (self.map.width / 2) - 2,
-            (self.map.height / 2) - 2,
-            &mut rng,
-        );
+        let mut maze = Grid::new((self.map.width / 2) - 2, (self.map.height / 2) - 2, rng);
         maze.generate_maze(self);
 
         // Find a starting point; start at the middle and walk left until we find an open tile
@@ -88,7 +85,7 @@ impl MazeBuilder {
         self.take_snapshot();
 
         // Now we build a noise map for use in spawning entities later
-        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, &mut rng);
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
     }
 }
 
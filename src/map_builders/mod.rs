@@ -1,4 +1,9 @@
-use crate::{components::Position, map::Map};
+use crate::{
+    components::Position,
+    map::{self, Map},
+    MAP_HEIGHT, MAP_WIDTH,
+};
+use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
 mod simple_map;
@@ -7,6 +12,7 @@ mod bsp_dungeon;
 use bsp_dungeon::BspDungeonBuilder;
 mod bsp_interior;
 mod common;
+mod room_connections;
 use bsp_interior::BspInteriorBuilder;
 mod cellular_automata;
 use cellular_automata::CellularAutomataBuilder;
@@ -16,9 +22,18 @@ mod maze;
 use maze::MazeBuilder;
 mod dla;
 use dla::DLABuilder;
+mod voronoi;
+use voronoi::VoronoiCellBuilder;
+mod waveform_collapse;
+use waveform_collapse::WaveformCollapseBuilder;
+mod boss_arena;
+use boss_arena::BossArenaBuilder;
+mod ambient_light;
+use ambient_light::AmbientLightBuilder;
 
 pub trait MapBuilder {
-    fn build_map(&mut self);
+    /// Snapshots for the map generation visualizer are only kept when `record_history` is set.
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool);
     fn spawn_entities(&mut self, ecs: &mut World);
     fn get_map(&self) -> Map;
     fn get_starting_position(&self) -> Position;
@@ -26,21 +41,80 @@ pub trait MapBuilder {
     fn take_snapshot(&mut self);
 }
 
-pub fn random_builder(new_depth: i32) -> Box<dyn MapBuilder> {
-    let mut rng = rltk::RandomNumberGenerator::new();
-    let builder = rng.roll_dice(1, 12);
-    match builder {
-        1 => Box::new(BspDungeonBuilder::new(new_depth)),
-        2 => Box::new(BspInteriorBuilder::new(new_depth)),
-        3 => Box::new(CellularAutomataBuilder::new(new_depth)),
-        4 => Box::new(DrunkardsWalkBuilder::open_area(new_depth)),
-        5 => Box::new(DrunkardsWalkBuilder::open_halls(new_depth)),
-        6 => Box::new(DrunkardsWalkBuilder::winding_passages(new_depth)),
-        7 => Box::new(MazeBuilder::new(new_depth)),
-        8 => Box::new(DLABuilder::walk_inwards(new_depth)),
-        9 => Box::new(DLABuilder::walk_outwards(new_depth)),
-        10 => Box::new(DLABuilder::central_attractor(new_depth)),
-        11 => Box::new(DLABuilder::insectoid(new_depth)),
-        _ => Box::new(SimpleMapBuilder::new(new_depth)),
+// Every so often a level is a huge open desert, far bigger than the screen
+const DESERT_WIDTH: i32 = 200;
+const DESERT_HEIGHT: i32 = 120;
+
+// The boss waits at the bottom, and nothing lies below it
+pub const FINAL_DEPTH: i32 = 10;
+
+/// Each level gets a generator of its own, derived from the run's seed and the depth. Levels then
+/// follow from the seed recorded for the run, whatever was rolled in play before reaching them.
+pub fn level_rng(seed: u64, depth: i32) -> RandomNumberGenerator {
+    RandomNumberGenerator::seeded(seed ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Picks a builder for the level. Pass it the level's generator from `level_rng`, and build with
+/// the same one, so the choice and the layout both follow from the seed.
+pub fn random_builder(new_depth: i32, rng: &mut RandomNumberGenerator) -> Box<dyn MapBuilder> {
+    if new_depth == FINAL_DEPTH {
+        return Box::new(BossArenaBuilder::new(new_depth, MAP_WIDTH, MAP_HEIGHT));
+    }
+
+    if new_depth > 1 && rng.roll_dice(1, 6) == 1 {
+        let desert = Box::new(CellularAutomataBuilder::new(
+            new_depth,
+            DESERT_WIDTH,
+            DESERT_HEIGHT,
+        ));
+        return Box::new(AmbientLightBuilder::surface(desert));
     }
+
+    let (width, height) = (MAP_WIDTH, MAP_HEIGHT);
+    let builder = rng.roll_dice(1, 16);
+    let mut result: Box<dyn MapBuilder> = match builder {
+        1 => Box::new(BspDungeonBuilder::new(new_depth, width, height)),
+        2 => Box::new(BspInteriorBuilder::new(new_depth, width, height)),
+        3 => Box::new(CellularAutomataBuilder::new(new_depth, width, height)),
+        4 => Box::new(DrunkardsWalkBuilder::open_area(new_depth, width, height)),
+        5 => Box::new(DrunkardsWalkBuilder::open_halls(new_depth, width, height)),
+        6 => Box::new(DrunkardsWalkBuilder::winding_passages(
+            new_depth, width, height,
+        )),
+        7 => Box::new(MazeBuilder::new(new_depth, width, height)),
+        8 => Box::new(DLABuilder::walk_inwards(new_depth, width, height)),
+        9 => Box::new(DLABuilder::walk_outwards(new_depth, width, height)),
+        10 => Box::new(DLABuilder::central_attractor(new_depth, width, height)),
+        11 => Box::new(DLABuilder::insectoid(new_depth, width, height)),
+        12 => Box::new(WaveformCollapseBuilder::from_prefab(
+            new_depth,
+            width,
+            height,
+            waveform_collapse::SAND_CAVES_PREFAB,
+            waveform_collapse::DEFAULT_CHUNK_SIZE,
+        )),
+        13 => Box::new(VoronoiCellBuilder::pythagoras(new_depth, width, height)),
+        14 => Box::new(VoronoiCellBuilder::manhattan(new_depth, width, height)),
+        15 => Box::new(VoronoiCellBuilder::chebyshev(new_depth, width, height)),
+        _ => Box::new(SimpleMapBuilder::new(new_depth, width, height)),
+    };
+
+    // Sometimes we take the map we built and use it as the source for Wave Function Collapse
+    if builder != 12 && rng.roll_dice(1, 3) == 1 {
+        let chunk_size = rng.range(
+            waveform_collapse::MIN_CHUNK_SIZE,
+            waveform_collapse::MAX_CHUNK_SIZE + 1,
+        );
+        result = Box::new(WaveformCollapseBuilder::derived_map(
+            new_depth, width, height, result, chunk_size,
+        ));
+    }
+
+    // Deeper down, some of the caves never see any light but what you bring
+    let is_cave = matches!(builder, 3..=6 | 12);
+    if is_cave && new_depth > 2 && rng.roll_dice(1, 3) == 1 {
+        result = Box::new(AmbientLightBuilder::new(result, map::PITCH_BLACK));
+    }
+
+    result
 }
@@ -0,0 +1,284 @@
+use super::common;
+use crate::{Map, Rect, TileType};
+use rltk::{Point, RandomNumberGenerator};
+
+const MAX_WINDING_STEPS: i32 = 400;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum RoomConnection {
+    NearestNeighbour,
+    SpanningTree { extra_loops: i32 },
+    BspSiblings,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CorridorStyle {
+    Straight,
+    DogLeg,
+    Winding,
+}
+
+impl RoomConnection {
+    pub fn random(rng: &mut RandomNumberGenerator) -> RoomConnection {
+        match rng.roll_dice(1, 3) {
+            1 => RoomConnection::NearestNeighbour,
+            2 => RoomConnection::SpanningTree {
+                extra_loops: rng.roll_dice(1, 4),
+            },
+            _ => RoomConnection::BspSiblings,
+        }
+    }
+}
+
+impl CorridorStyle {
+    pub fn random(rng: &mut RandomNumberGenerator) -> CorridorStyle {
+        match rng.roll_dice(1, 3) {
+            1 => CorridorStyle::Straight,
+            2 => CorridorStyle::DogLeg,
+            _ => CorridorStyle::Winding,
+        }
+    }
+}
+
+/// Works out which pairs of rooms should be joined by a corridor. Whatever the strategy, every
+/// room ends up reachable from every other room.
+pub fn connect_rooms(
+    rooms: &[Rect],
+    connection: RoomConnection,
+    rng: &mut RandomNumberGenerator,
+) -> Vec<(usize, usize)> {
+    if rooms.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut corridors = match connection {
+        RoomConnection::NearestNeighbour => nearest_neighbour(rooms),
+        RoomConnection::SpanningTree { extra_loops } => spanning_tree(rooms, extra_loops, rng),
+        RoomConnection::BspSiblings => {
+            let mut corridors = Vec::new();
+            bsp_siblings(rooms, (0..rooms.len()).collect(), &mut corridors);
+            corridors
+        }
+    };
+
+    ensure_connected(rooms, &mut corridors);
+    corridors
+}
+
+pub fn dig_corridor(
+    map: &mut Map,
+    rng: &mut RandomNumberGenerator,
+    style: CorridorStyle,
+    start: (i32, i32),
+    end: (i32, i32),
+) {
+    match style {
+        CorridorStyle::Straight => apply_straight_corridor(map, start, end),
+        CorridorStyle::DogLeg => apply_dog_leg_corridor(map, rng, start, end),
+        CorridorStyle::Winding => apply_winding_corridor(map, rng, start, end),
+    }
+}
+
+fn room_distance(a: &Rect, b: &Rect) -> f32 {
+    let (ax, ay) = a.center();
+    let (bx, by) = b.center();
+    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(ax, ay), Point::new(bx, by))
+}
+
+fn closest_pair(rooms: &[Rect], group_a: &[usize], group_b: &[usize]) -> (usize, usize) {
+    let mut best = (group_a[0], group_b[0], f32::MAX);
+    for a in group_a.iter() {
+        for b in group_b.iter() {
+            let distance = room_distance(&rooms[*a], &rooms[*b]);
+            if distance < best.2 {
+                best = (*a, *b, distance);
+            }
+        }
+    }
+    (best.0, best.1)
+}
+
+/// Each room connects to the closest room that hasn't already made its own connection.
+fn nearest_neighbour(rooms: &[Rect]) -> Vec<(usize, usize)> {
+    let mut corridors = Vec::new();
+    let mut connected = vec![false; rooms.len()];
+
+    for (i, room) in rooms.iter().enumerate() {
+        let nearest = rooms
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i && !connected[*j])
+            .map(|(j, other)| (j, room_distance(room, other)))
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        if let Some((j, _)) = nearest {
+            corridors.push((i, j));
+            connected[i] = true;
+        }
+    }
+
+    corridors
+}
+
+/// Prim's algorithm over the room centers, followed by a few extra corridors so that the
+/// dungeon has some loops in it rather than being a pure tree.
+fn spanning_tree(
+    rooms: &[Rect],
+    extra_loops: i32,
+    rng: &mut RandomNumberGenerator,
+) -> Vec<(usize, usize)> {
+    let mut corridors: Vec<(usize, usize)> = Vec::new();
+    let mut in_tree = vec![false; rooms.len()];
+    in_tree[0] = true;
+
+    for _i in 1..rooms.len() {
+        let tree: Vec<usize> = (0..rooms.len()).filter(|i| in_tree[*i]).collect();
+        let outside: Vec<usize> = (0..rooms.len()).filter(|i| !in_tree[*i]).collect();
+        let (a, b) = closest_pair(rooms, &tree, &outside);
+        in_tree[b] = true;
+        corridors.push((a, b));
+    }
+
+    for _i in 0..extra_loops {
+        let a = (rng.roll_dice(1, rooms.len() as i32) - 1) as usize;
+        let nearest = (0..rooms.len())
+            .filter(|b| *b != a && !corridors.contains(&(a, *b)) && !corridors.contains(&(*b, a)))
+            .map(|b| (b, room_distance(&rooms[a], &rooms[b])))
+            .min_by(|x, y| x.1.partial_cmp(&y.1).unwrap());
+
+        if let Some((b, _)) = nearest {
+            corridors.push((a, b));
+        }
+    }
+
+    corridors
+}
+
+/// Recursively splits the rooms in half along their widest axis, joining the two halves at
+/// their closest point. Rooms that end up as siblings in the tree are always connected.
+fn bsp_siblings(rooms: &[Rect], mut group: Vec<usize>, corridors: &mut Vec<(usize, usize)>) {
+    if group.len() < 2 {
+        return;
+    }
+
+    let xs: Vec<i32> = group.iter().map(|i| rooms[*i].center().0).collect();
+    let ys: Vec<i32> = group.iter().map(|i| rooms[*i].center().1).collect();
+    let spread_x = xs.iter().max().unwrap() - xs.iter().min().unwrap();
+    let spread_y = ys.iter().max().unwrap() - ys.iter().min().unwrap();
+    if spread_x >= spread_y {
+        group.sort_by_key(|i| rooms[*i].center().0);
+    } else {
+        group.sort_by_key(|i| rooms[*i].center().1);
+    }
+
+    let other_half = group.split_off(group.len() / 2);
+    corridors.push(closest_pair(rooms, &group, &other_half));
+
+    bsp_siblings(rooms, group, corridors);
+    bsp_siblings(rooms, other_half, corridors);
+}
+
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        parents[root] = parents[parents[root]];
+        root = parents[root];
+    }
+    root
+}
+
+fn ensure_connected(rooms: &[Rect], corridors: &mut Vec<(usize, usize)>) {
+    let mut parents: Vec<usize> = (0..rooms.len()).collect();
+    for (a, b) in corridors.iter() {
+        let root_a = find_root(&mut parents, *a);
+        let root_b = find_root(&mut parents, *b);
+        parents[root_a] = root_b;
+    }
+
+    loop {
+        let root = find_root(&mut parents, 0);
+        let (connected, disconnected): (Vec<usize>, Vec<usize>) =
+            (0..rooms.len()).partition(|i| find_root(&mut parents, *i) == root);
+        if disconnected.is_empty() {
+            break;
+        }
+
+        let (a, b) = closest_pair(rooms, &connected, &disconnected);
+        let root_b = find_root(&mut parents, b);
+        parents[root_b] = root;
+        corridors.push((a, b));
+    }
+}
+
+fn carve(map: &mut Map, x: i32, y: i32) {
+    if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
+        let idx = map.xy_idx(x, y);
+        map.tiles[idx] = TileType::Floor;
+    }
+}
+
+fn apply_straight_corridor(map: &mut Map, start: (i32, i32), end: (i32, i32)) {
+    let line = rltk::line2d(
+        rltk::LineAlg::Bresenham,
+        Point::new(start.0, start.1),
+        Point::new(end.0, end.1),
+    );
+    for step in line.iter() {
+        carve(map, step.x, step.y);
+    }
+    carve(map, start.0, start.1);
+}
+
+fn apply_dog_leg_corridor(
+    map: &mut Map,
+    rng: &mut RandomNumberGenerator,
+    start: (i32, i32),
+    end: (i32, i32),
+) {
+    if rng.range(0, 2) == 1 {
+        common::apply_horizontal_tunnel(map, start.0, end.0, start.1);
+        common::apply_vertical_tunnel(map, start.1, end.1, end.0);
+    } else {
+        common::apply_vertical_tunnel(map, start.1, end.1, start.0);
+        common::apply_horizontal_tunnel(map, start.0, end.0, end.1);
+    }
+}
+
+/// A drunkard that mostly staggers towards its destination. If it runs out of steps, we finish
+/// the job with a dog-leg so that the rooms are still joined.
+fn apply_winding_corridor(
+    map: &mut Map,
+    rng: &mut RandomNumberGenerator,
+    start: (i32, i32),
+    end: (i32, i32),
+) {
+    let (mut x, mut y) = start;
+    let mut steps = 0;
+
+    while (x, y) != end && steps < MAX_WINDING_STEPS {
+        carve(map, x, y);
+
+        if rng.roll_dice(1, 4) == 1 {
+            // Stagger in a random direction
+            match rng.roll_dice(1, 4) {
+                1 => x -= 1,
+                2 => x += 1,
+                3 => y -= 1,
+                _ => y += 1,
+            }
+        } else if x != end.0 && (y == end.1 || rng.range(0, 2) == 1) {
+            x += (end.0 - x).signum();
+        } else {
+            y += (end.1 - y).signum();
+        }
+
+        x = i32::max(1, i32::min(map.width - 2, x));
+        y = i32::max(1, i32::min(map.height - 2, y));
+        steps += 1;
+    }
+
+    if (x, y) != end {
+        apply_dog_leg_corridor(map, rng, (x, y), end);
+    }
+    carve(map, end.0, end.1);
+}
@@ -1,5 +1,9 @@
-use super::{common, MapBuilder};
-use crate::{components::Position, map::Map, spawner, Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use super::{
+    common,
+    room_connections::{self, CorridorStyle, RoomConnection},
+    MapBuilder,
+};
+use crate::{components::Position, map::Map, spawner, Rect, TileType};
 use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
@@ -9,26 +13,26 @@ pub struct SimpleMapBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    record_history: bool,
 }
 
 impl SimpleMapBuilder {
-    pub fn new(new_depth: i32) -> SimpleMapBuilder {
+    pub fn new(new_depth: i32, width: i32, height: i32) -> SimpleMapBuilder {
         SimpleMapBuilder {
-            map: Map::new(new_depth),
+            map: Map::new(new_depth, width, height),
             starting_position: Position { x: 0, y: 0 },
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            record_history: false,
         }
     }
 
-    fn rooms_and_corridors(&mut self) {
+    fn rooms_and_corridors(&mut self, rng: &mut RandomNumberGenerator) {
         const MAX_ROOMS: i32 = 30;
         const MIN_SIZE: i32 = 6;
         const MAX_SIZE: i32 = 10;
 
-        let mut rng = RandomNumberGenerator::new();
-
         for _i in 0..MAX_ROOMS {
             let w = rng.range(MIN_SIZE, MAX_SIZE);
             let h = rng.range(MIN_SIZE, MAX_SIZE);
@@ -42,26 +46,27 @@ impl SimpleMapBuilder {
                 }
             }
             if ok {
-                self.take_snapshot();
                 common::apply_room_to_map(&mut self.map, &new_room);
-
-                if !self.rooms.is_empty() {
-                    let (new_x, new_y) = new_room.center();
-                    let (prev_x, prev_y) = self.rooms[self.rooms.len() - 1].center();
-                    if rng.range(0, 2) == 1 {
-                        common::apply_horizontal_tunnel(&mut self.map, prev_x, new_x, prev_y);
-                        common::apply_vertical_tunnel(&mut self.map, prev_y, new_y, new_x);
-                    } else {
-                        common::apply_vertical_tunnel(&mut self.map, prev_y, new_y, prev_x);
-                        common::apply_horizontal_tunnel(&mut self.map, prev_x, new_x, new_y);
-                    }
-                }
-
                 self.rooms.push(new_room);
                 self.take_snapshot();
             }
         }
 
+        // Join the rooms up, using a different strategy each time we build
+        let connection = RoomConnection::random(rng);
+        let corridor_style = CorridorStyle::random(rng);
+        let corridors = room_connections::connect_rooms(&self.rooms, connection, rng);
+        for (from, to) in corridors.iter() {
+            room_connections::dig_corridor(
+                &mut self.map,
+                rng,
+                corridor_style,
+                self.rooms[*from].center(),
+                self.rooms[*to].center(),
+            );
+            self.take_snapshot();
+        }
+
         let stairs_position = self.rooms[self.rooms.len() - 1].center();
         let stairs_idx = self.map.xy_idx(stairs_position.0, stairs_position.1);
         self.map.tiles[stairs_idx] = TileType::DownStairs;
@@ -75,8 +80,9 @@ impl SimpleMapBuilder {
 }
 
 impl MapBuilder for SimpleMapBuilder {
-    fn build_map(&mut self) {
-        self.rooms_and_corridors();
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.rooms_and_corridors(rng);
     }
 
     fn spawn_entities(&mut self, ecs: &mut World) {
@@ -98,7 +104,7 @@ impl MapBuilder for SimpleMapBuilder {
     }
 
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
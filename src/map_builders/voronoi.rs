@@ -0,0 +1,178 @@
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::{components::Position, spawner, Map, TileType};
+
+use super::{common, MapBuilder};
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum DistanceAlgorithm {
+    Pythagoras,
+    Manhattan,
+    Chebyshev,
+}
+
+pub struct VoronoiCellBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    history: Vec<Map>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
+    n_seeds: usize,
+    distance_algorithm: DistanceAlgorithm,
+}
+
+impl MapBuilder for VoronoiCellBuilder {
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.history.clone()
+    }
+
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.noise_areas.iter() {
+            spawner::spawn_region(ecs, area.1, self.depth);
+        }
+    }
+
+    fn take_snapshot(&mut self) {
+        if self.record_history {
+            let mut snapshot = self.map.clone();
+            for v in snapshot.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+            self.history.push(snapshot);
+        }
+    }
+}
+
+impl VoronoiCellBuilder {
+    pub fn new(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        distance_algorithm: DistanceAlgorithm,
+    ) -> VoronoiCellBuilder {
+        VoronoiCellBuilder {
+            map: Map::new(new_depth, width, height),
+            starting_position: Position { x: 0, y: 0 },
+            depth: new_depth,
+            history: Vec::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
+            n_seeds: 64,
+            distance_algorithm,
+        }
+    }
+
+    pub fn pythagoras(new_depth: i32, width: i32, height: i32) -> VoronoiCellBuilder {
+        VoronoiCellBuilder::new(new_depth, width, height, DistanceAlgorithm::Pythagoras)
+    }
+
+    pub fn manhattan(new_depth: i32, width: i32, height: i32) -> VoronoiCellBuilder {
+        VoronoiCellBuilder::new(new_depth, width, height, DistanceAlgorithm::Manhattan)
+    }
+
+    pub fn chebyshev(new_depth: i32, width: i32, height: i32) -> VoronoiCellBuilder {
+        VoronoiCellBuilder::new(new_depth, width, height, DistanceAlgorithm::Chebyshev)
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Scatter the seeds that each cell of the hive grows from
+        let mut voronoi_seeds: Vec<(usize, rltk::Point)> = Vec::new();
+        while voronoi_seeds.len() < self.n_seeds {
+            let vx = rng.roll_dice(1, self.map.width - 1);
+            let vy = rng.roll_dice(1, self.map.height - 1);
+            let vidx = self.map.xy_idx(vx, vy);
+            let candidate = (vidx, rltk::Point::new(vx, vy));
+            if !voronoi_seeds.contains(&candidate) {
+                voronoi_seeds.push(candidate);
+            }
+        }
+
+        // Every tile belongs to whichever seed is closest to it
+        let mut voronoi_distance = vec![(0, 0.0f32); self.n_seeds];
+        let mut voronoi_membership: Vec<i32> =
+            vec![0; self.map.width as usize * self.map.height as usize];
+        for (i, vid) in voronoi_membership.iter_mut().enumerate() {
+            let x = i as i32 % self.map.width;
+            let y = i as i32 / self.map.width;
+
+            for (seed, pos) in voronoi_seeds.iter().enumerate() {
+                let distance = match self.distance_algorithm {
+                    DistanceAlgorithm::Pythagoras => rltk::DistanceAlg::PythagorasSquared
+                        .distance2d(rltk::Point::new(x, y), pos.1),
+                    DistanceAlgorithm::Manhattan => {
+                        rltk::DistanceAlg::Manhattan.distance2d(rltk::Point::new(x, y), pos.1)
+                    }
+                    DistanceAlgorithm::Chebyshev => {
+                        rltk::DistanceAlg::Chebyshev.distance2d(rltk::Point::new(x, y), pos.1)
+                    }
+                };
+                voronoi_distance[seed] = (seed, distance);
+            }
+
+            voronoi_distance.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            *vid = voronoi_distance[0].0 as i32;
+        }
+
+        // Carve out the inside of each cell, leaving walls along the cell edges
+        for y in 1..self.map.height - 1 {
+            for x in 1..self.map.width - 1 {
+                let mut neighbors = 0;
+                let my_idx = self.map.xy_idx(x, y);
+                let my_seed = voronoi_membership[my_idx];
+                if voronoi_membership[self.map.xy_idx(x - 1, y)] != my_seed {
+                    neighbors += 1;
+                }
+                if voronoi_membership[self.map.xy_idx(x + 1, y)] != my_seed {
+                    neighbors += 1;
+                }
+                if voronoi_membership[self.map.xy_idx(x, y - 1)] != my_seed {
+                    neighbors += 1;
+                }
+                if voronoi_membership[self.map.xy_idx(x, y + 1)] != my_seed {
+                    neighbors += 1;
+                }
+
+                if neighbors < 2 {
+                    self.map.tiles[my_idx] = TileType::Floor;
+                }
+            }
+            self.take_snapshot();
+        }
+
+        // Find a starting point as close to the middle as we can
+        let start_idx = common::find_floor_nearest_center(&self.map)
+            .expect("Voronoi map has no floor to start on");
+        self.starting_position = Position {
+            x: start_idx as i32 % self.map.width,
+            y: start_idx as i32 / self.map.width,
+        };
+
+        // Find all tiles we can reach from the starting point
+        let exit_tile =
+            common::remove_unreachable_areas_returning_most_distant(&mut self.map, start_idx);
+        self.take_snapshot();
+
+        // Place the stairs
+        self.map.tiles[exit_tile] = TileType::DownStairs;
+        self.take_snapshot();
+
+        // Now we build a noise map for use in spawning entities later
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
+    }
+}
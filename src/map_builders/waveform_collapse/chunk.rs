@@ -0,0 +1,30 @@
+use crate::{Map, TileType};
+
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub struct MapChunk {
+    pub pattern: Vec<TileType>,
+    pub exits: [Vec<bool>; 4],
+    pub has_exits: bool,
+    pub compatible_with: [Vec<usize>; 4],
+}
+
+pub fn tile_idx_in_chunk(chunk_size: i32, x: i32, y: i32) -> usize {
+    ((y * chunk_size) + x) as usize
+}
+
+pub fn render_pattern_to_map(
+    map: &mut Map,
+    chunk: &MapChunk,
+    chunk_size: i32,
+    start_x: i32,
+    start_y: i32,
+) {
+    let mut i = 0usize;
+    for tile_y in 0..chunk_size {
+        for tile_x in 0..chunk_size {
+            let map_idx = map.xy_idx(start_x + tile_x, start_y + tile_y);
+            map.tiles[map_idx] = chunk.pattern[i];
+            i += 1;
+        }
+    }
+}
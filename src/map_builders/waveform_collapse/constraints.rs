@@ -0,0 +1,234 @@
+use super::chunk::{tile_idx_in_chunk, MapChunk};
+use crate::{Map, TileType};
+use std::collections::HashSet;
+
+const NORTH: usize = 0;
+const SOUTH: usize = 1;
+const WEST: usize = 2;
+const EAST: usize = 3;
+
+/// Slices the map into chunk_size squares, optionally adding the mirrored versions of each
+/// chunk so that the solver has more variety to work with.
+pub fn build_patterns(
+    map: &Map,
+    chunk_size: i32,
+    include_flipping: bool,
+    dedupe: bool,
+) -> Vec<Vec<TileType>> {
+    let chunks_x = map.width / chunk_size;
+    let chunks_y = map.height / chunk_size;
+    let mut patterns = Vec::new();
+
+    for cy in 0..chunks_y {
+        for cx in 0..chunks_x {
+            let start_x = cx * chunk_size;
+            let end_x = (cx + 1) * chunk_size;
+            let start_y = cy * chunk_size;
+            let end_y = (cy + 1) * chunk_size;
+
+            // Normal orientation
+            let mut pattern: Vec<TileType> = Vec::new();
+            for y in start_y..end_y {
+                for x in start_x..end_x {
+                    let idx = map.xy_idx(x, y);
+                    pattern.push(map.tiles[idx]);
+                }
+            }
+            patterns.push(pattern);
+
+            if include_flipping {
+                // Flip horizontal
+                let mut pattern: Vec<TileType> = Vec::new();
+                for y in start_y..end_y {
+                    for x in start_x..end_x {
+                        let idx = map.xy_idx(start_x + (end_x - 1 - x), y);
+                        pattern.push(map.tiles[idx]);
+                    }
+                }
+                patterns.push(pattern);
+
+                // Flip vertical
+                let mut pattern: Vec<TileType> = Vec::new();
+                for y in start_y..end_y {
+                    for x in start_x..end_x {
+                        let idx = map.xy_idx(x, start_y + (end_y - 1 - y));
+                        pattern.push(map.tiles[idx]);
+                    }
+                }
+                patterns.push(pattern);
+
+                // Flip both
+                let mut pattern: Vec<TileType> = Vec::new();
+                for y in start_y..end_y {
+                    for x in start_x..end_x {
+                        let idx = map.xy_idx(start_x + (end_x - 1 - x), start_y + (end_y - 1 - y));
+                        pattern.push(map.tiles[idx]);
+                    }
+                }
+                patterns.push(pattern);
+            }
+        }
+    }
+
+    if dedupe {
+        // Keep the first of each pattern, so the order doesn't change from run to run
+        let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+        patterns.retain(|pattern| seen.insert(pattern.clone()));
+    }
+
+    patterns
+}
+
+/// Works out where each pattern can be entered from, and which other patterns may sit next to
+/// it in each direction.
+pub fn patterns_to_constraints(patterns: Vec<Vec<TileType>>, chunk_size: i32) -> Vec<MapChunk> {
+    let mut constraints: Vec<MapChunk> = Vec::new();
+    for p in patterns {
+        let mut new_chunk = MapChunk {
+            pattern: p,
+            exits: [
+                vec![false; chunk_size as usize],
+                vec![false; chunk_size as usize],
+                vec![false; chunk_size as usize],
+                vec![false; chunk_size as usize],
+            ],
+            has_exits: true,
+            compatible_with: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+        };
+
+        let mut n_exits = 0;
+        for x in 0..chunk_size {
+            let north_idx = tile_idx_in_chunk(chunk_size, x, 0);
+            if new_chunk.pattern[north_idx] == TileType::Floor {
+                new_chunk.exits[NORTH][x as usize] = true;
+                n_exits += 1;
+            }
+
+            let south_idx = tile_idx_in_chunk(chunk_size, x, chunk_size - 1);
+            if new_chunk.pattern[south_idx] == TileType::Floor {
+                new_chunk.exits[SOUTH][x as usize] = true;
+                n_exits += 1;
+            }
+
+            let west_idx = tile_idx_in_chunk(chunk_size, 0, x);
+            if new_chunk.pattern[west_idx] == TileType::Floor {
+                new_chunk.exits[WEST][x as usize] = true;
+                n_exits += 1;
+            }
+
+            let east_idx = tile_idx_in_chunk(chunk_size, chunk_size - 1, x);
+            if new_chunk.pattern[east_idx] == TileType::Floor {
+                new_chunk.exits[EAST][x as usize] = true;
+                n_exits += 1;
+            }
+        }
+
+        if n_exits == 0 {
+            new_chunk.has_exits = false;
+        }
+
+        constraints.push(new_chunk);
+    }
+
+    // Build the compatibility matrix
+    let all_chunks = constraints.clone();
+    for c in constraints.iter_mut() {
+        for (j, potential) in all_chunks.iter().enumerate() {
+            // Solid chunks fit anywhere
+            if !c.has_exits || !potential.has_exits {
+                for compat in c.compatible_with.iter_mut() {
+                    compat.push(j);
+                }
+                continue;
+            }
+
+            for direction in 0..4 {
+                let opposite = match direction {
+                    NORTH => SOUTH,
+                    SOUTH => NORTH,
+                    WEST => EAST,
+                    _ => WEST,
+                };
+
+                let mut it_fits = false;
+                let mut has_any = false;
+                for (slot, can_enter) in c.exits[direction].iter().enumerate() {
+                    if *can_enter {
+                        has_any = true;
+                        if potential.exits[opposite][slot] {
+                            it_fits = true;
+                        }
+                    }
+                }
+
+                if it_fits {
+                    c.compatible_with[direction].push(j);
+                } else if !has_any && !potential.exits[opposite].iter().any(|e| *e) {
+                    // Neither side has an opening, so two walls can sit back to back
+                    c.compatible_with[direction].push(j);
+                }
+            }
+        }
+    }
+
+    constraints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_with_floor(width: i32, height: i32, floor: &[(i32, i32)]) -> Map {
+        let mut map = Map::new(1, width, height);
+        for (x, y) in floor.iter() {
+            let idx = map.xy_idx(*x, *y);
+            map.tiles[idx] = TileType::Floor;
+        }
+        map
+    }
+
+    #[test]
+    fn dedupe_keeps_the_first_of_each_pattern_in_a_stable_order() {
+        // A solid chunk, whose flips are all the same, and one with a single corner of floor
+        let map = map_with_floor(4, 2, &[(2, 0)]);
+        let patterns = build_patterns(&map, 2, true, true);
+        assert_eq!(patterns.len(), 5);
+        assert!(patterns[0].iter().all(|t| *t == TileType::Wall));
+        assert!(patterns[1][0] == TileType::Floor);
+        assert!(patterns == build_patterns(&map, 2, true, true));
+    }
+
+    #[test]
+    fn without_dedupe_every_orientation_is_kept() {
+        let map = map_with_floor(4, 2, &[(2, 0)]);
+        assert_eq!(build_patterns(&map, 2, true, false).len(), 8);
+        assert_eq!(build_patterns(&map, 2, false, false).len(), 2);
+    }
+
+    #[test]
+    fn openings_must_line_up_with_their_neighbours() {
+        // Open on the east, open on the west, and solid
+        let map = map_with_floor(6, 2, &[(1, 0), (1, 1), (2, 0), (2, 1)]);
+        let constraints = patterns_to_constraints(build_patterns(&map, 2, false, false), 2);
+
+        assert!(constraints[0].exits[EAST].iter().all(|e| *e));
+        assert!(!constraints[0].exits[WEST].iter().any(|e| *e));
+        assert!(constraints[0].compatible_with[EAST].contains(&1));
+        assert!(!constraints[0].compatible_with[EAST].contains(&0));
+        assert!(constraints[1].compatible_with[WEST].contains(&0));
+    }
+
+    #[test]
+    fn solid_chunks_fit_anywhere() {
+        let map = map_with_floor(6, 2, &[(1, 0), (1, 1), (2, 0), (2, 1)]);
+        let constraints = patterns_to_constraints(build_patterns(&map, 2, false, false), 2);
+
+        assert!(!constraints[2].has_exits);
+        for compatible in constraints[2].compatible_with.iter() {
+            assert_eq!(compatible, &vec![0, 1, 2]);
+        }
+        for chunk in constraints[..2].iter() {
+            assert!(chunk.compatible_with.iter().all(|c| c.contains(&2)));
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use super::{common, simple_map::SimpleMapBuilder, MapBuilder};
+use crate::{components::Position, spawner, Map, TileType};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+use std::collections::BTreeMap;
+
+mod chunk;
+use chunk::{render_pattern_to_map, MapChunk};
+mod constraints;
+use constraints::{build_patterns, patterns_to_constraints};
+mod solver;
+use solver::Solver;
+
+// The prefab is 16 tiles square, so 8 keeps its chunks aligned. Derived maps can use any size in
+// the range; smaller chunks give more variety, larger ones stay closer to the source.
+pub const DEFAULT_CHUNK_SIZE: i32 = 8;
+pub const MIN_CHUNK_SIZE: i32 = 6;
+pub const MAX_CHUNK_SIZE: i32 = 10;
+const MAX_ATTEMPTS: i32 = 10;
+const MIN_FLOOR_PERCENT: f32 = 0.2;
+
+/// A small cave-and-pillar layout that tiles cleanly, used when we don't have another map to
+/// learn from. '#' is wall, anything else is floor.
+pub const SAND_CAVES_PREFAB: &str = "\
+####..####..####
+#..............#
+#..###....###..#
+...#........#...
+...#...##...#...
+#......##......#
+##....####....##
+#......##......#
+#..............#
+####..#..#..####
+#.....#..#.....#
+#..##......##..#
+...##......##...
+#..............#
+#......##......#
+####..####..####";
+
+pub enum WaveformSource {
+    Derived(Box<dyn MapBuilder>),
+    Prefab(&'static str),
+}
+
+pub struct WaveformCollapseBuilder {
+    map: Map,
+    starting_position: Position,
+    depth: i32,
+    history: Vec<Map>,
+    record_history: bool,
+    noise_areas: BTreeMap<i32, Vec<usize>>,
+    source: WaveformSource,
+    chunk_size: i32,
+}
+
+impl MapBuilder for WaveformCollapseBuilder {
+    fn get_map(&self) -> Map {
+        self.map.clone()
+    }
+
+    fn get_starting_position(&self) -> Position {
+        self.starting_position.clone()
+    }
+
+    fn get_snapshot_history(&self) -> Vec<Map> {
+        self.history.clone()
+    }
+
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, record_history: bool) {
+        self.record_history = record_history;
+        self.build(rng);
+    }
+
+    fn spawn_entities(&mut self, ecs: &mut World) {
+        for area in self.noise_areas.iter() {
+            spawner::spawn_region(ecs, area.1, self.depth);
+        }
+    }
+
+    fn take_snapshot(&mut self) {
+        if self.record_history {
+            let mut snapshot = self.map.clone();
+            for v in snapshot.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+            self.history.push(snapshot);
+        }
+    }
+}
+
+impl WaveformCollapseBuilder {
+    pub fn new(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        source: WaveformSource,
+        chunk_size: i32,
+    ) -> WaveformCollapseBuilder {
+        WaveformCollapseBuilder {
+            map: Map::new(new_depth, width, height),
+            starting_position: Position { x: 0, y: 0 },
+            depth: new_depth,
+            history: Vec::new(),
+            record_history: false,
+            noise_areas: BTreeMap::new(),
+            source,
+            chunk_size,
+        }
+    }
+
+    /// Learns its patterns from whatever the given builder produces.
+    pub fn derived_map(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        builder: Box<dyn MapBuilder>,
+        chunk_size: i32,
+    ) -> WaveformCollapseBuilder {
+        WaveformCollapseBuilder::new(
+            new_depth,
+            width,
+            height,
+            WaveformSource::Derived(builder),
+            chunk_size,
+        )
+    }
+
+    /// Learns its patterns from an ASCII prefab, tiled across the whole map.
+    pub fn from_prefab(
+        new_depth: i32,
+        width: i32,
+        height: i32,
+        template: &'static str,
+        chunk_size: i32,
+    ) -> WaveformCollapseBuilder {
+        WaveformCollapseBuilder::new(
+            new_depth,
+            width,
+            height,
+            WaveformSource::Prefab(template),
+            chunk_size,
+        )
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator) {
+        // Build the map we are going to learn from
+        match &mut self.source {
+            WaveformSource::Derived(builder) => {
+                builder.build_map(rng, self.record_history);
+                self.map = builder.get_map();
+                self.history = builder.get_snapshot_history();
+            }
+            WaveformSource::Prefab(template) => {
+                load_prefab(&mut self.map, *template);
+            }
+        }
+        for t in self.map.tiles.iter_mut() {
+            if *t == TileType::DownStairs {
+                *t = TileType::Floor;
+            }
+        }
+        self.take_snapshot();
+        let source_map = self.map.clone();
+
+        let patterns = build_patterns(&source_map, self.chunk_size, true, true);
+        let constraints = patterns_to_constraints(patterns, self.chunk_size);
+        self.render_tile_gallery(&constraints);
+
+        // Keep solving until we get a map that is both possible and big enough to play on
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            self.map = Map::new(self.depth, self.map.width, self.map.height);
+            let mut solver = Solver::new(constraints.clone(), self.chunk_size, &self.map);
+            while !solver.iteration(&mut self.map, rng) {
+                self.take_snapshot();
+            }
+            self.wall_off_edges();
+            self.take_snapshot();
+
+            if solver.possible && self.place_start_and_stairs() {
+                break;
+            }
+
+            if attempts >= MAX_ATTEMPTS {
+                // Give up on collapsing, and use the map we learned from. If even that won't
+                // do, fall back on plain rooms and corridors, which always give a playable level.
+                self.map = source_map;
+                if !self.place_start_and_stairs() {
+                    let mut fallback =
+                        SimpleMapBuilder::new(self.depth, self.map.width, self.map.height);
+                    fallback.build_map(rng, false);
+                    self.map = fallback.get_map();
+                    self.starting_position = fallback.get_starting_position();
+                }
+                break;
+            }
+        }
+        self.take_snapshot();
+
+        // Now we build a noise map for use in spawning entities later
+        self.noise_areas = common::generate_voronoi_spawn_regions(&self.map, rng);
+    }
+
+    /// Chunks don't always line up with the map edges, so close the level in with a solid ring
+    /// of wall, and wall off any strip the chunks didn't reach.
+    fn wall_off_edges(&mut self) {
+        let covered_width = self.map.width / self.chunk_size * self.chunk_size;
+        let covered_height = self.map.height / self.chunk_size * self.chunk_size;
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                if x == 0 || y == 0 || x >= covered_width - 1 || y >= covered_height - 1 {
+                    let idx = self.map.xy_idx(x, y);
+                    self.map.tiles[idx] = TileType::Wall;
+                }
+            }
+        }
+    }
+
+    /// Culls unreachable areas and places the start and the stairs. Returns false if there
+    /// isn't enough connected floor left to make a decent level.
+    fn place_start_and_stairs(&mut self) -> bool {
+        let start_idx = match common::find_floor_nearest_center(&self.map) {
+            None => return false,
+            Some(idx) => idx,
+        };
+        self.starting_position = Position {
+            x: start_idx as i32 % self.map.width,
+            y: start_idx as i32 / self.map.width,
+        };
+
+        let exit_tile =
+            common::remove_unreachable_areas_returning_most_distant(&mut self.map, start_idx);
+        self.map.tiles[exit_tile] = TileType::DownStairs;
+
+        let floor_tile_count = self
+            .map
+            .tiles
+            .iter()
+            .filter(|a| **a != TileType::Wall)
+            .count();
+        let total_tiles = (self.map.width * self.map.height) as f32;
+        floor_tile_count as f32 >= total_tiles * MIN_FLOOR_PERCENT
+    }
+
+    fn render_tile_gallery(&mut self, constraints: &[MapChunk]) {
+        self.map = Map::new(0, self.map.width, self.map.height);
+        let mut x = 1;
+        let mut y = 1;
+        for chunk in constraints.iter() {
+            render_pattern_to_map(&mut self.map, chunk, self.chunk_size, x, y);
+
+            x += self.chunk_size + 1;
+            if x + self.chunk_size > self.map.width {
+                // Move to the next row
+                x = 1;
+                y += self.chunk_size + 1;
+
+                if y + self.chunk_size > self.map.height {
+                    // Move to the next page
+                    self.take_snapshot();
+                    self.map = Map::new(0, self.map.width, self.map.height);
+                    y = 1;
+                }
+            }
+        }
+        self.take_snapshot();
+    }
+}
+
+fn load_prefab(map: &mut Map, template: &str) {
+    let rows: Vec<Vec<char>> = template
+        .lines()
+        .map(|line| line.chars().collect())
+        .filter(|row: &Vec<char>| !row.is_empty())
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+
+    for y in 1..map.height - 1 {
+        let row = &rows[y as usize % rows.len()];
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            map.tiles[idx] = match row[x as usize % row.len()] {
+                '#' => TileType::Wall,
+                _ => TileType::Floor,
+            };
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::chunk::{render_pattern_to_map, MapChunk};
+use crate::Map;
+use rltk::RandomNumberGenerator;
+use std::collections::HashSet;
+
+pub struct Solver {
+    constraints: Vec<MapChunk>,
+    chunk_size: i32,
+    chunks: Vec<Option<usize>>,
+    chunks_x: usize,
+    chunks_y: usize,
+    remaining: Vec<(usize, i32)>, // (index, # neighbors)
+    pub possible: bool,
+}
+
+impl Solver {
+    pub fn new(constraints: Vec<MapChunk>, chunk_size: i32, map: &Map) -> Solver {
+        let chunks_x = (map.width / chunk_size) as usize;
+        let chunks_y = (map.height / chunk_size) as usize;
+        let mut remaining: Vec<(usize, i32)> = Vec::new();
+        for i in 0..(chunks_x * chunks_y) {
+            remaining.push((i, 0));
+        }
+
+        Solver {
+            constraints,
+            chunk_size,
+            chunks: vec![None; chunks_x * chunks_y],
+            chunks_x,
+            chunks_y,
+            remaining,
+            possible: true,
+        }
+    }
+
+    fn chunk_idx(&self, x: usize, y: usize) -> usize {
+        (y * self.chunks_x) + x
+    }
+
+    fn count_neighbors(&self, chunk_x: usize, chunk_y: usize) -> i32 {
+        let mut neighbors = 0;
+
+        if chunk_x > 0 && self.chunks[self.chunk_idx(chunk_x - 1, chunk_y)].is_some() {
+            neighbors += 1;
+        }
+        if chunk_x < self.chunks_x - 1
+            && self.chunks[self.chunk_idx(chunk_x + 1, chunk_y)].is_some()
+        {
+            neighbors += 1;
+        }
+        if chunk_y > 0 && self.chunks[self.chunk_idx(chunk_x, chunk_y - 1)].is_some() {
+            neighbors += 1;
+        }
+        if chunk_y < self.chunks_y - 1
+            && self.chunks[self.chunk_idx(chunk_x, chunk_y + 1)].is_some()
+        {
+            neighbors += 1;
+        }
+
+        neighbors
+    }
+
+    /// Places a single chunk. Returns true once the map is complete, or once we have hit a
+    /// spot where no chunk fits (in which case `possible` is cleared).
+    pub fn iteration(&mut self, map: &mut Map, rng: &mut RandomNumberGenerator) -> bool {
+        if self.remaining.is_empty() {
+            return true;
+        }
+
+        // Populate the neighbor count of the remaining list
+        let mut remain_copy = self.remaining.clone();
+        let mut neighbors_exist = false;
+        for r in remain_copy.iter_mut() {
+            let chunk_x = r.0 % self.chunks_x;
+            let chunk_y = r.0 / self.chunks_x;
+            let neighbor_count = self.count_neighbors(chunk_x, chunk_y);
+            if neighbor_count > 0 {
+                neighbors_exist = true;
+            }
+            r.1 = neighbor_count;
+        }
+        remain_copy.sort_by(|a, b| b.1.cmp(&a.1));
+        self.remaining = remain_copy;
+
+        // Work outwards from what we've already placed; if nothing has been placed, start anywhere
+        let remaining_index = if !neighbors_exist {
+            (rng.roll_dice(1, self.remaining.len() as i32) - 1) as usize
+        } else {
+            0usize
+        };
+        let chunk_index = self.remaining[remaining_index].0;
+        self.remaining.remove(remaining_index);
+
+        let chunk_x = chunk_index % self.chunks_x;
+        let chunk_y = chunk_index / self.chunks_x;
+
+        let mut options: Vec<Vec<usize>> = Vec::new();
+        if chunk_x > 0 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x - 1, chunk_y)] {
+                options.push(self.constraints[nt].compatible_with[3].clone());
+            }
+        }
+        if chunk_x < self.chunks_x - 1 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x + 1, chunk_y)] {
+                options.push(self.constraints[nt].compatible_with[2].clone());
+            }
+        }
+        if chunk_y > 0 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y - 1)] {
+                options.push(self.constraints[nt].compatible_with[1].clone());
+            }
+        }
+        if chunk_y < self.chunks_y - 1 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y + 1)] {
+                options.push(self.constraints[nt].compatible_with[0].clone());
+            }
+        }
+
+        let new_chunk_idx = if options.is_empty() {
+            // There is nothing nearby, so we can have anything!
+            (rng.roll_dice(1, self.constraints.len() as i32) - 1) as usize
+        } else {
+            // There are neighbors, so we have to be compatible with all of them
+            let mut options_to_check: HashSet<usize> = HashSet::new();
+            for o in options.iter() {
+                for i in o.iter() {
+                    options_to_check.insert(*i);
+                }
+            }
+
+            let mut possible_options: Vec<usize> = options_to_check
+                .into_iter()
+                .filter(|candidate| options.iter().all(|o| o.contains(candidate)))
+                .collect();
+            possible_options.sort_unstable();
+
+            if possible_options.is_empty() {
+                self.possible = false;
+                return true;
+            }
+
+            let selected = (rng.roll_dice(1, possible_options.len() as i32) - 1) as usize;
+            possible_options[selected]
+        };
+
+        self.chunks[chunk_index] = Some(new_chunk_idx);
+        render_pattern_to_map(
+            map,
+            &self.constraints[new_chunk_idx],
+            self.chunk_size,
+            chunk_x as i32 * self.chunk_size,
+            chunk_y as i32 * self.chunk_size,
+        );
+
+        false
+    }
+}
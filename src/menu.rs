@@ -1,11 +1,18 @@
-use super::RunState;
-use rltk::{Rltk, VirtualKeyCode, RGB};
+use super::{
+    controls::{Action, Controls},
+    RunState,
+};
+use rltk::{Rltk, RGB};
 use specs::World;
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
     NewGame,
     LoadGame,
+    HallOfFame,
+    Achievements,
+    Controls,
+    Settings,
     Quit,
 }
 
@@ -18,6 +25,7 @@ pub enum MainMenuResult {
 pub fn main_menu(ecs: &mut World, ctx: &mut Rltk) -> MainMenuResult {
     let save_exists = crate::systems::saveload_system::does_save_exist();
     let runstate = ecs.fetch::<RunState>();
+    let controls = ecs.fetch::<Controls>();
 
     ctx.print_color_centered(
         15,
@@ -64,35 +72,107 @@ pub fn main_menu(ecs: &mut World, ctx: &mut Rltk) -> MainMenuResult {
             }
         }
 
-        if selection == MainMenuSelection::Quit {
+        if selection == MainMenuSelection::HallOfFame {
+            ctx.print_color_centered(
+                26,
+                RGB::named(rltk::MAGENTA),
+                RGB::named(rltk::BLACK),
+                "Hall of Fame",
+            );
+        } else {
             ctx.print_color_centered(
                 26,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                "Hall of Fame",
+            );
+        }
+
+        if selection == MainMenuSelection::Achievements {
+            ctx.print_color_centered(
+                27,
+                RGB::named(rltk::MAGENTA),
+                RGB::named(rltk::BLACK),
+                "Achievements",
+            );
+        } else {
+            ctx.print_color_centered(
+                27,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                "Achievements",
+            );
+        }
+
+        if selection == MainMenuSelection::Controls {
+            ctx.print_color_centered(
+                28,
+                RGB::named(rltk::MAGENTA),
+                RGB::named(rltk::BLACK),
+                "Controls",
+            );
+        } else {
+            ctx.print_color_centered(
+                28,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                "Controls",
+            );
+        }
+
+        if selection == MainMenuSelection::Settings {
+            ctx.print_color_centered(
+                29,
+                RGB::named(rltk::MAGENTA),
+                RGB::named(rltk::BLACK),
+                "Settings",
+            );
+        } else {
+            ctx.print_color_centered(
+                29,
+                RGB::named(rltk::WHITE),
+                RGB::named(rltk::BLACK),
+                "Settings",
+            );
+        }
+
+        if selection == MainMenuSelection::Quit {
+            ctx.print_color_centered(
+                30,
                 RGB::named(rltk::MAGENTA),
                 RGB::named(rltk::BLACK),
                 "Quit",
             );
         } else {
-            ctx.print_color_centered(26, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "Quit");
+            ctx.print_color_centered(30, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "Quit");
         }
 
-        match ctx.key {
+        match controls.menu_action(ctx.key) {
             None => {
                 return MainMenuResult::NoSelection {
                     selected: selection,
                 }
             }
-            Some(key) => match key {
-                VirtualKeyCode::Escape => {
+            Some(action) => match action {
+                Action::MenuCancel => {
                     return MainMenuResult::NoSelection {
                         selected: MainMenuSelection::Quit,
                     }
                 }
-                VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
+                Action::MenuUp => {
                     let mut newselection;
                     match selection {
                         MainMenuSelection::NewGame => newselection = MainMenuSelection::Quit,
                         MainMenuSelection::LoadGame => newselection = MainMenuSelection::NewGame,
-                        MainMenuSelection::Quit => newselection = MainMenuSelection::LoadGame,
+                        MainMenuSelection::HallOfFame => newselection = MainMenuSelection::LoadGame,
+                        MainMenuSelection::Achievements => {
+                            newselection = MainMenuSelection::HallOfFame
+                        }
+                        MainMenuSelection::Controls => {
+                            newselection = MainMenuSelection::Achievements
+                        }
+                        MainMenuSelection::Settings => newselection = MainMenuSelection::Controls,
+                        MainMenuSelection::Quit => newselection = MainMenuSelection::Settings,
                     }
                     if newselection == MainMenuSelection::LoadGame && !save_exists {
                         newselection = MainMenuSelection::NewGame;
@@ -101,21 +181,29 @@ pub fn main_menu(ecs: &mut World, ctx: &mut Rltk) -> MainMenuResult {
                         selected: newselection,
                     };
                 }
-                VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
+                Action::MenuDown => {
                     let mut newselection;
                     match selection {
                         MainMenuSelection::NewGame => newselection = MainMenuSelection::LoadGame,
-                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::Quit,
+                        MainMenuSelection::LoadGame => newselection = MainMenuSelection::HallOfFame,
+                        MainMenuSelection::HallOfFame => {
+                            newselection = MainMenuSelection::Achievements
+                        }
+                        MainMenuSelection::Achievements => {
+                            newselection = MainMenuSelection::Controls
+                        }
+                        MainMenuSelection::Controls => newselection = MainMenuSelection::Settings,
+                        MainMenuSelection::Settings => newselection = MainMenuSelection::Quit,
                         MainMenuSelection::Quit => newselection = MainMenuSelection::NewGame,
                     }
                     if newselection == MainMenuSelection::LoadGame && !save_exists {
-                        newselection = MainMenuSelection::Quit;
+                        newselection = MainMenuSelection::HallOfFame;
                     }
                     return MainMenuResult::NoSelection {
                         selected: newselection,
                     };
                 }
-                VirtualKeyCode::Return => {
+                Action::MenuSelect => {
                     return MainMenuResult::Selected {
                         selected: selection,
                     }
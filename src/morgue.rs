@@ -0,0 +1,197 @@
+use super::{
+    components,
+    map::TileType,
+    scores,
+    spawner::{ARTIFACT_NAME, BOSS_NAME},
+    GameLog, Map,
+};
+use specs::prelude::*;
+use std::fs;
+
+const MORGUE_DIR: &str = "./morgue";
+
+// How much of the log makes it into the morgue file
+const MORGUE_LOG_LINES: usize = 50;
+
+/// Where the morgue file for the current run goes, named after the character and the seed.
+pub fn morgue_path(ecs: &World) -> String {
+    let player_entity = *ecs.fetch::<Entity>();
+    let name: String = ecs
+        .read_storage::<components::Name>()
+        .get(player_entity)
+        .map_or("player".to_string(), |n| n.name.clone())
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let seed = ecs
+        .read_storage::<components::RunStats>()
+        .get(player_entity)
+        .map_or(0, |stats| stats.seed);
+
+    format!("{}/{}-{}.txt", MORGUE_DIR, name, seed)
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum Outcome {
+    Died,
+    Won,
+}
+
+/// One line on how the run ended, for the score table.
+pub fn fate(ecs: &World, outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Died => format!("Killed by {}", cause_of_death(ecs)),
+        Outcome::Won => format!("Claimed the {}", ARTIFACT_NAME),
+    }
+}
+
+/// How the run went, as shown on the end screen and at the top of the morgue file.
+pub fn run_summary(ecs: &World, outcome: Outcome) -> Vec<String> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let names = ecs.read_storage::<components::Name>();
+    let run_stats = ecs.read_storage::<components::RunStats>();
+    let combat_stats = ecs.read_storage::<components::CombatStats>();
+    let mana = ecs.read_storage::<components::Mana>();
+    let attributes = ecs.read_storage::<components::Attributes>();
+    let equipped = ecs.read_storage::<components::Equipped>();
+    let backpack = ecs.read_storage::<components::InInventory>();
+
+    let mut lines: Vec<String> = Vec::new();
+    let name = names
+        .get(player_entity)
+        .map_or("Player".to_string(), |n| n.name.clone());
+    lines.push(match outcome {
+        Outcome::Died => format!(
+            "{} was killed by {} on depth {}.",
+            name,
+            cause_of_death(ecs),
+            map.depth
+        ),
+        Outcome::Won => format!(
+            "{} slew {} and claimed the {}.",
+            name, BOSS_NAME, ARTIFACT_NAME
+        ),
+    });
+
+    if let Some(stats) = run_stats.get(player_entity) {
+        lines.push(format!(
+            "Turns: {}    Score: {}    Seed: {}",
+            stats.turns,
+            scores::score(stats, outcome),
+            stats.seed
+        ));
+        lines.push(format!(
+            "Damage dealt {}, taken {}. Items used {}. Tiles explored {}.",
+            stats.damage_dealt, stats.damage_taken, stats.items_used, stats.tiles_explored
+        ));
+        let kills: Vec<String> = stats
+            .kills
+            .iter()
+            .map(|(monster, count)| format!("{} x{}", monster, count))
+            .collect();
+        if kills.is_empty() {
+            lines.push("Kills: none".to_string());
+        } else {
+            lines.push(format!("Kills: {}", kills.join(", ")));
+        }
+    }
+
+    if let Some(stats) = combat_stats.get(player_entity) {
+        let mana = mana.get(player_entity).map_or(0, |pool| pool.max_mana);
+        lines.push(format!(
+            "HP {}/{}  Power {}  Defense {}  Mana {}",
+            stats.hp, stats.max_hp, stats.power, stats.defense, mana
+        ));
+    }
+    if let Some(attributes) = attributes.get(player_entity) {
+        lines.push(format!(
+            "Strength {}  Agility {}  Toughness {}  Intellect {}",
+            attributes.strength, attributes.agility, attributes.toughness, attributes.intellect
+        ));
+    }
+
+    let worn: Vec<String> = (&entities, &equipped, &names)
+        .join()
+        .filter(|(_, item, _)| item.owner == player_entity)
+        .map(|(_, _, name)| name.name.clone())
+        .collect();
+    lines.push(format!("Equipment: {}", list_or_none(&worn)));
+    let carried: Vec<String> = (&entities, &backpack, &names)
+        .join()
+        .filter(|(_, item, _)| item.owner == player_entity)
+        .map(|(_, _, name)| name.name.clone())
+        .collect();
+    lines.push(format!("Inventory: {}", list_or_none(&carried)));
+
+    lines
+}
+
+/// Whatever last hurt the player.
+fn cause_of_death(ecs: &World) -> String {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.read_storage::<components::LastDamagedBy>()
+        .get(player_entity)
+        .map_or("unknown causes".to_string(), |last| last.cause.clone())
+}
+
+fn list_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Writes the summary, the end of the log and what the player had seen of the last level.
+pub fn write_morgue(ecs: &World, outcome: Outcome) {
+    let mut lines = run_summary(ecs, outcome);
+
+    lines.push(String::new());
+    lines.push("Last messages:".to_string());
+    {
+        let log = ecs.fetch::<GameLog>();
+        let start = log.entries.len().saturating_sub(MORGUE_LOG_LINES);
+        lines.extend(log.entries[start..].iter().cloned());
+    }
+
+    lines.push(String::new());
+    lines.push("Last map:".to_string());
+    lines.extend(map_dump(ecs));
+
+    // A full or read-only disk shouldn't take the game down with the character
+    let path = morgue_path(ecs);
+    if let Err(e) = fs::create_dir_all(MORGUE_DIR).and_then(|_| fs::write(&path, lines.join("\n")))
+    {
+        rltk::console::log(format!("Unable to write {}: {}", path, e));
+    }
+}
+
+fn map_dump(ecs: &World) -> Vec<String> {
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<rltk::Point>();
+
+    (0..map.height)
+        .map(|y| {
+            (0..map.width)
+                .map(|x| {
+                    let idx = map.xy_idx(x, y);
+                    if x == player_pos.x && y == player_pos.y {
+                        '@'
+                    } else if !map.revealed_tiles[idx] {
+                        ' '
+                    } else {
+                        match map.tiles[idx] {
+                            TileType::Wall => '#',
+                            TileType::Floor => '.',
+                            TileType::DownStairs => '>',
+                        }
+                    }
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
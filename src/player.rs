@@ -1,57 +1,271 @@
-use super::{components, map, GameLog, RunState, State, MAX_X, MAX_Y, MIN_X, MIN_Y};
-use rltk::{Point, Rltk, VirtualKeyCode};
+use super::{
+    camera, companions, components,
+    controls::{Action, ActionGroup, Controls},
+    effects::{EffectQueue, EffectType, Targets},
+    map,
+    systems::spell_system,
+    travel, GameLog, RunState, State, VIEWPORT_HEIGHT, VIEWPORT_WIDTH,
+};
+use rltk::{Point, Rltk};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
+// Taking the time to look around closely makes hidden traps easier to spot
+const SEARCH_BONUS: i32 = 5;
+
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    // Player movement
-    match ctx.key {
+    // Click on somewhere we've been to walk there
+    if ctx.left_click {
+        let (mouse_x, mouse_y) = ctx.mouse_pos();
+        if mouse_x < VIEWPORT_WIDTH && mouse_y < VIEWPORT_HEIGHT {
+            travel::stop_travel(&mut gs.ecs);
+            let destination = camera::screen_to_world(&gs.ecs, mouse_x, mouse_y);
+            return travel::travel_to(&mut gs.ecs, destination);
+        }
+    }
+
+    // Any key press interrupts travel, otherwise we keep going
+    if ctx.key.is_some() {
+        travel::stop_travel(&mut gs.ecs);
+    } else if let Some(run_state) = travel::continue_travel(&mut gs.ecs) {
+        return run_state;
+    }
+
+    let action = match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
-        Some(key) => match key {
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
-            VirtualKeyCode::I => return RunState::ShowInventory,
-            VirtualKeyCode::D => return RunState::ShowDropItem,
-            VirtualKeyCode::Escape => return RunState::SaveGame,
-            VirtualKeyCode::Period => {
-                if try_next_level(&mut gs.ecs) {
-                    return RunState::NextLevel;
-                }
-            }
-            VirtualKeyCode::R => return RunState::ShowRemoveItem,
-            VirtualKeyCode::Space | VirtualKeyCode::Numpad5 => return skip_turn(&mut gs.ecs),
+        Some(key) => gs
+            .ecs
+            .fetch::<Controls>()
+            .action_for(key, ActionGroup::Game),
+    };
 
-            // Cardinal Directions
-            VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-                try_move_player(-1, 0, &mut gs.ecs)
+    match action {
+        Some(Action::PickUp) => get_item(&mut gs.ecs),
+        Some(Action::Inventory) => return RunState::ShowInventory,
+        Some(Action::Drop) => return RunState::ShowDropItem,
+        Some(Action::SaveAndQuit) => return RunState::SaveGame,
+        Some(Action::Descend) => {
+            if try_next_level(&mut gs.ecs) {
+                return RunState::NextLevel;
             }
-
-            VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-                try_move_player(1, 0, &mut gs.ecs)
+        }
+        Some(Action::RemoveItem) => return RunState::ShowRemoveItem,
+        Some(Action::SearchDisarm) => return search_or_disarm(&mut gs.ecs),
+        Some(Action::TravelToStairs) => return travel::travel_to_stairs(&mut gs.ecs),
+        Some(Action::AutoExplore) => return travel::start_exploring(&mut gs.ecs),
+        Some(Action::Wait) => return skip_turn(&mut gs.ecs),
+        Some(Action::QuestLog) => return RunState::ShowQuestLog,
+        Some(Action::CommandCompanions) => {
+            if companions::has_companions(&gs.ecs) {
+                return RunState::ShowCompanionOrders;
             }
-
-            VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-                try_move_player(0, -1, &mut gs.ecs)
+            gs.ecs
+                .fetch_mut::<GameLog>()
+                .entries
+                .push("You have no companions to command.".to_string());
+            return RunState::AwaitingInput;
+        }
+        Some(Action::ShowControls) => {
+            return RunState::ShowControls {
+                selection: 0,
+                waiting_for_key: false,
+                conflict: None,
+                return_to_menu: false,
             }
+        }
 
-            VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-                try_move_player(0, 1, &mut gs.ecs)
-            }
+        // Spell hotbar
+        Some(Action::Spell1) => return cast_spell(&mut gs.ecs, 0),
+        Some(Action::Spell2) => return cast_spell(&mut gs.ecs, 1),
+        Some(Action::Spell3) => return cast_spell(&mut gs.ecs, 2),
+        Some(Action::Spell4) => return cast_spell(&mut gs.ecs, 3),
+        Some(Action::Spell5) => return cast_spell(&mut gs.ecs, 4),
+        Some(Action::Spell6) => return cast_spell(&mut gs.ecs, 5),
+        Some(Action::Spell7) => return cast_spell(&mut gs.ecs, 6),
+        Some(Action::Spell8) => return cast_spell(&mut gs.ecs, 7),
+        Some(Action::Spell9) => return cast_spell(&mut gs.ecs, 8),
 
-            // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::U => try_move_player(1, -1, &mut gs.ecs),
+        // Cardinal Directions
+        Some(Action::MoveWest) => return try_move_player(-1, 0, &mut gs.ecs),
+        Some(Action::MoveEast) => return try_move_player(1, 0, &mut gs.ecs),
+        Some(Action::MoveNorth) => return try_move_player(0, -1, &mut gs.ecs),
+        Some(Action::MoveSouth) => return try_move_player(0, 1, &mut gs.ecs),
 
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => try_move_player(-1, -1, &mut gs.ecs),
+        // Diagonals
+        Some(Action::MoveNorthEast) => return try_move_player(1, -1, &mut gs.ecs),
+        Some(Action::MoveNorthWest) => return try_move_player(-1, -1, &mut gs.ecs),
+        Some(Action::MoveSouthEast) => return try_move_player(1, 1, &mut gs.ecs),
+        Some(Action::MoveSouthWest) => return try_move_player(-1, 1, &mut gs.ecs),
 
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => try_move_player(1, 1, &mut gs.ecs),
+        _ => return RunState::AwaitingInput,
+    }
+    RunState::PlayerTurn
+}
 
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => try_move_player(-1, 1, &mut gs.ecs),
+fn cast_spell(ecs: &mut World, slot: usize) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let spell = match spell_system::known_spells(ecs, player_entity).get(slot) {
+        None => return RunState::AwaitingInput,
+        Some(spell) => *spell,
+    };
+
+    let mana_cost = ecs
+        .read_storage::<components::Spell>()
+        .get(spell)
+        .unwrap()
+        .mana_cost;
+    let mana = ecs
+        .read_storage::<components::Mana>()
+        .get(player_entity)
+        .map_or(0, |pool| pool.mana);
+    if mana < mana_cost {
+        let spell_name = ecs
+            .read_storage::<components::Name>()
+            .get(spell)
+            .unwrap()
+            .name
+            .clone();
+        ecs.fetch_mut::<GameLog>().entries.push(format!(
+            "You don't have enough mana to cast {}.",
+            spell_name
+        ));
+        return RunState::AwaitingInput;
+    }
 
-            _ => return RunState::AwaitingInput,
-        },
+    let ranged = ecs.read_storage::<components::Ranged>();
+    if let Some(ranged) = ranged.get(spell) {
+        return RunState::ShowTargeting {
+            range: ranged.range,
+            item: spell,
+        };
     }
+
+    ecs.write_storage::<components::WantsToCastSpell>()
+        .insert(
+            player_entity,
+            components::WantsToCastSpell {
+                spell,
+                target: None,
+            },
+        )
+        .expect("Unable to insert intent");
+    RunState::PlayerTurn
+}
+
+/// Tries to disarm a trap we know about next to us, and searches for hidden ones otherwise.
+fn search_or_disarm(ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let player_pos = *ecs.fetch::<Point>();
+    let skills = ecs
+        .read_storage::<components::Skills>()
+        .get(player_entity)
+        .cloned()
+        .unwrap_or(components::Skills {
+            perception: 0,
+            disarm: 0,
+        });
+
+    let mut known_traps: Vec<Entity> = Vec::new();
+    let mut hidden_traps: Vec<Entity> = Vec::new();
+    {
+        let map = ecs.fetch::<map::Map>();
+        let traps = ecs.read_storage::<components::Trap>();
+        let hidden = ecs.read_storage::<components::Invisible>();
+        for x in player_pos.x - 1..=player_pos.x + 1 {
+            for y in player_pos.y - 1..=player_pos.y + 1 {
+                if x < 0 || x >= map.width || y < 0 || y >= map.height {
+                    continue;
+                }
+                for entity in map.tile_content[map.xy_idx(x, y)].iter() {
+                    if traps.get(*entity).is_none() {
+                        continue;
+                    }
+                    if hidden.get(*entity).is_some() {
+                        hidden_traps.push(*entity);
+                    } else {
+                        known_traps.push(*entity);
+                    }
+                }
+            }
+        }
+    }
+
+    match known_traps.first() {
+        Some(trap) => disarm_trap(ecs, *trap, skills.disarm),
+        None => {
+            let mut found_any = false;
+            for trap in hidden_traps {
+                let spot_difficulty = ecs
+                    .read_storage::<components::Trap>()
+                    .get(trap)
+                    .unwrap()
+                    .spot_difficulty;
+                let roll = ecs
+                    .write_resource::<rltk::RandomNumberGenerator>()
+                    .roll_dice(1, 20);
+                if roll + skills.perception + SEARCH_BONUS >= spot_difficulty {
+                    let name = trap_name(ecs, trap);
+                    ecs.write_storage::<components::Invisible>().remove(trap);
+                    ecs.fetch_mut::<GameLog>()
+                        .entries
+                        .push(format!("You spotted a {}.", name));
+                    found_any = true;
+                }
+            }
+            if !found_any {
+                ecs.fetch_mut::<GameLog>()
+                    .entries
+                    .push("You search the area, but find nothing.".to_string());
+            }
+        }
+    }
+
     RunState::PlayerTurn
 }
 
+fn disarm_trap(ecs: &mut World, trap: Entity, disarm_skill: i32) {
+    let disarm_difficulty = ecs
+        .read_storage::<components::Trap>()
+        .get(trap)
+        .unwrap()
+        .disarm_difficulty;
+    let roll = ecs
+        .write_resource::<rltk::RandomNumberGenerator>()
+        .roll_dice(1, 20);
+    let name = trap_name(ecs, trap);
+
+    if roll + disarm_skill >= disarm_difficulty {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You disarm the {}.", name));
+        ecs.entities().delete(trap).expect("Unable to delete trap");
+    } else if roll == 1 {
+        // A botched attempt sets it off in your face
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You fumble, and set off the {}!", name));
+        let player_entity = *ecs.fetch::<Entity>();
+        ecs.write_resource::<EffectQueue>().add_effect(
+            None,
+            Some(trap),
+            EffectType::TriggerFire { trigger: trap },
+            Targets::Single {
+                target: player_entity,
+            },
+        );
+    } else {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You fail to disarm the {}.", name));
+    }
+}
+
+fn trap_name(ecs: &World, trap: Entity) -> String {
+    ecs.read_storage::<components::Name>()
+        .get(trap)
+        .map_or_else(|| "trap".to_string(), |n| n.name.clone())
+}
+
 pub fn try_next_level(ecs: &mut World) -> bool {
     let player_pos = ecs.fetch::<Point>();
     let map = ecs.fetch::<map::Map>();
@@ -99,16 +313,39 @@ fn skip_turn(ecs: &mut World) -> RunState {
         }
     }
 
+    let hydration_clocks = ecs.read_storage::<components::HydrationClock>();
+    if let Some(clock) = hydration_clocks.get(*player_entity) {
+        match clock.state {
+            components::HydrationState::Thirsty => can_heal = false,
+            components::HydrationState::Parched => can_heal = false,
+            _ => {}
+        }
+    }
+
+    if ecs
+        .read_storage::<components::FoodPoisoning>()
+        .get(*player_entity)
+        .is_some()
+    {
+        can_heal = false;
+    }
+
     if can_heal {
         let mut health_components = ecs.write_storage::<components::CombatStats>();
         let player_hp = health_components.get_mut(*player_entity).unwrap();
         player_hp.hp = i32::min(player_hp.hp + 1, player_hp.max_hp);
+
+        let mut mana_pools = ecs.write_storage::<components::Mana>();
+        if let Some(pool) = mana_pools.get_mut(*player_entity) {
+            pool.mana = i32::min(pool.mana + 1, pool.max_mana);
+        }
     }
 
     RunState::PlayerTurn
 }
 
-pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+/// Moves the player, or attacks or talks to whoever is in the way. Companions swap places.
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
     let mut positions = ecs.write_storage::<components::Position>();
     let mut players = ecs.write_storage::<components::Player>();
     let mut viewsheds = ecs.write_storage::<components::Viewshed>();
@@ -116,16 +353,58 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let entities = ecs.entities();
     let mut wants_to_melee = ecs.write_storage::<components::WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<components::EntityMoved>();
-    let map = ecs.fetch::<map::Map>();
+    let entangled = ecs.read_storage::<components::Entangled>();
+    let npcs = ecs.read_storage::<components::Npc>();
+    let companions = ecs.read_storage::<components::Companion>();
+    let water_sources = ecs.read_storage::<components::WaterSource>();
+    let mut wants_to_drink = ecs.write_storage::<components::WantsToDrink>();
+    let mut map = ecs.fetch_mut::<map::Map>();
+    let mut swapped_with: Option<(Entity, i32, i32)> = None;
 
     for (entity, _player, pos, viewshed) in
         (&entities, &mut players, &mut positions, &mut viewsheds).join()
     {
+        if pos.x + delta_x < 0
+            || pos.x + delta_x > map.width - 1
+            || pos.y + delta_y < 0
+            || pos.y + delta_y > map.height - 1
+        {
+            return RunState::PlayerTurn;
+        }
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
         let entities_at_destination = &map.tile_content[destination_idx];
 
+        let mut companion_in_the_way = None;
         for potential_target in entities_at_destination {
+            if npcs.get(*potential_target).is_some() {
+                return RunState::Talking {
+                    npc: *potential_target,
+                    node: 0,
+                };
+            }
+            // Companions trade places with you rather than blocking the way
+            if companions.get(*potential_target).is_some() {
+                if entangled.get(*potential_target).is_some() {
+                    ecs.fetch_mut::<GameLog>()
+                        .entries
+                        .push("Your companion is stuck fast in the sand.".to_string());
+                    return RunState::PlayerTurn;
+                }
+                companion_in_the_way = Some(*potential_target);
+                break;
+            }
+            if water_sources.get(*potential_target).is_some() {
+                wants_to_drink
+                    .insert(
+                        entity,
+                        components::WantsToDrink {
+                            source: *potential_target,
+                        },
+                    )
+                    .expect("Unable to insert want to drink");
+                return RunState::PlayerTurn;
+            }
             let target = combat_stats.get(*potential_target);
             match target {
                 None => {}
@@ -138,14 +417,24 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                             },
                         )
                         .expect("Add Target Failed");
-                    return; // So we don't move after attacking
+                    return RunState::PlayerTurn; // So we don't move after attacking
                 }
             }
         }
 
-        if !map.blocked[destination_idx] {
-            pos.x = min(MAX_X, max(MIN_X, pos.x + delta_x));
-            pos.y = min(MAX_Y, max(MIN_Y, pos.y + delta_y));
+        if entangled.get(entity).is_some() {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push("You struggle against the sand, but can't pull free.".to_string());
+            return RunState::PlayerTurn;
+        }
+
+        if !map.blocked[destination_idx] || companion_in_the_way.is_some() {
+            if let Some(companion) = companion_in_the_way {
+                swapped_with = Some((companion, pos.x, pos.y));
+            }
+            pos.x = min(map.width - 1, max(0, pos.x + delta_x));
+            pos.y = min(map.height - 1, max(0, pos.y + delta_y));
 
             viewshed.dirty = true;
             let mut ppos = ecs.write_resource::<Point>();
@@ -157,6 +446,33 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                 .expect("Failed to insert entity moved");
         }
     }
+
+    // The player's position is borrowed by the loop, so the companion moves afterwards
+    if let Some((companion, x, y)) = swapped_with {
+        if let Some(pos) = positions.get_mut(companion) {
+            pos.x = x;
+            pos.y = y;
+        }
+
+        // Both tiles stay occupied, but until the map is reindexed we must say who is where
+        let player_entity = *ecs.fetch::<Entity>();
+        let player_pos = *ecs.fetch::<Point>();
+        let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+        let companion_idx = map.xy_idx(x, y);
+        map.tile_content[player_idx].retain(|e| *e != companion);
+        map.tile_content[player_idx].push(player_entity);
+        map.tile_content[companion_idx].retain(|e| *e != player_entity);
+        map.tile_content[companion_idx].push(companion);
+
+        if let Some(viewshed) = viewsheds.get_mut(companion) {
+            viewshed.dirty = true;
+        }
+        entity_moved
+            .insert(companion, components::EntityMoved {})
+            .expect("Failed to insert entity moved");
+    }
+
+    RunState::PlayerTurn
 }
 
 fn get_item(ecs: &mut World) {
@@ -0,0 +1,297 @@
+use super::{
+    components,
+    controls::{Action, Controls},
+    spawner, GameLog,
+};
+use rltk::{Rltk, RGB};
+use serde::Deserialize;
+use specs::prelude::*;
+
+const QUEST_DATA: &str = include_str!("../resources/quests.json");
+
+#[derive(Deserialize)]
+pub enum Goal {
+    Kill { monster: String, count: i32 },
+    Fetch { item: String },
+    ReachDepth { depth: i32 },
+}
+
+#[derive(Deserialize)]
+pub struct Quest {
+    pub name: String,
+    pub giver: String,
+    pub description: String,
+    pub goal: Goal,
+    pub reward: Vec<String>,
+}
+
+/// Every quest an NPC can hand out. Read from `resources/quests.json`, which is built into the
+/// binary; what the player has actually taken on lives in their `QuestLog`.
+pub struct QuestBook {
+    quests: Vec<Quest>,
+}
+
+impl QuestBook {
+    pub fn load() -> QuestBook {
+        QuestBook {
+            quests: serde_json::from_str(QUEST_DATA).expect("Unable to parse quest data"),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&Quest> {
+        self.quests.iter().find(|q| q.name == name)
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum QuestState {
+    Available,
+    Active,
+    Ready,
+    Done,
+}
+
+/// How far along the goal the player is, and how far they need to get.
+fn progress(ecs: &World, quest: &Quest, entry: &components::QuestProgress) -> (i32, i32) {
+    let player_entity = *ecs.fetch::<Entity>();
+    match &quest.goal {
+        Goal::Kill { monster, count } => {
+            let kills = ecs
+                .read_storage::<components::RunStats>()
+                .get(player_entity)
+                .map_or(0, |stats| stats.kills.get(monster).copied().unwrap_or(0));
+            (i32::min(kills - entry.baseline, *count), *count)
+        }
+        Goal::Fetch { item } => (i32::from(carried_item(ecs, item).is_some()), 1),
+        Goal::ReachDepth { depth } => {
+            let deepest = ecs
+                .read_storage::<components::RunStats>()
+                .get(player_entity)
+                .map_or(0, |stats| stats.deepest_depth);
+            (i32::min(deepest, *depth), *depth)
+        }
+    }
+}
+
+/// Something with this name the player is carrying or wearing.
+fn carried_item(ecs: &World, item_name: &str) -> Option<Entity> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let names = ecs.read_storage::<components::Name>();
+    let backpack = ecs.read_storage::<components::InInventory>();
+    let equipped = ecs.read_storage::<components::Equipped>();
+
+    (&entities, &names)
+        .join()
+        .filter(|(item, name)| {
+            name.name == item_name
+                && (backpack.get(*item).map(|b| b.owner) == Some(player_entity)
+                    || equipped.get(*item).map(|e| e.owner) == Some(player_entity))
+        })
+        .map(|(item, _)| item)
+        .next()
+}
+
+pub fn quest_state(ecs: &World, quest_name: &str) -> QuestState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let book = ecs.fetch::<QuestBook>();
+    let logs = ecs.read_storage::<components::QuestLog>();
+    let (quest, entry) = match (
+        book.get(quest_name),
+        logs.get(player_entity)
+            .and_then(|log| log.quests.iter().find(|q| q.name == quest_name)),
+    ) {
+        (Some(quest), Some(entry)) => (quest, entry),
+        _ => return QuestState::Available,
+    };
+
+    if entry.turned_in {
+        return QuestState::Done;
+    }
+    let (current, needed) = progress(ecs, quest, entry);
+    if current >= needed {
+        QuestState::Ready
+    } else {
+        QuestState::Active
+    }
+}
+
+pub fn accept(ecs: &mut World, quest_name: &str) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let baseline = match ecs.fetch::<QuestBook>().get(quest_name).map(|q| &q.goal) {
+        // Only kills from here on count
+        Some(Goal::Kill { monster, .. }) => ecs
+            .read_storage::<components::RunStats>()
+            .get(player_entity)
+            .map_or(0, |stats| stats.kills.get(monster).copied().unwrap_or(0)),
+        _ => 0,
+    };
+
+    if let Some(log) = ecs
+        .write_storage::<components::QuestLog>()
+        .get_mut(player_entity)
+    {
+        log.quests.push(components::QuestProgress {
+            name: quest_name.to_string(),
+            baseline,
+            turned_in: false,
+            announced: false,
+        });
+    }
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("New quest: {}.", quest_name));
+}
+
+pub fn turn_in(ecs: &mut World, quest_name: &str) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let (fetched, reward) = {
+        let book = ecs.fetch::<QuestBook>();
+        let quest = match book.get(quest_name) {
+            Some(quest) => quest,
+            None => return,
+        };
+        let fetched = match &quest.goal {
+            Goal::Fetch { item } => carried_item(ecs, item),
+            _ => None,
+        };
+        (fetched, quest.reward.clone())
+    };
+
+    // Whatever we were sent to fetch stays with the quest giver
+    if let Some(item) = fetched {
+        ecs.delete_entity(item)
+            .expect("Unable to delete quest item");
+    }
+    if let Some(log) = ecs
+        .write_storage::<components::QuestLog>()
+        .get_mut(player_entity)
+    {
+        if let Some(entry) = log.quests.iter_mut().find(|q| q.name == quest_name) {
+            entry.turned_in = true;
+        }
+    }
+    // Rewards are listed by spawn name, which isn't always what the item is called
+    let given: Vec<Entity> = reward
+        .iter()
+        .filter_map(|item| spawner::give_named_item(ecs, player_entity, item))
+        .collect();
+    let names = ecs.read_storage::<components::Name>();
+    let received: Vec<String> = given
+        .iter()
+        .filter_map(|item| names.get(*item).map(|n| n.name.clone()))
+        .collect();
+
+    ecs.fetch_mut::<GameLog>().entries.push(format!(
+        "Quest complete: {}. You receive {}.",
+        quest_name,
+        received.join(", ")
+    ));
+}
+
+/// Lets the player know once a quest is ready to hand in.
+pub fn announce_progress(ecs: &World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let book = ecs.fetch::<QuestBook>();
+    let mut logs = ecs.write_storage::<components::QuestLog>();
+    let log = match logs.get_mut(player_entity) {
+        Some(log) => log,
+        None => return,
+    };
+
+    for entry in log.quests.iter_mut() {
+        if entry.turned_in || entry.announced {
+            continue;
+        }
+        if let Some(quest) = book.get(&entry.name) {
+            let (current, needed) = progress(ecs, quest, entry);
+            if current >= needed {
+                entry.announced = true;
+                ecs.fetch_mut::<GameLog>().entries.push(format!(
+                    "{} is done. Return to a {}.",
+                    quest.name, quest.giver
+                ));
+            }
+        }
+    }
+}
+
+pub fn show_quest_log(ecs: &World, ctx: &mut Rltk) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    let controls = ecs.fetch::<Controls>();
+    let book = ecs.fetch::<QuestBook>();
+    let logs = ecs.read_storage::<components::QuestLog>();
+
+    ctx.draw_box(
+        10,
+        8,
+        60,
+        32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        13,
+        8,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Quest Log",
+    );
+
+    let mut y = 10;
+    let entries = logs
+        .get(player_entity)
+        .map_or(&[][..], |log| &log.quests[..]);
+    if entries.is_empty() {
+        ctx.print_color(
+            12,
+            y,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            "You haven't taken on any quests.",
+        );
+    }
+    for entry in entries.iter() {
+        let quest = match book.get(&entry.name) {
+            Some(quest) => quest,
+            None => continue,
+        };
+        let (current, needed) = progress(ecs, quest, entry);
+        let (status, fg) = if entry.turned_in {
+            ("done".to_string(), RGB::named(rltk::GREY))
+        } else if current >= needed {
+            (
+                format!("return to a {}", quest.giver),
+                RGB::named(rltk::GREEN),
+            )
+        } else {
+            (format!("{}/{}", current, needed), RGB::named(rltk::WHITE))
+        };
+        ctx.print_color(12, y, fg, RGB::named(rltk::BLACK), &quest.name);
+        ctx.print_color(
+            68 - status.len() as i32,
+            y,
+            fg,
+            RGB::named(rltk::BLACK),
+            &status,
+        );
+        ctx.print_color(
+            14,
+            y + 1,
+            RGB::named(rltk::GREY),
+            RGB::named(rltk::BLACK),
+            &quest.description,
+        );
+        y += 3;
+    }
+
+    ctx.print_color(
+        13,
+        39,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        format!("{} to close", controls.describe_keys(Action::MenuCancel)),
+    );
+
+    controls.menu_action(ctx.key) == Some(Action::MenuCancel)
+}
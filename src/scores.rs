@@ -0,0 +1,227 @@
+use super::{
+    components,
+    morgue::{self, Outcome},
+};
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCORES_FILE: &str = "./scores.json";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: i32,
+    pub fate: String,
+    pub depth: i32,
+    pub turns: i32,
+    pub kills: i32,
+    pub seed: u64,
+    // Seconds since the Unix epoch
+    pub date: u64,
+    // The rest of the run's statistics. Older score files don't have them.
+    #[serde(default)]
+    pub kills_by_monster: BTreeMap<String, i32>,
+    #[serde(default)]
+    pub damage_dealt: i32,
+    #[serde(default)]
+    pub damage_taken: i32,
+    #[serde(default)]
+    pub items_used: i32,
+    #[serde(default)]
+    pub meals_eaten: i32,
+    #[serde(default)]
+    pub tiles_explored: i32,
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ScoreOrder {
+    Score,
+    Date,
+}
+
+impl ScoreOrder {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ScoreOrder::Score => "score",
+            ScoreOrder::Date => "date",
+        }
+    }
+
+    pub fn next(&self) -> ScoreOrder {
+        match self {
+            ScoreOrder::Score => ScoreOrder::Date,
+            ScoreOrder::Date => ScoreOrder::Score,
+        }
+    }
+}
+
+/// Every finished run, as read from `scores.json` when the Hall of Fame is opened.
+pub struct HallOfFame {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl HallOfFame {
+    pub fn load() -> HallOfFame {
+        if !Path::new(SCORES_FILE).exists() {
+            return HallOfFame {
+                entries: Vec::new(),
+            };
+        }
+
+        let data = fs::read_to_string(SCORES_FILE).expect("Unable to read scores file");
+        let entries = match serde_json::from_str(&data) {
+            Ok(entries) => entries,
+            Err(e) => {
+                rltk::console::log(format!("Ignoring broken {}: {}", SCORES_FILE, e));
+                Vec::new()
+            }
+        };
+        HallOfFame { entries }
+    }
+
+    fn save(&self) {
+        let data = serde_json::to_string_pretty(&self.entries).expect("Unable to serialize scores");
+        if let Err(e) = fs::write(SCORES_FILE, data) {
+            rltk::console::log(format!("Unable to write {}: {}", SCORES_FILE, e));
+        }
+    }
+
+    pub fn sort(&mut self, order: ScoreOrder) {
+        match order {
+            ScoreOrder::Score => self.entries.sort_by(|a, b| b.score.cmp(&a.score)),
+            ScoreOrder::Date => self.entries.sort_by(|a, b| b.date.cmp(&a.date)),
+        }
+    }
+}
+
+// Winning beats everything
+const VICTORY_BONUS: i32 = 5000;
+
+// Going deeper is worth the most, then fighting, then poking into every corner
+pub fn score(stats: &components::RunStats, outcome: Outcome) -> i32 {
+    let kills: i32 = stats.kills.values().sum();
+    let bonus = if outcome == Outcome::Won {
+        VICTORY_BONUS
+    } else {
+        0
+    };
+    stats.deepest_depth * 100 + kills * 10 + stats.damage_dealt + stats.tiles_explored / 10 + bonus
+}
+
+/// Adds the run that just ended to the score table.
+pub fn record_score(ecs: &World, outcome: Outcome) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let run_stats = ecs.read_storage::<components::RunStats>();
+    let stats = match run_stats.get(player_entity) {
+        Some(stats) => stats,
+        None => return,
+    };
+    let name = ecs
+        .read_storage::<components::Name>()
+        .get(player_entity)
+        .map_or("Player".to_string(), |n| n.name.clone());
+
+    let mut hall_of_fame = HallOfFame::load();
+    hall_of_fame.entries.push(ScoreEntry {
+        name,
+        score: score(stats, outcome),
+        fate: morgue::fate(ecs, outcome),
+        depth: stats.deepest_depth,
+        turns: stats.turns,
+        kills: stats.kills.values().sum(),
+        seed: stats.seed,
+        date: timestamp(),
+        kills_by_monster: stats.kills.clone(),
+        damage_dealt: stats.damage_dealt,
+        damage_taken: stats.damage_taken,
+        items_used: stats.items_used,
+        meals_eaten: stats.meals_eaten,
+        tiles_explored: stats.tiles_explored,
+    });
+    hall_of_fame.save();
+}
+
+// Seconds since the Unix epoch
+pub fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Formats a timestamp as YYYY-MM-DD (UTC), using the civil-from-days algorithm so we don't
+/// need a date crate.
+pub fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: i32, date: u64) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            score,
+            fate: String::new(),
+            depth: 1,
+            turns: 0,
+            kills: 0,
+            seed: 0,
+            date,
+            kills_by_monster: BTreeMap::new(),
+            damage_dealt: 0,
+            damage_taken: 0,
+            items_used: 0,
+            meals_eaten: 0,
+            tiles_explored: 0,
+        }
+    }
+
+    fn names(hall: &HallOfFame) -> Vec<&str> {
+        hall.entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    fn hall() -> HallOfFame {
+        HallOfFame {
+            entries: vec![
+                entry("Paul", 50, 300),
+                entry("Jessica", 120, 100),
+                entry("Stilgar", 50, 200),
+                entry("Chani", 80, 400),
+            ],
+        }
+    }
+
+    #[test]
+    fn best_scores_come_first() {
+        let mut hall = hall();
+        hall.sort(ScoreOrder::Score);
+        assert_eq!(names(&hall), vec!["Jessica", "Chani", "Paul", "Stilgar"]);
+    }
+
+    #[test]
+    fn latest_runs_come_first() {
+        let mut hall = hall();
+        hall.sort(ScoreOrder::Date);
+        assert_eq!(names(&hall), vec!["Chani", "Paul", "Stilgar", "Jessica"]);
+    }
+}
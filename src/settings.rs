@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const SETTINGS_FILE: &str = "./settings.json";
+
+// In the order they are registered with the context in main, so the position is the font index
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Font {
+    Terminal8x8,
+    Vga8x16,
+}
+
+impl Font {
+    pub const ALL: [Font; 2] = [Font::Terminal8x8, Font::Vga8x16];
+
+    pub fn file(&self) -> &'static str {
+        match self {
+            Font::Terminal8x8 => "terminal8x8.jpg",
+            Font::Vga8x16 => "vga8x16.jpg",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        Font::ALL.iter().position(|f| f == self).unwrap()
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum Setting {
+    MapgenVisualizer,
+    Scanlines,
+    Font,
+    Fullscreen,
+    LogVerbosity,
+    AutoExplorePickup,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 6] = [
+        Setting::MapgenVisualizer,
+        Setting::Scanlines,
+        Setting::Font,
+        Setting::Fullscreen,
+        Setting::LogVerbosity,
+        Setting::AutoExplorePickup,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Setting::MapgenVisualizer => "Show map generation",
+            Setting::Scanlines => "CRT scanlines",
+            Setting::Font => "Font",
+            Setting::Fullscreen => "Fullscreen",
+            Setting::LogVerbosity => "Log",
+            Setting::AutoExplorePickup => "Auto-explore picks up items",
+        }
+    }
+}
+
+/// Player preferences. Loaded from `settings.json` at startup and written back whenever they
+/// change on the settings screen; anything missing from the file keeps its default.
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub show_mapgen_visualizer: bool,
+    pub scanlines: bool,
+    pub font: Font,
+    pub fullscreen: bool,
+    pub verbose_log: bool,
+    pub auto_explore_pickup: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            show_mapgen_visualizer: true,
+            scanlines: true,
+            font: Font::Terminal8x8,
+            fullscreen: false,
+            verbose_log: true,
+            auto_explore_pickup: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        if !Path::new(SETTINGS_FILE).exists() {
+            let settings = Settings::default();
+            settings.save();
+            return settings;
+        }
+
+        let data = fs::read_to_string(SETTINGS_FILE).expect("Unable to read settings file");
+        match serde_json::from_str(&data) {
+            Ok(settings) => settings,
+            Err(e) => {
+                rltk::console::log(format!("Ignoring broken {}: {}", SETTINGS_FILE, e));
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let data = serde_json::to_string_pretty(self).expect("Unable to serialize settings");
+        if let Err(e) = fs::write(SETTINGS_FILE, data) {
+            rltk::console::log(format!("Unable to write {}: {}", SETTINGS_FILE, e));
+        }
+    }
+
+    pub fn toggle(&mut self, setting: Setting) {
+        match setting {
+            Setting::MapgenVisualizer => self.show_mapgen_visualizer = !self.show_mapgen_visualizer,
+            Setting::Scanlines => self.scanlines = !self.scanlines,
+            Setting::Font => {
+                self.font = Font::ALL[(self.font.index() + 1) % Font::ALL.len()];
+            }
+            Setting::Fullscreen => self.fullscreen = !self.fullscreen,
+            Setting::LogVerbosity => self.verbose_log = !self.verbose_log,
+            Setting::AutoExplorePickup => self.auto_explore_pickup = !self.auto_explore_pickup,
+        }
+    }
+
+    pub fn describe(&self, setting: Setting) -> String {
+        let on_off = |value: bool| (if value { "On" } else { "Off" }).to_string();
+        match setting {
+            Setting::MapgenVisualizer => on_off(self.show_mapgen_visualizer),
+            Setting::Scanlines => on_off(self.scanlines),
+            Setting::Font => self.font.file().to_string(),
+            // The window can only be made fullscreen when it is created
+            Setting::Fullscreen => format!("{} (after restart)", on_off(self.fullscreen)),
+            Setting::LogVerbosity => {
+                if self.verbose_log {
+                    "Verbose".to_string()
+                } else {
+                    "Brief".to_string()
+                }
+            }
+            Setting::AutoExplorePickup => on_off(self.auto_explore_pickup),
+        }
+    }
+}
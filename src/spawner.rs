@@ -1,12 +1,19 @@
 use crate::{
-    components::*, random_table::RandomTable, systems::hunger_system::WELL_FED_NUTRITION, Rect,
-    MAP_WIDTH,
+    character_creation::BASE_ATTRIBUTE,
+    clock,
+    components::*,
+    random_table::RandomTable,
+    systems::{
+        hunger_system::{HUNGRY_NUTRITION, WELL_FED_NUTRITION},
+        hydration_system::FULL_HYDRATION,
+        initiative_system::ACTION_COST,
+    },
+    Rect,
 };
 use crate::{Map, TileType};
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
-use std::collections::HashMap;
 
 const MAX_SPAWNS: i32 = 4;
 
@@ -14,6 +21,19 @@ const ITEM_LAYER: i32 = 5;
 const TRAP_LAYER: i32 = 4;
 const CHARACTER_LAYER: i32 = 3;
 
+pub const BOSS_NAME: &str = "Shai-Hulud";
+pub const ARTIFACT_NAME: &str = "Amulet of the Maker";
+
+// Names the achievements watch for
+pub const GOLEM_NAME: &str = "Golem";
+pub const FIREBALL_SCROLL_NAME: &str = "Fireball Scroll";
+pub const MAGIC_MAPPING_SCROLL_NAME: &str = "Scroll of Magic Mapping";
+
+// Energy gained per turn; see the initiative system
+const NORMAL_SPEED: i32 = 100;
+const FAST_SPEED: i32 = 200;
+const SLOW_SPEED: i32 = 50;
+
 pub fn spawn_room(ecs: &mut World, room: &Rect, map_depth: i32) {
     let mut possible_targets: Vec<usize> = Vec::new();
     {
@@ -33,8 +53,8 @@ pub fn spawn_room(ecs: &mut World, room: &Rect, map_depth: i32) {
 }
 
 pub fn spawn_region(ecs: &mut World, area: &[usize], map_depth: i32) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+    let spawn_table = room_table(map_depth, clock::is_night_outside(ecs));
+    let mut spawn_points: Vec<(usize, String)> = Vec::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
     // Scope to keep the borrow checker happy
@@ -55,36 +75,62 @@ pub fn spawn_region(ecs: &mut World, area: &[usize], map_depth: i32) {
                 (rng.roll_dice(1, areas.len() as i32) - 1) as usize
             };
             let map_idx = areas[array_index];
-            spawn_points.insert(map_idx, spawn_table.roll(&mut rng));
+            spawn_points.push((map_idx, spawn_table.roll(&mut rng)));
             areas.remove(array_index);
         }
     }
 
     // Actually spawn the monsters
     for spawn in spawn_points.iter() {
-        spawn_entity(ecs, &spawn);
+        spawn_entity(ecs, spawn);
     }
 }
 
-fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
-    let x = *spawn.0 as i32 % MAP_WIDTH;
-    let y = *spawn.0 as i32 / MAP_WIDTH;
-
-    match spawn.1.as_ref() {
-        "Goblin" => goblin(ecs, x, y),
-        "Orc" => orc(ecs, x, y),
-        "Health Potion" => health_potion(ecs, x, y),
-        "Fireball Scroll" => fireball_scroll(ecs, x, y),
-        "Confusion Scroll" => confusion_scroll(ecs, x, y),
-        "Magic Missile Scroll" => magic_missile_scroll(ecs, x, y),
-        "Dagger" => dagger(ecs, x, y),
-        "Shield" => shield(ecs, x, y),
-        "Longsword" => longsword(ecs, x, y),
-        "Tower Shield" => tower_shield(ecs, x, y),
-        "Rations" => rations(ecs, x, y),
-        "Magic Mapping Scroll" => magic_mapping_scroll(ecs, x, y),
-        "Bear Trap" => bear_trap(ecs, x, y),
-        _ => {}
+fn spawn_entity(ecs: &mut World, spawn: &(usize, String)) {
+    let map_width = ecs.fetch::<Map>().width;
+    let x = spawn.0 as i32 % map_width;
+    let y = spawn.0 as i32 / map_width;
+
+    spawn_named_entity(ecs, &spawn.1, x, y);
+}
+
+fn spawn_named_entity(ecs: &mut World, name: &str, x: i32, y: i32) -> Option<Entity> {
+    match name {
+        "Goblin" => Some(goblin(ecs, x, y)),
+        "Orc" => Some(orc(ecs, x, y)),
+        "Sand Runner" => Some(sand_runner(ecs, x, y)),
+        "Golem" => Some(golem(ecs, x, y)),
+        "Fremen Elder" | "Water Merchant" | "Sietch Scout" | "Fremen Warrior" => {
+            Some(npc(ecs, x, y, name))
+        }
+        "Health Potion" => Some(health_potion(ecs, x, y)),
+        "Fireball Scroll" => Some(fireball_scroll(ecs, x, y)),
+        "Confusion Scroll" => Some(confusion_scroll(ecs, x, y)),
+        "Magic Missile Scroll" => Some(magic_missile_scroll(ecs, x, y)),
+        "Dagger" => Some(dagger(ecs, x, y)),
+        "Shield" => Some(shield(ecs, x, y)),
+        "Longsword" => Some(longsword(ecs, x, y)),
+        "Tower Shield" => Some(tower_shield(ecs, x, y)),
+        "Rations" => Some(rations(ecs, x, y)),
+        "Jerky" => Some(jerky(ecs, x, y)),
+        "Glowing Spice" => Some(glowing_spice(ecs, x, y)),
+        "Torch" => Some(torch(ecs, x, y)),
+        "Water Flask" => Some(water_flask(ecs, x, y)),
+        "Well" => Some(water_source(ecs, x, y, "Well", None)),
+        "Cistern" => Some(water_source(ecs, x, y, "Cistern", Some(3))),
+        "Magic Mapping Scroll" => Some(magic_mapping_scroll(ecs, x, y)),
+        "Teleport Scroll" => Some(teleport_scroll(ecs, x, y)),
+        "Bear Trap" => Some(bear_trap(ecs, x, y)),
+        "Teleport Trap" => Some(teleport_trap(ecs, x, y)),
+        "Alarm Trap" => Some(alarm_trap(ecs, x, y)),
+        "Gas Trap" => Some(gas_trap(ecs, x, y)),
+        "Pit Trap" => Some(pit_trap(ecs, x, y)),
+        "Quicksand" => Some(quicksand(ecs, x, y)),
+        "Lava Vent" => Some(lava_vent(ecs, x, y)),
+        "Sand Blast" => Some(sand_blast(ecs, x, y)),
+        "Dust Storm" => Some(dust_storm(ecs, x, y)),
+        "Spice Sight" => Some(spice_sight(ecs, x, y)),
+        _ => None,
     }
 }
 
@@ -120,13 +166,69 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             state: HungerState::WellFed,
             total_nutrition: WELL_FED_NUTRITION,
         })
+        .with(HydrationClock {
+            state: HydrationState::Quenched,
+            water: FULL_HYDRATION,
+        })
+        .with(Mana {
+            max_mana: 10,
+            mana: 10,
+        })
+        .with(Skills {
+            perception: 1,
+            disarm: 1,
+        })
+        .with(Attributes {
+            strength: BASE_ATTRIBUTE,
+            agility: BASE_ATTRIBUTE,
+            toughness: BASE_ATTRIBUTE,
+            intellect: BASE_ATTRIBUTE,
+        })
+        .with(Initiative {
+            speed: NORMAL_SPEED,
+            energy: ACTION_COST,
+        })
+        .with(QuestLog::default())
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
     player
 }
 
-pub fn invisibility_timer(ecs: &mut World) -> Entity {
+/// Hands a new character their class's equipment and the spells they already know.
+pub fn starting_kit(ecs: &mut World, owner: Entity, items: &[&str], spells: &[&str]) {
+    for name in items.iter() {
+        let item = match *name {
+            "Invisibility Timer" => Some(invisibility_timer(ecs)),
+            "Confusion Wand" => Some(confusion_wand(ecs)),
+            _ => spawn_named_entity(ecs, name, 0, 0),
+        };
+        if let Some(item) = item {
+            give_item(ecs, owner, item);
+        }
+    }
+
+    for name in spells.iter() {
+        if let Some(spell) = spawn_named_entity(ecs, name, 0, 0) {
+            ecs.write_storage::<Position>().remove(spell);
+            ecs.write_storage::<KnownSpell>()
+                .insert(spell, KnownSpell { owner })
+                .expect("Unable to insert known spell");
+        }
+    }
+}
+
+/// Spawns an item straight into the owner's backpack, such as a quest reward.
+pub fn give_named_item(ecs: &mut World, owner: Entity, name: &str) -> Option<Entity> {
+    let item = spawn_named_entity(ecs, name, 0, 0)?;
+    ecs.write_storage::<Position>().remove(item);
+    ecs.write_storage::<InInventory>()
+        .insert(item, InInventory { owner })
+        .expect("Unable to insert backpack entry");
+    Some(item)
+}
+
+fn invisibility_timer(ecs: &mut World) -> Entity {
     ecs.create_entity()
         .with(Renderable {
             layer: CHARACTER_LAYER,
@@ -138,13 +240,14 @@ pub fn invisibility_timer(ecs: &mut World) -> Entity {
             name: "Invisibility Timer".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.5 })
         .with(AppliesInvisiblity { turns: 18 })
         .with(Cooldown { turns: 60 })
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
-pub fn confusion_wand(ecs: &mut World) -> Entity {
+fn confusion_wand(ecs: &mut World) -> Entity {
     ecs.create_entity()
         .with(Renderable {
             layer: CHARACTER_LAYER,
@@ -156,6 +259,7 @@ pub fn confusion_wand(ecs: &mut World) -> Entity {
             name: "Confusion Wand".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.5 })
         .with(CausesConfusion { turns: 5 })
         .with(Ranged { range: 6 })
         .with(Cooldown { turns: 45 })
@@ -163,15 +267,241 @@ pub fn confusion_wand(ecs: &mut World) -> Entity {
         .build()
 }
 
-fn orc(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('o'), "Orc");
+fn orc(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('o'),
+        "Orc",
+        DietType::Carnivore,
+        CombatStats {
+            max_hp: 16,
+            hp: 16,
+            defense: 1,
+            power: 4,
+        },
+        NORMAL_SPEED,
+    )
 }
-fn goblin(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin");
+fn goblin(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('g'),
+        "Goblin",
+        DietType::Herbivore,
+        CombatStats {
+            max_hp: 16,
+            hp: 16,
+            defense: 1,
+            power: 4,
+        },
+        NORMAL_SPEED,
+    )
+}
+// Fragile, but gets two moves for each of yours
+fn sand_runner(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('r'),
+        "Sand Runner",
+        DietType::Carnivore,
+        CombatStats {
+            max_hp: 8,
+            hp: 8,
+            defense: 0,
+            power: 3,
+        },
+        FAST_SPEED,
+    )
+}
+// Hits hard and takes a beating, but only moves every other turn
+fn golem(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('G'),
+        GOLEM_NAME,
+        DietType::Omnivore,
+        CombatStats {
+            max_hp: 30,
+            hp: 30,
+            defense: 3,
+            power: 8,
+        },
+        SLOW_SPEED,
+    )
 }
 
-fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharType, name: S) {
+// Doesn't fight, and can't be hurt; bumping into one starts a conversation
+fn npc(ecs: &mut World, x: i32, y: i32, name: &str) -> Entity {
     ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            layer: CHARACTER_LAYER,
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+        })
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(Npc {})
+        .with(BlocksTile {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+/// Turns a friendly NPC into a companion who fights alongside the player.
+pub fn make_companion(ecs: &mut World, npc: Entity) {
+    ecs.write_storage::<Npc>().remove(npc);
+    ecs.write_storage::<Companion>()
+        .insert(
+            npc,
+            Companion {
+                order: CompanionOrder::Follow,
+            },
+        )
+        .expect("Unable to insert companion");
+    ecs.write_storage::<CombatStats>()
+        .insert(
+            npc,
+            CombatStats {
+                max_hp: 20,
+                hp: 20,
+                defense: 1,
+                power: 5,
+            },
+        )
+        .expect("Unable to insert companion stats");
+    ecs.write_storage::<Viewshed>()
+        .insert(
+            npc,
+            Viewshed {
+                visible_tiles: Vec::new(),
+                range: 8,
+                dirty: true,
+            },
+        )
+        .expect("Unable to insert companion viewshed");
+    ecs.write_storage::<Initiative>()
+        .insert(
+            npc,
+            Initiative {
+                speed: NORMAL_SPEED,
+                energy: 0,
+            },
+        )
+        .expect("Unable to insert companion initiative");
+    if let Some(render) = ecs.write_storage::<Renderable>().get_mut(npc) {
+        render.fg = RGB::named(rltk::GREEN);
+    }
+}
+
+/// The great worm at the bottom of the desert. It carries the artifact, and has a couple of
+/// attacks of its own that it uses whenever they are off cooldown.
+pub fn boss(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let boss = monster(
+        ecs,
+        x,
+        y,
+        rltk::to_cp437('W'),
+        BOSS_NAME,
+        DietType::Omnivore,
+        CombatStats {
+            max_hp: 80,
+            hp: 80,
+            defense: 4,
+            power: 10,
+        },
+        NORMAL_SPEED,
+    );
+    // It feels the player's footsteps long before it could see them
+    if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(boss) {
+        viewshed.range = 12;
+    }
+
+    let attacks = [sand_surge(ecs), spice_breath(ecs)];
+    for attack in attacks {
+        give_item(ecs, boss, attack);
+    }
+    let amulet = amulet_of_the_maker(ecs);
+    give_item(ecs, boss, amulet);
+
+    boss
+}
+
+// Innate attacks are carried like items, so the monster item AI uses them, but they have no
+// Item component and so vanish with their owner instead of being dropped
+fn sand_surge(ecs: &mut World) -> Entity {
+    ecs.create_entity()
+        .with(Name {
+            name: "Sand Surge".to_string(),
+        })
+        .with(Ranged { range: 8 })
+        .with(InflictsDamage { damage: 12 })
+        .with(AreaOfEffect { radius: 2 })
+        .with(Cooldown { turns: 8 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn spice_breath(ecs: &mut World) -> Entity {
+    ecs.create_entity()
+        .with(Name {
+            name: "Spice Breath".to_string(),
+        })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 7 })
+        .with(Cooldown { turns: 4 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn amulet_of_the_maker(ecs: &mut World) -> Entity {
+    ecs.create_entity()
+        .with(Renderable {
+            glyph: rltk::to_cp437('"'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: ARTIFACT_NAME.to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.2 })
+        .with(Artifact {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn monster<S: ToString>(
+    ecs: &mut World,
+    x: i32,
+    y: i32,
+    glyph: rltk::FontCharType,
+    name: S,
+    diet_type: DietType,
+    stats: CombatStats,
+    speed: i32,
+) -> Entity {
+    let name = name.to_string();
+
+    // Stagger starting appetites so the whole level doesn't get hungry on the same turn
+    let total_nutrition = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.range(HUNGRY_NUTRITION, WELL_FED_NUTRITION)
+    };
+
+    let monster = ecs
+        .create_entity()
         .with(Position { x, y })
         .with(Renderable {
             layer: CHARACTER_LAYER,
@@ -187,21 +517,80 @@ fn monster<S: ToString>(ecs: &mut World, x: i32, y: i32, glyph: rltk::FontCharTy
         .with(Monster {
             is_targeting_player: false,
         })
-        .with(Name {
-            name: name.to_string(),
-        })
+        .with(Name { name: name.clone() })
         .with(BlocksTile {})
-        .with(CombatStats {
-            max_hp: 16,
-            hp: 16,
-            defense: 1,
-            power: 4,
+        .with(stats)
+        .with(Initiative { speed, energy: 0 })
+        .with(HungerClock {
+            state: HungerState::Normal,
+            total_nutrition,
         })
+        .with(Diet { diet_type })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
+
+    spawn_loot(ecs, monster, x, y, &name);
+    monster
+}
+
+/// Rolls on the creature's loot table, equipping anything it can and carrying the rest.
+fn spawn_loot(ecs: &mut World, owner: Entity, x: i32, y: i32, monster_name: &str) {
+    let roll = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        loot_table(monster_name).roll(&mut rng)
+    };
+    if let Some(item) = spawn_named_entity(ecs, &roll, x, y) {
+        give_item(ecs, owner, item);
+    }
+}
+
+/// Equips the item if it can be, and otherwise puts it in the owner's backpack.
+fn give_item(ecs: &mut World, owner: Entity, item: Entity) {
+    ecs.write_storage::<Position>().remove(item);
+    let slot = ecs.read_storage::<Equippable>().get(item).map(|e| e.slot);
+    match slot {
+        Some(slot) => {
+            ecs.write_storage::<Equipped>()
+                .insert(item, Equipped { owner, slot })
+                .expect("Unable to equip loot");
+        }
+        None => {
+            ecs.write_storage::<InInventory>()
+                .insert(item, InInventory { owner })
+                .expect("Unable to insert loot into backpack");
+        }
+    }
+}
+
+/// Left behind when a creature dies. Fresh meat, mostly.
+pub fn corpse(ecs: &mut World, x: i32, y: i32, creature_name: &str) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::RED3),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: format!("{} Corpse", creature_name),
+        })
+        .with(Item {})
+        .with(Weight { kg: 10.0 })
+        .with(Food {
+            nutrition: 200,
+            kind: FoodKind::Meat,
+        })
+        .with(Poisonous {
+            chance: 25,
+            turns: 6,
+        })
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn health_potion(ecs: &mut World, x: i32, y: i32) {
+fn health_potion(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -214,13 +603,14 @@ fn health_potion(ecs: &mut World, x: i32, y: i32) {
             name: "Health Potion".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.5 })
         .with(Consumable {})
         .with(ProvidesHealing { heal_amount: 8 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
+fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -233,14 +623,15 @@ fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Magic Missile Scroll".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 8 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
+fn fireball_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -250,18 +641,19 @@ fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
             layer: ITEM_LAYER,
         })
         .with(Name {
-            name: "Fireball Scroll".to_string(),
+            name: FIREBALL_SCROLL_NAME.to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(InflictsDamage { damage: 20 })
         .with(AreaOfEffect { radius: 3 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
+fn confusion_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -274,14 +666,15 @@ fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
             name: "Confusion Scroll".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.1 })
         .with(Consumable {})
         .with(Ranged { range: 6 })
         .with(CausesConfusion { turns: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn dagger(ecs: &mut World, x: i32, y: i32) {
+fn dagger(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -294,15 +687,16 @@ fn dagger(ecs: &mut World, x: i32, y: i32) {
             name: "Dagger".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 1.0 })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 2 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn shield(ecs: &mut World, x: i32, y: i32) {
+fn shield(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -315,15 +709,16 @@ fn shield(ecs: &mut World, x: i32, y: i32) {
             name: "Shield".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 5.0 })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .with(DefenseBonus { defense: 1 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn longsword(ecs: &mut World, x: i32, y: i32) {
+fn longsword(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -336,15 +731,16 @@ fn longsword(ecs: &mut World, x: i32, y: i32) {
             name: "Longsword".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 3.0 })
         .with(Equippable {
             slot: EquipmentSlot::Melee,
         })
         .with(MeleePowerBonus { power: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn tower_shield(ecs: &mut World, x: i32, y: i32) {
+fn tower_shield(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -357,15 +753,16 @@ fn tower_shield(ecs: &mut World, x: i32, y: i32) {
             name: "Tower Shield".to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 10.0 })
         .with(Equippable {
             slot: EquipmentSlot::Shield,
         })
         .with(DefenseBonus { defense: 3 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
+fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -375,16 +772,37 @@ fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) {
             layer: ITEM_LAYER,
         })
         .with(Name {
-            name: "Scroll of Magic Mapping".to_string(),
+            name: MAGIC_MAPPING_SCROLL_NAME.to_string(),
         })
         .with(Item {})
+        .with(Weight { kg: 0.1 })
         .with(MagicMapper {})
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+fn teleport_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Scroll of Teleportation".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.1 })
+        .with(TeleportsRandomly {})
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn rations(ecs: &mut World, x: i32, y: i32) {
+fn rations(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -397,13 +815,129 @@ fn rations(ecs: &mut World, x: i32, y: i32) {
             name: "Rations".to_string(),
         })
         .with(Item {})
-        .with(Food { nutrition: 350 })
+        .with(Weight { kg: 0.5 })
+        .with(Food {
+            nutrition: 350,
+            kind: FoodKind::Plant,
+        })
         .with(Consumable {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+fn jerky(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::BROWN1),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Jerky".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.2 })
+        .with(Food {
+            nutrition: 250,
+            kind: FoodKind::Meat,
+        })
+        .with(Consumable {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn glowing_spice(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Glowing Spice".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.1 })
+        .with(Food {
+            nutrition: 150,
+            kind: FoodKind::Plant,
+        })
+        .with(Consumable {})
+        .with(LightSource {
+            color: RGB::from_f32(0.2, 0.6, 0.8),
+            range: 3,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn bear_trap(ecs: &mut World, x: i32, y: i32) {
+fn torch(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Torch".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 1.0 })
+        .with(LightSource {
+            color: RGB::from_f32(1.0, 0.75, 0.4),
+            range: 6,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn water_flask(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('!'),
+            fg: RGB::named(rltk::LIGHT_BLUE),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Water Flask".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 1.0 })
+        .with(WaterFlask {
+            sips: 3,
+            max_sips: 3,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn water_source(ecs: &mut World, x: i32, y: i32, name: &str, drinks: Option<i32>) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('○'),
+            fg: RGB::named(rltk::BLUE),
+            bg: RGB::named(rltk::BLACK),
+            layer: CHARACTER_LAYER,
+        })
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(WaterSource { drinks })
+        .with(BlocksTile {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn bear_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -416,17 +950,254 @@ fn bear_trap(ecs: &mut World, x: i32, y: i32) {
             name: "Bear Trap".to_string(),
         })
         .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 18,
+            disarm_difficulty: 12,
+        })
         .with(InflictsDamage { damage: 6 })
         .with(EntryTrigger {})
         .with(SingleActivation {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn room_table(map_depth: i32) -> RandomTable {
+fn teleport_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('^'),
+            fg: RGB::named(rltk::MAGENTA),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Teleport Trap".to_string(),
+        })
+        .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 20,
+            disarm_difficulty: 15,
+        })
+        .with(TeleportsRandomly {})
+        .with(EntryTrigger {})
+        .with(Cooldown { turns: 10 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn alarm_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('^'),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Alarm Trap".to_string(),
+        })
+        .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 17,
+            disarm_difficulty: 10,
+        })
+        .with(SoundsAlarm { radius: 20 })
+        .with(EntryTrigger {})
+        .with(Cooldown { turns: 20 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn gas_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('^'),
+            fg: RGB::named(rltk::PURPLE),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Gas Trap".to_string(),
+        })
+        .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 18,
+            disarm_difficulty: 13,
+        })
+        .with(CausesConfusion { turns: 4 })
+        .with(AreaOfEffect { radius: 2 })
+        .with(EntryTrigger {})
+        .with(Cooldown { turns: 15 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn pit_trap(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('^'),
+            fg: RGB::named(rltk::GREY),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Pit Trap".to_string(),
+        })
+        .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 19,
+            disarm_difficulty: 14,
+        })
+        .with(InflictsDamage { damage: 3 })
+        .with(DropsALevel {})
+        .with(EntryTrigger {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+// Never hidden, since it glows, but it still burns anything that steps in
+fn lava_vent(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('~'),
+            fg: RGB::named(rltk::ORANGE_RED),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Lava Vent".to_string(),
+        })
+        .with(InflictsDamage { damage: 4 })
+        .with(EntryTrigger {})
+        .with(LightSource {
+            color: RGB::from_f32(0.9, 0.3, 0.1),
+            range: 4,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn quicksand(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('~'),
+            fg: RGB::named(rltk::WHEAT),
+            bg: RGB::named(rltk::BLACK),
+            layer: TRAP_LAYER,
+        })
+        .with(Name {
+            name: "Quicksand".to_string(),
+        })
+        .with(Invisible { turns: None })
+        .with(Trap {
+            spot_difficulty: 16,
+            disarm_difficulty: 18,
+        })
+        .with(Entangles { turns: 3 })
+        .with(EntryTrigger {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn sand_blast(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('♪'),
+            fg: RGB::named(rltk::KHAKI),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Sand Blast".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.5 })
+        .with(Spell { mana_cost: 2 })
+        .with(Ranged { range: 6 })
+        .with(InflictsDamage { damage: 6 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn dust_storm(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('♪'),
+            fg: RGB::named(rltk::WHEAT),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Dust Storm".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.5 })
+        .with(Spell { mana_cost: 4 })
+        .with(Ranged { range: 6 })
+        .with(AreaOfEffect { radius: 2 })
+        .with(CausesConfusion { turns: 3 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn spice_sight(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('♪'),
+            fg: RGB::named(rltk::ORANGE),
+            bg: RGB::named(rltk::BLACK),
+            layer: ITEM_LAYER,
+        })
+        .with(Name {
+            name: "Spice Sight".to_string(),
+        })
+        .with(Item {})
+        .with(Weight { kg: 0.5 })
+        .with(Spell { mana_cost: 6 })
+        .with(MagicMapper {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn loot_table(monster_name: &str) -> RandomTable {
+    match monster_name {
+        "Goblin" => RandomTable::new()
+            .add("None", 6)
+            .add("Dagger", 2)
+            .add("Health Potion", 1)
+            .add("Rations", 1),
+        "Orc" => RandomTable::new()
+            .add("None", 4)
+            .add("Dagger", 2)
+            .add("Shield", 2)
+            .add("Longsword", 1)
+            .add("Jerky", 2),
+        _ => RandomTable::new(),
+    }
+}
+
+// The desert belongs to the hunters after dark
+const NIGHT_HUNTER_BONUS: i32 = 4;
+
+fn room_table(map_depth: i32, is_night: bool) -> RandomTable {
+    let night_bonus = if is_night { NIGHT_HUNTER_BONUS } else { 0 };
     RandomTable::new()
         .add("Goblin", 10)
-        .add("Orc", 1 + map_depth)
+        .add("Orc", 1 + map_depth + night_bonus)
+        .add("Sand Runner", map_depth + night_bonus * 2)
+        .add("Golem", map_depth - 2 + night_bonus)
+        .add("Fremen Elder", 1)
+        .add("Water Merchant", 1)
+        .add("Sietch Scout", 1)
+        .add("Fremen Warrior", 1)
         .add("Health Potion", 7)
         .add("Fireball Scroll", 2 + map_depth)
         .add("Confusion Scroll", 2 + map_depth)
@@ -436,6 +1207,21 @@ fn room_table(map_depth: i32) -> RandomTable {
         .add("Longsword", map_depth - 1)
         .add("Tower Shield", map_depth - 1)
         .add("Magic Mapping Scroll", 2)
+        .add("Teleport Scroll", 2)
         .add("Rations", 10)
+        .add("Jerky", 4)
+        .add("Glowing Spice", 2)
+        .add("Torch", 3)
+        .add("Water Flask", 3)
+        .add("Cistern", 2)
+        .add("Well", 1)
         .add("Bear Trap", 3)
+        .add("Teleport Trap", map_depth)
+        .add("Alarm Trap", 2)
+        .add("Gas Trap", map_depth)
+        .add("Pit Trap", map_depth - 1)
+        .add("Quicksand", 2)
+        .add("Lava Vent", map_depth - 3)
+        .add("Dust Storm", map_depth - 1)
+        .add("Spice Sight", map_depth - 2)
 }
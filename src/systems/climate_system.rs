@@ -0,0 +1,67 @@
+use crate::{
+    clock::{self, TimeOfDay},
+    components::{HungerClock, HydrationClock, RunStats},
+    game_log::GameLog,
+    Map, RunState,
+};
+use specs::prelude::*;
+
+// Extra water lost to the sun, and food burned against the cold, each turn on the surface
+const SUN_HEAT: i32 = 2;
+const NIGHT_COLD: i32 = 1;
+
+/// The weather on the surface: the sun dries everyone out by day, and keeping warm at night
+/// burns through food. Underground, neither reaches you.
+pub struct ClimateSystem {}
+
+impl<'a> System<'a> for ClimateSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        ReadStorage<'a, RunStats>,
+        WriteStorage<'a, HydrationClock>,
+        WriteStorage<'a, HungerClock>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (map, player_entity, run_state, run_stats, mut hydration, mut hunger, mut log) = data;
+
+        if *run_state != RunState::NewTurn || !map.surface {
+            return;
+        }
+
+        let turns = run_stats.get(*player_entity).map_or(0, |stats| stats.turns);
+        let time_of_day = clock::time_of_day(turns);
+        if time_of_day != clock::time_of_day(turns - 1) {
+            match time_of_day {
+                TimeOfDay::Dawn => log
+                    .entries
+                    .push("The sky pales over the dunes.".to_string()),
+                TimeOfDay::Day => log
+                    .entries
+                    .push("The sun is up, and the sand starts to burn.".to_string()),
+                TimeOfDay::Dusk => log.entries.push("The sun sinks low.".to_string()),
+                TimeOfDay::Night => log
+                    .entries
+                    .push("Night falls, and the desert turns cold.".to_string()),
+            }
+        }
+
+        match time_of_day {
+            TimeOfDay::Day => {
+                for clock in (&mut hydration).join() {
+                    clock.water = i32::max(0, clock.water - SUN_HEAT);
+                }
+            }
+            TimeOfDay::Night => {
+                for clock in (&mut hunger).join() {
+                    clock.total_nutrition -= NIGHT_COLD;
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    components::{self, CompanionOrder},
+    systems::monster_ai_system::move_towards,
+    Map, RunState,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+// Close enough to keep up, without standing in the player's way
+const FOLLOW_DISTANCE: f32 = 2.5;
+// How far from the player a following companion will wander to pick a fight
+const LEASH: f32 = 8.0;
+
+/// Decides what the player's companions do. Following companions go after monsters they can
+/// see near the player, staying companions only hit what's next to them, and attacking
+/// companions chase their target until it dies.
+pub struct CompanionAI {}
+
+impl<'a> System<'a> for CompanionAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, RunState>,
+        Entities<'a>,
+        WriteStorage<'a, components::Companion>,
+        WriteStorage<'a, components::CompanionTarget>,
+        WriteStorage<'a, components::Viewshed>,
+        ReadStorage<'a, components::MyTurn>,
+        WriteStorage<'a, components::Position>,
+        WriteStorage<'a, components::WantsToMelee>,
+        ReadStorage<'a, components::Monster>,
+        ReadStorage<'a, components::Confusion>,
+        ReadStorage<'a, components::Entangled>,
+        WriteStorage<'a, components::EntityMoved>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            player_pos,
+            run_state,
+            entities,
+            mut companions,
+            mut targets,
+            mut viewsheds,
+            my_turn,
+            mut positions,
+            mut wants_to_melee,
+            monsters,
+            confused,
+            entangled,
+            mut entities_moved,
+        ) = data;
+
+        if *run_state != RunState::MonsterTurn {
+            return;
+        }
+
+        // Collected up front, since we move companions around below
+        let hostiles: Vec<(Entity, Point)> = (&entities, &monsters, &positions)
+            .join()
+            .map(|(monster, _, pos)| (monster, Point::new(pos.x, pos.y)))
+            .collect();
+        let distance = |a: Point, b: Point| rltk::DistanceAlg::Pythagoras.distance2d(a, b);
+
+        for (entity, companion, viewshed, _my_turn, pos) in (
+            &entities,
+            &mut companions,
+            &mut viewsheds,
+            &my_turn,
+            &mut positions,
+        )
+            .join()
+        {
+            if confused.get(entity).is_some() {
+                continue;
+            }
+            let my_pos = Point::new(pos.x, pos.y);
+
+            // Once the target is dead there's nothing left to do but come back
+            let ordered_target = targets.get(entity).map(|t| t.target);
+            let target =
+                ordered_target.and_then(|t| hostiles.iter().find(|(h, _)| *h == t).copied());
+            if ordered_target.is_some() && target.is_none() {
+                targets.remove(entity);
+                companion.order = CompanionOrder::Follow;
+            }
+
+            let adjacent = hostiles
+                .iter()
+                .find(|(_, hostile_pos)| distance(my_pos, *hostile_pos) < 1.5)
+                .copied();
+            let quarry = match companion.order {
+                CompanionOrder::Attack => target,
+                CompanionOrder::Stay => adjacent,
+                CompanionOrder::Follow => adjacent.or_else(|| {
+                    hostiles
+                        .iter()
+                        .filter(|(_, hostile_pos)| {
+                            viewshed.visible_tiles.contains(hostile_pos)
+                                && distance(*player_pos, *hostile_pos) < LEASH
+                        })
+                        .min_by(|a, b| {
+                            distance(my_pos, a.1)
+                                .partial_cmp(&distance(my_pos, b.1))
+                                .unwrap()
+                        })
+                        .copied()
+                }),
+            };
+
+            let destination = match quarry {
+                Some((victim, victim_pos)) if distance(my_pos, victim_pos) < 1.5 => {
+                    wants_to_melee
+                        .insert(entity, components::WantsToMelee { target: victim })
+                        .expect("Unable to insert attack");
+                    None
+                }
+                Some((_, victim_pos)) => Some(victim_pos),
+                None if companion.order == CompanionOrder::Follow
+                    && distance(my_pos, *player_pos) > FOLLOW_DISTANCE =>
+                {
+                    Some(*player_pos)
+                }
+                None => None,
+            };
+
+            if let Some(destination) = destination {
+                if entangled.get(entity).is_none()
+                    && move_towards(&mut map, pos, viewshed, destination)
+                {
+                    entities_moved
+                        .insert(entity, components::EntityMoved {})
+                        .expect("Could not insert entity moved");
+                }
+            }
+        }
+    }
+}
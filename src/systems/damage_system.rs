@@ -1,23 +1,35 @@
+use crate::achievements::{GameEvent, GameEvents};
 use crate::components;
 use crate::map;
+use crate::{spawner, GameLog};
 use specs::prelude::*;
 
 pub struct DamageSystem {}
 
 impl<'a> System<'a> for DamageSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, components::CombatStats>,
         WriteStorage<'a, components::SufferDamage>,
         ReadStorage<'a, components::Position>,
         WriteExpect<'a, map::Map>,
         Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteStorage<'a, components::RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut stats, mut damage, positions, mut map, entities) = data;
+        let (mut stats, mut damage, positions, mut map, entities, player_entity, mut run_stats) =
+            data;
 
         for (entity, stats, damage) in (&entities, &mut stats, &damage).join() {
-            stats.hp -= damage.amount.iter().sum::<i32>();
+            let total = damage.amount.iter().sum::<i32>();
+            stats.hp -= total;
+            if entity == *player_entity {
+                if let Some(run_stats) = run_stats.get_mut(entity) {
+                    run_stats.damage_taken += total;
+                }
+            }
             let pos = positions.get(entity);
             if let Some(pos) = pos {
                 let idx = map.xy_idx(pos.x, pos.y);
@@ -50,9 +62,96 @@ pub fn delete_the_dead(ecs: &mut World) -> Option<bool> {
         }
     }
 
+    for victim in dead.iter() {
+        count_kill(ecs, *victim);
+    }
     for victim in dead {
+        drop_everything(ecs, victim);
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 
     None
 }
+
+fn count_kill(ecs: &World, victim: Entity) {
+    let last_damaged_by = ecs.read_storage::<components::LastDamagedBy>();
+    let killing_blow = match last_damaged_by.get(victim) {
+        Some(last) if last.by_player => last,
+        _ => return,
+    };
+
+    let player_entity = *ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<components::Name>();
+    let mut run_stats = ecs.write_storage::<components::RunStats>();
+    if let (Some(name), Some(stats)) = (names.get(victim), run_stats.get_mut(player_entity)) {
+        *stats.kills.entry(name.name.clone()).or_insert(0) += 1;
+        ecs.write_resource::<GameEvents>().emit(GameEvent::Kill {
+            victim: name.name.clone(),
+            with: killing_blow.source.clone(),
+        });
+    }
+}
+
+/// Leaves a corpse where the victim fell, and spills whatever it was carrying or wielding
+/// onto the same tile.
+fn drop_everything(ecs: &mut World, victim: Entity) {
+    let victim_pos = ecs
+        .read_storage::<components::Position>()
+        .get(victim)
+        .cloned();
+    let victim_pos = match victim_pos {
+        Some(pos) => pos,
+        None => return,
+    };
+    let victim_name = ecs
+        .read_storage::<components::Name>()
+        .get(victim)
+        .map(|n| n.name.clone())
+        .unwrap_or_else(|| "Creature".to_string());
+
+    {
+        let entities = ecs.entities();
+        let map = ecs.fetch::<map::Map>();
+        let names = ecs.read_storage::<components::Name>();
+        let mut positions = ecs.write_storage::<components::Position>();
+        let mut backpack = ecs.write_storage::<components::InInventory>();
+        let mut equipped = ecs.write_storage::<components::Equipped>();
+        let items = ecs.read_storage::<components::Item>();
+        let mut log = ecs.fetch_mut::<GameLog>();
+
+        let mut to_drop: Vec<Entity> = Vec::new();
+        for (item, carried) in (&entities, &backpack).join() {
+            if carried.owner == victim {
+                to_drop.push(item);
+            }
+        }
+        for (item, wielded) in (&entities, &equipped).join() {
+            if wielded.owner == victim {
+                to_drop.push(item);
+            }
+        }
+
+        let is_visible = map.visible_tiles[map.xy_idx(victim_pos.x, victim_pos.y)];
+        for item in to_drop {
+            backpack.remove(item);
+            equipped.remove(item);
+            // Innate attacks aren't things you can pick up
+            if items.get(item).is_none() {
+                entities.delete(item).expect("Unable to delete");
+                continue;
+            }
+            positions
+                .insert(item, victim_pos.clone())
+                .expect("Unable to insert position");
+
+            if is_visible {
+                if let Some(item_name) = names.get(item) {
+                    log.entries
+                        .push(format!("The {} drops a {}.", victim_name, item_name.name));
+                }
+            }
+        }
+    }
+
+    spawner::corpse(ecs, victim_pos.x, victim_pos.y, &victim_name);
+}
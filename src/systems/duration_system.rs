@@ -4,18 +4,33 @@ use specs::prelude::*;
 pub struct DurationSystem {}
 
 impl<'a> System<'a> for DurationSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         Entities<'a>,
         ReadExpect<'a, RunState>,
         WriteStorage<'a, components::Invisible>,
         WriteStorage<'a, components::ActiveCooldown>,
         WriteStorage<'a, components::Confusion>,
+        WriteStorage<'a, components::FoodPoisoning>,
+        WriteStorage<'a, components::SufferDamage>,
+        WriteStorage<'a, components::Entangled>,
+        WriteStorage<'a, components::LastDamagedBy>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, run_state, mut invisibles, mut active_cooldowns, mut confusion) = data;
+        let (
+            entities,
+            run_state,
+            mut invisibles,
+            mut active_cooldowns,
+            mut confusion,
+            mut food_poisoning,
+            mut inflict_damage,
+            mut entangled,
+            mut last_damaged_by,
+        ) = data;
 
-        if *run_state != RunState::MonsterTurn {
+        if *run_state != RunState::NewTurn {
             return;
         }
 
@@ -62,5 +77,42 @@ impl<'a> System<'a> for DurationSystem {
         for entity in entities_to_remove {
             confusion.remove(entity);
         }
+
+        let mut entities_to_remove: Vec<Entity> = vec![];
+        {
+            for (entity, poisoned) in (&entities, &mut food_poisoning).join() {
+                components::SufferDamage::new_damage(&mut inflict_damage, entity, 1);
+                last_damaged_by
+                    .insert(
+                        entity,
+                        components::LastDamagedBy {
+                            cause: "food poisoning".to_string(),
+                            source: None,
+                            by_player: false,
+                        },
+                    )
+                    .expect("Unable to insert damage cause");
+                poisoned.turns -= 1;
+                if poisoned.turns < 1 {
+                    entities_to_remove.push(entity);
+                }
+            }
+        }
+        for entity in entities_to_remove {
+            food_poisoning.remove(entity);
+        }
+
+        let mut entities_to_remove: Vec<Entity> = vec![];
+        {
+            for (entity, stuck) in (&entities, &mut entangled).join() {
+                stuck.turns -= 1;
+                if stuck.turns < 1 {
+                    entities_to_remove.push(entity);
+                }
+            }
+        }
+        for entity in entities_to_remove {
+            entangled.remove(entity);
+        }
     }
 }
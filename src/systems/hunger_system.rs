@@ -1,17 +1,25 @@
 use crate::{
-    components::{HungerClock, HungerState, SufferDamage},
+    achievements::{GameEvent, GameEvents},
+    components::{
+        Food, FoodPoisoning, HungerClock, HungerState, LastDamagedBy, Name, Poisonous, Position,
+        RunStats, SufferDamage, WantsToEat,
+    },
     game_log::GameLog,
-    RunState,
+    Map, RunState,
 };
+use rltk::RandomNumberGenerator;
 use specs::prelude::*;
 
 pub struct HungerSystem {}
 
 pub const WELL_FED_NUTRITION: i32 = 650;
 const NORMAL_NUTRITION: i32 = 600;
-const HUNGRY_NUTRITION: i32 = 400;
+pub const HUNGRY_NUTRITION: i32 = 400;
 const STARVING_NUTRITION: i32 = 200;
 
+// Starving monsters waste away slowly rather than dropping dead a few turns later
+const MONSTER_STARVATION_INTERVAL: i32 = 10;
+
 impl<'a> System<'a> for HungerSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
@@ -21,78 +29,190 @@ impl<'a> System<'a> for HungerSystem {
         ReadExpect<'a, RunState>,
         WriteStorage<'a, SufferDamage>,
         WriteExpect<'a, GameLog>,
+        WriteStorage<'a, LastDamagedBy>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut hunger_clock, player_entity, run_state, mut inflict_damage, mut log) =
-            data;
+        let (
+            entities,
+            mut hunger_clock,
+            player_entity,
+            run_state,
+            mut inflict_damage,
+            mut log,
+            mut last_damaged_by,
+        ) = data;
+
+        if *run_state != RunState::NewTurn {
+            return;
+        }
 
         for (entity, clock) in (&entities, &mut hunger_clock).join() {
-            let mut proceed = false;
+            clock.total_nutrition -= 1;
 
-            match *run_state {
-                RunState::PlayerTurn => {
-                    if entity == *player_entity {
-                        proceed = true;
-                    }
+            if clock.total_nutrition > WELL_FED_NUTRITION {
+                clock.total_nutrition = WELL_FED_NUTRITION;
+            }
+
+            if in_range(clock.total_nutrition, NORMAL_NUTRITION, WELL_FED_NUTRITION)
+                && clock.state != HungerState::WellFed
+            {
+                clock.state = HungerState::WellFed;
+                if entity == *player_entity {
+                    log.entries.push("You are well fed!".to_string());
                 }
-                RunState::MonsterTurn => {
-                    if entity != *player_entity {
-                        proceed = true;
-                    }
+            } else if in_range(clock.total_nutrition, HUNGRY_NUTRITION, NORMAL_NUTRITION)
+                && clock.state != HungerState::Normal
+            {
+                clock.state = HungerState::Normal;
+                if entity == *player_entity {
+                    log.entries.push("You are not hungry.".to_string());
+                }
+            } else if in_range(clock.total_nutrition, STARVING_NUTRITION, HUNGRY_NUTRITION)
+                && clock.state != HungerState::Hungry
+            {
+                clock.state = HungerState::Hungry;
+                if entity == *player_entity {
+                    log.entries.push("You are hungry.".to_string());
+                }
+            } else if in_range(clock.total_nutrition, -10000, STARVING_NUTRITION)
+                && clock.state != HungerState::Starving
+            {
+                clock.state = HungerState::Starving;
+                if entity == *player_entity {
+                    log.entries
+                        .push("You are starving! Eat something!".to_string());
                 }
-                _ => proceed = false,
             }
 
-            if proceed {
-                clock.total_nutrition -= 1;
-
-                if clock.total_nutrition > WELL_FED_NUTRITION {
-                    clock.total_nutrition = WELL_FED_NUTRITION;
+            if clock.total_nutrition < STARVING_NUTRITION {
+                let hurts = if entity == *player_entity {
+                    log.entries.push(
+                        "Your hunger pangs are getting painful! You suffer 1 damage.".to_string(),
+                    );
+                    true
+                } else {
+                    clock.total_nutrition % MONSTER_STARVATION_INTERVAL == 0
+                };
+                if hurts {
+                    SufferDamage::new_damage(&mut inflict_damage, entity, 1);
+                    last_damaged_by
+                        .insert(
+                            entity,
+                            LastDamagedBy {
+                                cause: "starvation".to_string(),
+                                source: None,
+                                by_player: false,
+                            },
+                        )
+                        .expect("Unable to insert damage cause");
                 }
+            }
+        }
+    }
+}
 
-                if in_range(clock.total_nutrition, NORMAL_NUTRITION, WELL_FED_NUTRITION)
-                    && clock.state != HungerState::WellFed
-                {
-                    clock.state = HungerState::WellFed;
-                    if entity == *player_entity {
-                        log.entries.push("You are well fed!".to_string());
-                    }
-                } else if in_range(clock.total_nutrition, HUNGRY_NUTRITION, NORMAL_NUTRITION)
-                    && clock.state != HungerState::Normal
-                {
-                    clock.state = HungerState::Normal;
-                    if entity == *player_entity {
-                        log.entries.push("You are not hungry.".to_string());
-                    }
-                } else if in_range(clock.total_nutrition, STARVING_NUTRITION, HUNGRY_NUTRITION)
-                    && clock.state != HungerState::Hungry
-                {
-                    clock.state = HungerState::Hungry;
-                    if entity == *player_entity {
-                        log.entries.push("You are hungry.".to_string());
+pub struct EatingSystem {}
+
+impl<'a> System<'a> for EatingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, RandomNumberGenerator>,
+        WriteStorage<'a, WantsToEat>,
+        WriteStorage<'a, HungerClock>,
+        ReadStorage<'a, Food>,
+        ReadStorage<'a, Poisonous>,
+        WriteStorage<'a, FoodPoisoning>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, RunStats>,
+        WriteExpect<'a, GameEvents>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            map,
+            mut log,
+            mut rng,
+            mut wants_eat,
+            mut hunger_clocks,
+            food,
+            poisonous,
+            mut food_poisoning,
+            names,
+            positions,
+            mut run_stats,
+            mut game_events,
+        ) = data;
+
+        let mut eaten: Vec<Entity> = Vec::new();
+        for (entity, wants_eat, clock) in (&entities, &wants_eat, &mut hunger_clocks).join() {
+            if eaten.contains(&wants_eat.food) {
+                continue;
+            }
+
+            let food_item = food.get(wants_eat.food);
+            if let Some(food_item) = food_item {
+                clock.total_nutrition += food_item.nutrition;
+                eaten.push(wants_eat.food);
+
+                let food_name = &names.get(wants_eat.food).unwrap().name;
+                if entity == *player_entity {
+                    log.entries.push(format!("You eat the {}.", food_name));
+                } else {
+                    // Only tell the player about meals they can actually see
+                    let pos = positions.get(entity);
+                    if let Some(pos) = pos {
+                        if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                            log.detail(format!(
+                                "The {} eats the {}.",
+                                names.get(entity).unwrap().name,
+                                food_name
+                            ));
+                        }
                     }
-                } else if in_range(clock.total_nutrition, -10000, STARVING_NUTRITION)
-                    && clock.state != HungerState::Starving
-                {
-                    clock.state = HungerState::Starving;
-                    if entity == *player_entity {
-                        log.entries
-                            .push("You are starving! Eat something!".to_string());
+                }
+
+                let mut rotten = false;
+                let poison = poisonous.get(wants_eat.food);
+                if let Some(poison) = poison {
+                    if rng.roll_dice(1, 100) <= poison.chance {
+                        rotten = true;
+                        food_poisoning
+                            .insert(
+                                entity,
+                                FoodPoisoning {
+                                    turns: poison.turns,
+                                },
+                            )
+                            .expect("Unable to insert food poisoning");
+                        if entity == *player_entity {
+                            log.entries
+                                .push(format!("The {} was rotten! You feel sick.", food_name));
+                        }
                     }
                 }
 
-                if clock.total_nutrition < STARVING_NUTRITION {
-                    if entity == *player_entity {
-                        log.entries.push(
-                            "Your hunger pangs are getting painful! You suffer 1 damage."
-                                .to_string(),
-                        );
+                if entity == *player_entity {
+                    if let Some(stats) = run_stats.get_mut(entity) {
+                        stats.meals_eaten += 1;
                     }
-                    SufferDamage::new_damage(&mut inflict_damage, entity, 1);
+                    game_events.emit(GameEvent::Ate { rotten });
                 }
             }
         }
+
+        for food_entity in eaten {
+            entities.delete(food_entity).expect("Delete failed");
+        }
+
+        wants_eat.clear();
     }
 }
 
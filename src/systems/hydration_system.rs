@@ -0,0 +1,200 @@
+use crate::{
+    components::{
+        HydrationClock, HydrationState, InInventory, LastDamagedBy, Name, SufferDamage,
+        WantsToDrink, WaterFlask, WaterSource,
+    },
+    game_log::GameLog,
+    RunState,
+};
+use specs::prelude::*;
+
+pub const FULL_HYDRATION: i32 = 1000;
+const QUENCHED_WATER: i32 = 800;
+const THIRSTY_WATER: i32 = 400;
+const PARCHED_WATER: i32 = 150;
+
+// A mouthful from a flask. A full flask buys a few hundred turns underground, less in the sun.
+const SIP_WATER: i32 = 250;
+
+pub struct HydrationSystem {}
+
+impl<'a> System<'a> for HydrationSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, HydrationClock>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, SufferDamage>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, LastDamagedBy>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut hydration_clock,
+            player_entity,
+            run_state,
+            mut inflict_damage,
+            mut log,
+            mut last_damaged_by,
+        ) = data;
+
+        if *run_state != RunState::NewTurn {
+            return;
+        }
+
+        for (entity, clock) in (&entities, &mut hydration_clock).join() {
+            clock.water = i32::max(0, clock.water - 1);
+
+            let state = hydration_state(clock.water);
+            if state != clock.state {
+                clock.state = state;
+                if entity == *player_entity {
+                    let entry = match state {
+                        HydrationState::Quenched => "Your thirst is quenched.",
+                        HydrationState::Normal => "You are not thirsty.",
+                        HydrationState::Thirsty => "You are thirsty.",
+                        HydrationState::Parched => "You are parched! Find water!",
+                    };
+                    log.entries.push(entry.to_string());
+                }
+            }
+
+            if state == HydrationState::Parched {
+                if entity == *player_entity {
+                    log.entries
+                        .push("Your throat burns with thirst! You suffer 1 damage.".to_string());
+                }
+                SufferDamage::new_damage(&mut inflict_damage, entity, 1);
+                last_damaged_by
+                    .insert(
+                        entity,
+                        LastDamagedBy {
+                            cause: "dehydration".to_string(),
+                            source: None,
+                            by_player: false,
+                        },
+                    )
+                    .expect("Unable to insert damage cause");
+            }
+        }
+    }
+}
+
+/// Handles sips from flasks, and drinks from wells and cisterns. Drinking from a well or
+/// cistern also tops up every flask the drinker is carrying.
+pub struct DrinkingSystem {}
+
+impl<'a> System<'a> for DrinkingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToDrink>,
+        WriteStorage<'a, HydrationClock>,
+        WriteStorage<'a, WaterFlask>,
+        WriteStorage<'a, WaterSource>,
+        ReadStorage<'a, InInventory>,
+        ReadStorage<'a, Name>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            mut log,
+            mut wants_drink,
+            mut hydration_clocks,
+            mut flasks,
+            mut sources,
+            backpack,
+            names,
+        ) = data;
+
+        let mut refilled: Vec<Entity> = Vec::new();
+        for (entity, wants_drink, clock) in (&entities, &wants_drink, &mut hydration_clocks).join()
+        {
+            let is_player = entity == *player_entity;
+            let source_name = names
+                .get(wants_drink.source)
+                .map_or("water".to_string(), |n| n.name.clone());
+
+            if let Some(flask) = flasks.get_mut(wants_drink.source) {
+                if flask.sips == 0 {
+                    if is_player {
+                        log.entries.push(format!("The {} is empty.", source_name));
+                    }
+                    continue;
+                }
+                flask.sips -= 1;
+                clock.water = i32::min(FULL_HYDRATION, clock.water + SIP_WATER);
+                if is_player {
+                    log.entries
+                        .push(format!("You take a sip from the {}.", source_name));
+                    if flask.sips == 0 {
+                        log.entries.push("That was the last of it.".to_string());
+                    }
+                }
+            } else if let Some(source) = sources.get_mut(wants_drink.source) {
+                if source.drinks == Some(0) {
+                    if is_player {
+                        log.entries
+                            .push(format!("The {} has run dry.", source_name));
+                    }
+                    continue;
+                }
+                if let Some(drinks) = source.drinks.as_mut() {
+                    *drinks -= 1;
+                }
+                clock.water = FULL_HYDRATION;
+                refilled.push(entity);
+                if is_player {
+                    log.entries.push(format!(
+                        "You drink deeply from the {} and fill your flasks.",
+                        source_name
+                    ));
+                }
+            }
+        }
+
+        for (flask, pack) in (&mut flasks, &backpack).join() {
+            if refilled.contains(&pack.owner) {
+                flask.sips = flask.max_sips;
+            }
+        }
+
+        wants_drink.clear();
+    }
+}
+
+fn hydration_state(water: i32) -> HydrationState {
+    if water >= QUENCHED_WATER {
+        HydrationState::Quenched
+    } else if water >= THIRSTY_WATER {
+        HydrationState::Normal
+    } else if water >= PARCHED_WATER {
+        HydrationState::Thirsty
+    } else {
+        HydrationState::Parched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thirst_sets_in_as_water_runs_down() {
+        assert_eq!(hydration_state(FULL_HYDRATION), HydrationState::Quenched);
+        assert_eq!(hydration_state(QUENCHED_WATER), HydrationState::Quenched);
+        assert_eq!(hydration_state(QUENCHED_WATER - 1), HydrationState::Normal);
+        assert_eq!(hydration_state(THIRSTY_WATER), HydrationState::Normal);
+        assert_eq!(hydration_state(THIRSTY_WATER - 1), HydrationState::Thirsty);
+        assert_eq!(hydration_state(PARCHED_WATER), HydrationState::Thirsty);
+        assert_eq!(hydration_state(PARCHED_WATER - 1), HydrationState::Parched);
+        assert_eq!(hydration_state(0), HydrationState::Parched);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{components, systems::inventory_system, RunState};
+use specs::prelude::*;
+
+// Energy needed to take an ordinary action. A creature with speed 100 acts once per turn.
+pub const ACTION_COST: i32 = 100;
+
+// Swinging something heavy or weaving a spell takes half as long again as a step
+const HEAVY_ACTION_COST: i32 = 150;
+const HEAVY_WEAPON_KG: f32 = 3.0;
+
+// Hauling too much, every step takes twice as long
+const OVERBURDENED_MOVE_COST: i32 = 200;
+
+/// Hands out turns. Whoever has enough energy goes next, monsters before the player, and when
+/// nobody does a new turn starts and everyone gains energy according to their speed. Nothing
+/// here is random, so the same speeds always give the same order.
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RunState>,
+        WriteStorage<'a, components::Initiative>,
+        WriteStorage<'a, components::MyTurn>,
+        WriteStorage<'a, components::RunStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, player_entity, mut run_state, mut initiatives, mut my_turn, mut run_stats) =
+            data;
+
+        if *run_state != RunState::Ticking {
+            return;
+        }
+
+        let mut player_ready = false;
+        let mut monsters_ready = false;
+        for (entity, initiative) in (&entities, &initiatives).join() {
+            if initiative.energy >= ACTION_COST {
+                if entity == *player_entity {
+                    player_ready = true;
+                } else {
+                    my_turn
+                        .insert(entity, components::MyTurn {})
+                        .expect("Unable to insert turn");
+                    monsters_ready = true;
+                }
+            }
+        }
+
+        if monsters_ready {
+            *run_state = RunState::MonsterTurn;
+        } else if player_ready {
+            *run_state = RunState::AwaitingInput;
+        } else {
+            for initiative in (&mut initiatives).join() {
+                initiative.energy += initiative.speed;
+            }
+            if let Some(stats) = run_stats.get_mut(*player_entity) {
+                stats.turns += 1;
+            }
+            *run_state = RunState::NewTurn;
+        }
+    }
+}
+
+/// Charges everyone who just acted for what they chose to do. Runs after the AI has picked its
+/// moves but before the intents are resolved, so we can still see what they were.
+pub struct TurnCostSystem {}
+
+impl<'a> System<'a> for TurnCostSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        WriteStorage<'a, components::Initiative>,
+        WriteStorage<'a, components::MyTurn>,
+        ReadStorage<'a, components::WantsToMelee>,
+        ReadStorage<'a, components::WantsToCastSpell>,
+        ReadStorage<'a, components::Equipped>,
+        ReadStorage<'a, components::Weight>,
+        ReadStorage<'a, components::EntityMoved>,
+        ReadStorage<'a, components::InInventory>,
+        ReadStorage<'a, components::Attributes>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            run_state,
+            mut initiatives,
+            mut my_turn,
+            wants_melee,
+            wants_cast,
+            equipped,
+            weights,
+            moved,
+            backpack,
+            attributes,
+        ) = data;
+
+        let mut acted: Vec<Entity> = Vec::new();
+        match *run_state {
+            RunState::PlayerTurn => acted.push(*player_entity),
+            RunState::MonsterTurn => acted.extend((&entities, &my_turn).join().map(|(e, _)| e)),
+            _ => return,
+        }
+
+        for entity in acted {
+            let wields_heavy_weapon = (&equipped, &weights).join().any(|(item, weight)| {
+                item.owner == entity
+                    && item.slot == components::EquipmentSlot::Melee
+                    && weight.kg >= HEAVY_WEAPON_KG
+            });
+            let is_heavy = wants_cast.get(entity).is_some()
+                || (wants_melee.get(entity).is_some() && wields_heavy_weapon);
+
+            let is_laden_step = moved.get(entity).is_some()
+                && inventory_system::is_overburdened_with(
+                    &entities,
+                    &weights,
+                    &backpack,
+                    &equipped,
+                    &attributes,
+                    entity,
+                );
+
+            if let Some(initiative) = initiatives.get_mut(entity) {
+                initiative.energy -= if is_heavy {
+                    HEAVY_ACTION_COST
+                } else if is_laden_step {
+                    OVERBURDENED_MOVE_COST
+                } else {
+                    ACTION_COST
+                };
+            }
+        }
+
+        my_turn.clear();
+    }
+}
@@ -1,5 +1,17 @@
-use crate::{components, systems::particle_system, GameLog, Map, RunState};
+use crate::{
+    components,
+    effects::{EffectQueue, EffectType, Targets},
+    GameLog, Map,
+};
 use specs::prelude::*;
+use specs::{storage::MaskedStorage, Storage};
+use std::ops::Deref;
+
+// Each point of strength lets you carry this many kg before you start to slow down
+const CARRY_PER_STRENGTH: f32 = 3.0;
+
+// Past this multiple of your capacity you can't pick anything else up at all
+const MAX_LOAD_MULTIPLIER: f32 = 1.5;
 
 pub struct ItemCollectionSystem {}
 
@@ -8,17 +20,53 @@ impl<'a> System<'a> for ItemCollectionSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
         WriteStorage<'a, components::WantsToPickupItem>,
         WriteStorage<'a, components::Position>,
         ReadStorage<'a, components::Name>,
         WriteStorage<'a, components::InInventory>,
+        ReadStorage<'a, components::Equipped>,
+        ReadStorage<'a, components::Weight>,
+        ReadStorage<'a, components::Attributes>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_pickup, mut positions, names, mut backpack) =
-            data;
+        let (
+            player_entity,
+            mut gamelog,
+            map,
+            entities,
+            mut wants_pickup,
+            mut positions,
+            names,
+            mut backpack,
+            equipped,
+            weights,
+            attributes,
+        ) = data;
 
         for pickup in wants_pickup.join() {
+            if let Some(attributes) = attributes.get(pickup.collected_by) {
+                let carried = weight_carried_by(
+                    &entities,
+                    &weights,
+                    &backpack,
+                    &equipped,
+                    pickup.collected_by,
+                );
+                let item_weight = weights.get(pickup.item).map_or(0.0, |w| w.kg);
+                if !within_load_limit(attributes, carried + item_weight) {
+                    if pickup.collected_by == *player_entity {
+                        gamelog.entries.push(format!(
+                            "The {} is too heavy for you to carry.",
+                            names.get(pickup.item).unwrap().name
+                        ));
+                    }
+                    continue;
+                }
+            }
+
             positions.remove(pickup.item);
             backpack
                 .insert(
@@ -34,6 +82,12 @@ impl<'a> System<'a> for ItemCollectionSystem {
                     "You pick up the {}.",
                     names.get(pickup.item).unwrap().name
                 ));
+            } else if player_can_see(&map, positions.get(pickup.collected_by)) {
+                gamelog.detail(format!(
+                    "The {} picks up the {}.",
+                    names.get(pickup.collected_by).unwrap().name,
+                    names.get(pickup.item).unwrap().name
+                ));
             }
         }
 
@@ -41,6 +95,92 @@ impl<'a> System<'a> for ItemCollectionSystem {
     }
 }
 
+pub fn carry_capacity(attributes: &components::Attributes) -> f32 {
+    attributes.strength as f32 * CARRY_PER_STRENGTH
+}
+
+fn within_load_limit(attributes: &components::Attributes, load: f32) -> bool {
+    load <= carry_capacity(attributes) * MAX_LOAD_MULTIPLIER
+}
+
+/// The total weight of everything an entity has in its backpack or is wearing. Takes the
+/// storages directly so systems can share it; the backpack may be borrowed for writing.
+fn weight_carried_by<B>(
+    entities: &Entities,
+    weights: &ReadStorage<components::Weight>,
+    backpack: &Storage<components::InInventory, B>,
+    equipped: &ReadStorage<components::Equipped>,
+    owner: Entity,
+) -> f32
+where
+    B: Deref<Target = MaskedStorage<components::InInventory>>,
+{
+    (entities, weights)
+        .join()
+        .filter(|(item, _)| {
+            backpack.get(*item).map_or(false, |b| b.owner == owner)
+                || equipped.get(*item).map_or(false, |e| e.owner == owner)
+        })
+        .map(|(_, weight)| weight.kg)
+        .sum()
+}
+
+pub fn carried_weight(ecs: &World, owner: Entity) -> f32 {
+    weight_carried_by(
+        &ecs.entities(),
+        &ecs.read_storage::<components::Weight>(),
+        &ecs.read_storage::<components::InInventory>(),
+        &ecs.read_storage::<components::Equipped>(),
+        owner,
+    )
+}
+
+/// Creatures without attributes don't care about weight.
+pub fn is_overburdened(ecs: &World, owner: Entity) -> bool {
+    is_overburdened_with(
+        &ecs.entities(),
+        &ecs.read_storage::<components::Weight>(),
+        &ecs.read_storage::<components::InInventory>(),
+        &ecs.read_storage::<components::Equipped>(),
+        &ecs.read_storage::<components::Attributes>(),
+        owner,
+    )
+}
+
+/// As `is_overburdened`, for systems that already hold the storages.
+pub fn is_overburdened_with<B>(
+    entities: &Entities,
+    weights: &ReadStorage<components::Weight>,
+    backpack: &Storage<components::InInventory, B>,
+    equipped: &ReadStorage<components::Equipped>,
+    attributes: &ReadStorage<components::Attributes>,
+    owner: Entity,
+) -> bool
+where
+    B: Deref<Target = MaskedStorage<components::InInventory>>,
+{
+    match attributes.get(owner) {
+        None => false,
+        Some(attributes) => {
+            weight_carried_by(entities, weights, backpack, equipped, owner)
+                > carry_capacity(attributes)
+        }
+    }
+}
+
+pub fn can_carry(ecs: &World, owner: Entity, item: Entity) -> bool {
+    match ecs.read_storage::<components::Attributes>().get(owner) {
+        None => true,
+        Some(attributes) => {
+            let item_weight = ecs
+                .read_storage::<components::Weight>()
+                .get(item)
+                .map_or(0.0, |w| w.kg);
+            within_load_limit(attributes, carried_weight(ecs, owner) + item_weight)
+        }
+    }
+}
+
 pub struct ItemUseSystem {}
 
 impl<'a> System<'a> for ItemUseSystem {
@@ -52,27 +192,13 @@ impl<'a> System<'a> for ItemUseSystem {
         Entities<'a>,
         WriteStorage<'a, components::WantsToUseItem>,
         ReadStorage<'a, components::Name>,
-        ReadStorage<'a, components::Consumable>,
-        ReadStorage<'a, components::ProvidesHealing>,
-        ReadStorage<'a, components::InflictsDamage>,
-        WriteStorage<'a, components::CombatStats>,
-        WriteStorage<'a, components::SufferDamage>,
         ReadStorage<'a, components::AreaOfEffect>,
-        // ReadStorage<'a, components::CausesConfusion>,
-        // WriteStorage<'a, components::Confusion>,
-        ReadStorage<'a, components::AppliesInvisiblity>,
-        WriteStorage<'a, components::WantsBeInvisible>,
-        ReadStorage<'a, components::Cooldown>,
-        WriteStorage<'a, components::ActiveCooldown>,
-        WriteExpect<'a, particle_system::ParticleBuilder>,
+        ReadStorage<'a, components::ActiveCooldown>,
         ReadStorage<'a, components::Position>,
         ReadStorage<'a, components::Equippable>,
         WriteStorage<'a, components::Equipped>,
         WriteStorage<'a, components::InInventory>,
-        ReadStorage<'a, components::MagicMapper>,
-        WriteExpect<'a, RunState>,
-        ReadStorage<'a, components::Food>,
-        WriteStorage<'a, components::HungerClock>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -83,349 +209,109 @@ impl<'a> System<'a> for ItemUseSystem {
             entities,
             mut wants_use,
             names,
-            consumables,
-            healing,
-            inflict_damage,
-            mut combat_stats,
-            mut suffer_damage,
             aoe,
-            // causes_confusion,
-            // mut confused,
-            applies_invisibility,
-            mut wants_be_invisible,
-            cooldowns,
-            mut active_cooldowns,
-            mut particle_builder,
+            active_cooldowns,
             positions,
             equippable,
             mut equipped,
             mut inventory,
-            magic_mapper,
-            mut run_state,
-            food,
-            mut hunger_clocks,
+            mut effect_queue,
         ) = data;
 
         for (entity, use_item) in (&entities, &wants_use).join() {
-            let mut used_item = true;
-            let mut can_use_item = true;
-
             let item_on_cooldown = active_cooldowns.get(use_item.item);
-            match item_on_cooldown {
-                None => {}
-                Some(cooldown) => {
-                    if entity == *player_entity {
-                        gamelog.entries.push(format!(
-                            "You cannot use {}, it is on cooldown for {} turns",
-                            names.get(use_item.item).unwrap().name,
-                            cooldown.turns_remaining
-                        ));
-                    }
-                    can_use_item = false;
-                    used_item = false;
+            if let Some(cooldown) = item_on_cooldown {
+                if entity == *player_entity {
+                    gamelog.entries.push(format!(
+                        "You cannot use {}, it is on cooldown for {} turns",
+                        names.get(use_item.item).unwrap().name,
+                        cooldown.turns_remaining
+                    ));
                 }
+                continue;
             }
 
-            if can_use_item {
-                // Targeting
-                let mut targets: Vec<Entity> = Vec::new();
-                match use_item.target {
-                    None => {
-                        targets.push(*player_entity);
-                    }
-                    Some(target) => {
-                        let area_effect = aoe.get(use_item.item);
-                        match area_effect {
-                            None => {
-                                // Single target in tile
-                                let tile_idx = map.xy_idx(target.x, target.y);
-                                for mob in map.tile_content[tile_idx].iter() {
-                                    targets.push(*mob);
-                                }
-                            }
-                            Some(area_effect) => {
-                                // AoE
-                                let mut blast_tiles =
-                                    rltk::field_of_view(target, area_effect.radius, &*map);
-                                blast_tiles.retain(|p| {
-                                    p.x > 0
-                                        && p.x < map.width - 1
-                                        && p.y > 0
-                                        && p.y < map.height - 1
-                                });
-                                for tile_idx in blast_tiles.iter() {
-                                    let idx = map.xy_idx(tile_idx.x, tile_idx.y);
-                                    for mob in map.tile_content[idx].iter() {
-                                        targets.push(*mob);
-                                    }
-                                    particle_builder.request(
-                                        tile_idx.x,
-                                        tile_idx.y,
-                                        rltk::RGB::named(rltk::ORANGE),
-                                        rltk::RGB::named(rltk::BLACK),
-                                        rltk::to_cp437('░'),
-                                        200.0,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // If it is equippable, then we want to equip it - and unequip whatever else was in that slot
-                let item_equippable = equippable.get(use_item.item);
-                match item_equippable {
-                    None => {}
-                    Some(can_equip) => {
-                        let target_slot = can_equip.slot;
-                        let target = targets[0];
-
-                        // Remove any items the target has in the item's slot
-                        let mut to_unequip: Vec<Entity> = Vec::new();
-                        for (item_entity, already_equipped, name) in
-                            (&entities, &equipped, &names).join()
-                        {
-                            if already_equipped.owner == target
-                                && already_equipped.slot == target_slot
-                            {
-                                to_unequip.push(item_entity);
-                                if target == *player_entity {
-                                    gamelog.entries.push(format!("You unequip {}.", name.name));
-                                }
-                            }
-                        }
-                        for item in to_unequip.iter() {
-                            equipped.remove(*item);
-                            inventory
-                                .insert(*item, components::InInventory { owner: target })
-                                .expect("Unable to insert backpack entry");
-                        }
-
-                        // Wield the item
-                        equipped
-                            .insert(
-                                use_item.item,
-                                components::Equipped {
-                                    owner: target,
-                                    slot: target_slot,
-                                },
-                            )
-                            .expect("Unable to insert equipped component");
-                        inventory.remove(use_item.item);
-                        if target == *player_entity {
-                            gamelog.entries.push(format!(
-                                "You equip {}.",
-                                names.get(use_item.item).unwrap().name
-                            ));
-                        }
-                    }
-                }
-
-                // It it is edible, eat it!
-                let item_edible = food.get(use_item.item);
-                match item_edible {
-                    None => {}
-                    Some(food_item) => {
-                        used_item = true;
-                        let target = targets[0];
-                        let hunger_clock = hunger_clocks.get_mut(target);
-                        if let Some(hunger_clock) = hunger_clock {
-                            hunger_clock.total_nutrition += food_item.nutrition;
-                            gamelog.entries.push(format!(
-                                "You eat the {}.",
-                                names.get(use_item.item).unwrap().name
+            // If it is equippable, then we want to equip it - and unequip whatever else was in that slot
+            let item_equippable = equippable.get(use_item.item);
+            if let Some(can_equip) = item_equippable {
+                let target_slot = can_equip.slot;
+
+                // Remove any items the user has in the item's slot
+                let mut to_unequip: Vec<Entity> = Vec::new();
+                for (item_entity, already_equipped, name) in (&entities, &equipped, &names).join() {
+                    if already_equipped.owner == entity && already_equipped.slot == target_slot {
+                        to_unequip.push(item_entity);
+                        if entity == *player_entity {
+                            gamelog.entries.push(format!("You unequip {}.", name.name));
+                        } else if player_can_see(&map, positions.get(entity)) {
+                            gamelog.detail(format!(
+                                "The {} unequips {}.",
+                                names.get(entity).unwrap().name,
+                                name.name
                             ));
                         }
                     }
                 }
-
-                // If it heals, apply the healing
-                let item_heals = healing.get(use_item.item);
-                match item_heals {
-                    None => {}
-                    Some(healer) => {
-                        used_item = false;
-                        for target in targets.iter() {
-                            let stats = combat_stats.get_mut(*target);
-                            if let Some(stats) = stats {
-                                stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
-                                if entity == *player_entity {
-                                    gamelog.entries.push(format!(
-                                        "You use the {}, healing {} hp.",
-                                        names.get(use_item.item).unwrap().name,
-                                        healer.heal_amount
-                                    ));
-                                }
-                                used_item = true;
-
-                                let pos = positions.get(*target);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(
-                                        pos.x,
-                                        pos.y,
-                                        rltk::RGB::named(rltk::GREEN),
-                                        rltk::RGB::named(rltk::BLACK),
-                                        rltk::to_cp437('♥'),
-                                        200.0,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // If its a magic mapper...
-                let is_mapper = magic_mapper.get(use_item.item);
-                match is_mapper {
-                    None => {}
-                    Some(_) => {
-                        used_item = true;
-                        gamelog
-                            .entries
-                            .push("The map is revealed to you!".to_string());
-                        *run_state = RunState::MagicMapReveal { row: 0 };
-                    }
-                }
-
-                // If it inflicts damage, apply it to the target cell
-                let item_damages = inflict_damage.get(use_item.item);
-                match item_damages {
-                    None => {}
-                    Some(damage) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            components::SufferDamage::new_damage(
-                                &mut suffer_damage,
-                                *mob,
-                                damage.damage,
-                            );
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(use_item.item).unwrap();
-                                gamelog.entries.push(format!(
-                                    "You use {} on {}, inflicting {} hp.",
-                                    item_name.name, mob_name.name, damage.damage
-                                ));
-                            }
-
-                            used_item = true;
-                            let pos = positions.get(*mob);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::RED),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('‼'),
-                                    200.0,
-                                );
-                            }
-                        }
-                    }
+                for item in to_unequip.iter() {
+                    equipped.remove(*item);
+                    inventory
+                        .insert(*item, components::InInventory { owner: entity })
+                        .expect("Unable to insert backpack entry");
                 }
 
-                // If it applies invisiblity, apply it
-                let makes_invisible = applies_invisibility.get(use_item.item);
-                match makes_invisible {
-                    None => {}
-                    Some(invisible) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            wants_be_invisible
-                                .insert(
-                                    *mob,
-                                    components::WantsBeInvisible {
-                                        entity: *mob,
-                                        turns: invisible.turns,
-                                    },
-                                )
-                                .expect("Failed to insert wants be invisible");
-                            if entity == *player_entity {
-                                let item_name = names.get(use_item.item).unwrap();
-                                gamelog.entries.push(format!(
-                                    "You use {}, and become invisible for {} turns.",
-                                    item_name.name, invisible.turns
-                                ));
-                            }
-
-                            used_item = true;
-                        }
-                    }
+                // Wield the item
+                equipped
+                    .insert(
+                        use_item.item,
+                        components::Equipped {
+                            owner: entity,
+                            slot: target_slot,
+                        },
+                    )
+                    .expect("Unable to insert equipped component");
+                inventory.remove(use_item.item);
+                if entity == *player_entity {
+                    gamelog.entries.push(format!(
+                        "You equip {}.",
+                        names.get(use_item.item).unwrap().name
+                    ));
+                } else if player_can_see(&map, positions.get(entity)) {
+                    gamelog.detail(format!(
+                        "The {} equips {}.",
+                        names.get(entity).unwrap().name,
+                        names.get(use_item.item).unwrap().name
+                    ));
                 }
-
-                // Can it pass along confusion?
-                //     let mut add_confusion = Vec::new();
-                //     {
-                //         let item_causes_confusion = causes_confusion.get(use_item.item);
-                //         match item_causes_confusion {
-                //             None => {}
-                //             Some(confusion) => {
-                //                 used_item = false;
-                //                 for mob in targets.iter() {
-                //                     add_confusion.push((*mob, confusion.turns));
-                //                     if entity == *player_entity {
-                //                         let mob_name = names.get(*mob).unwrap();
-                //                         let item_name = names.get(use_item.item).unwrap();
-                //                         gamelog.entries.push(format!(
-                //                             "You use {} on {}, confusing them.",
-                //                             item_name.name, mob_name.name
-                //                         ));
-
-                //                         let pos = positions.get(*mob);
-                //                         if let Some(pos) = pos {
-                //                             particle_builder.request(
-                //                                 pos.x,
-                //                                 pos.y,
-                //                                 rltk::RGB::named(rltk::MAGENTA),
-                //                                 rltk::RGB::named(rltk::BLACK),
-                //                                 rltk::to_cp437('?'),
-                //                                 400.0,
-                //                             );
-                //                         }
-                //                     }
-                //                     used_item = true;
-                //                 }
-                //             }
-                //         }
-                //     }
-                //     for (mob_entity, remaining_turns) in add_confusion.iter() {
-                //         confused
-                //             .insert(
-                //                 *mob_entity,
-                //                 components::Confusion {
-                //                     turns: *remaining_turns,
-                //                 },
-                //             )
-                //             .expect("Unable to insert status");
-                //     }
+                continue;
             }
 
-            if used_item {
-                // If its a consumable, we delete it on use
-                let consumable = consumables.get(use_item.item);
-                match consumable {
-                    None => {}
-                    Some(_) => {
-                        entities.delete(use_item.item).expect("Delete failed");
-                    }
-                }
-
-                // If it has a cooldown, add an active cooldown
-                let has_cooldown = cooldowns.get(use_item.item);
-                match has_cooldown {
-                    None => {}
-                    Some(cooldown) => {
-                        active_cooldowns
-                            .insert(
-                                use_item.item,
-                                components::ActiveCooldown {
-                                    turns_remaining: cooldown.turns,
-                                },
-                            )
-                            .expect("Failed to insert active cooldown");
+            // Everything else the item does is handled by the effects queue
+            let targets = match use_item.target {
+                None => Targets::Caster,
+                Some(target) => match aoe.get(use_item.item) {
+                    None => Targets::Tile {
+                        tile_idx: map.xy_idx(target.x, target.y),
+                    },
+                    Some(area_effect) => {
+                        let mut blast_tiles =
+                            rltk::field_of_view(target, area_effect.radius, &*map);
+                        blast_tiles.retain(|p| {
+                            p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
+                        });
+                        Targets::Area {
+                            tiles: blast_tiles.iter().map(|p| map.xy_idx(p.x, p.y)).collect(),
+                        }
                     }
-                }
-            }
+                },
+            };
+            effect_queue.add_effect(
+                Some(entity),
+                Some(use_item.item),
+                EffectType::ItemUse {
+                    item: use_item.item,
+                },
+                targets,
+            );
         }
 
         wants_use.clear();
@@ -531,3 +417,39 @@ impl<'a> System<'a> for ItemRemoveSystem {
         wants_remove.clear();
     }
 }
+
+/// Whether something happening at this position should make it into the player's log
+fn player_can_see(map: &Map, pos: Option<&components::Position>) -> bool {
+    match pos {
+        None => false,
+        Some(pos) => map.visible_tiles[map.xy_idx(pos.x, pos.y)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(strength: i32) -> components::Attributes {
+        components::Attributes {
+            strength,
+            agility: 10,
+            toughness: 10,
+            intellect: 10,
+        }
+    }
+
+    #[test]
+    fn capacity_follows_strength() {
+        assert_eq!(carry_capacity(&attributes(10)), 30.0);
+        assert_eq!(carry_capacity(&attributes(14)), 42.0);
+    }
+
+    #[test]
+    fn load_limit_is_half_again_the_capacity() {
+        let average = attributes(10);
+        assert!(within_load_limit(&average, 30.0));
+        assert!(within_load_limit(&average, 45.0));
+        assert!(!within_load_limit(&average, 45.5));
+    }
+}
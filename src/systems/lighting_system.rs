@@ -0,0 +1,80 @@
+use crate::{clock, components, Map};
+use rltk::{field_of_view, Point, RGB};
+use specs::prelude::*;
+
+/// Builds the light map: the level's ambient light, plus whatever each light source reaches,
+/// fading with distance. A carried light shines from whoever is carrying it. On the surface the
+/// ambient light follows the sun.
+pub struct LightingSystem {}
+
+impl<'a> System<'a> for LightingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        Entities<'a>,
+        ReadStorage<'a, components::LightSource>,
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::InInventory>,
+        ReadStorage<'a, components::Equipped>,
+        WriteStorage<'a, components::Viewshed>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, components::RunStats>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut map,
+            entities,
+            light_sources,
+            positions,
+            backpack,
+            equipped,
+            mut viewsheds,
+            player_entity,
+            run_stats,
+        ) = data;
+
+        if map.surface {
+            let turns = run_stats.get(*player_entity).map_or(0, |stats| stats.turns);
+            map.ambient_light = clock::sky_light(turns);
+        }
+
+        let previous = std::mem::take(&mut map.light);
+        map.reset_light();
+
+        for (entity, light_source) in (&entities, &light_sources).join() {
+            let holder = backpack
+                .get(entity)
+                .map(|b| b.owner)
+                .or_else(|| equipped.get(entity).map(|e| e.owner))
+                .unwrap_or(entity);
+            let origin = match positions.get(holder) {
+                Some(pos) => Point::new(pos.x, pos.y),
+                None => continue,
+            };
+
+            let lit_tiles = field_of_view(origin, light_source.range, &*map);
+            for tile in lit_tiles.iter() {
+                if tile.x < 0 || tile.x >= map.width || tile.y < 0 || tile.y >= map.height {
+                    continue;
+                }
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(origin, *tile);
+                let intensity = 1.0 - distance / (light_source.range as f32 + 1.0);
+                if intensity <= 0.0 {
+                    continue;
+                }
+                let idx = map.xy_idx(tile.x, tile.y);
+                let light = map.light[idx] + light_source.color * intensity;
+                map.light[idx] =
+                    RGB::from_f32(light.r.min(1.0), light.g.min(1.0), light.b.min(1.0));
+            }
+        }
+
+        // Everyone has to take another look when the light changes
+        if map.light != previous {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{components, systems::particle_system, GameLog};
+use crate::{
+    components,
+    effects::{EffectQueue, EffectType, Targets},
+    GameLog,
+};
 use specs::prelude::*;
 
 pub struct MeleeCombatSystem {}
@@ -9,11 +13,9 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteStorage<'a, components::WantsToMelee>,
         ReadStorage<'a, components::Name>,
         ReadStorage<'a, components::CombatStats>,
-        WriteStorage<'a, components::SufferDamage>,
         WriteStorage<'a, components::Invisible>,
         WriteExpect<'a, GameLog>,
-        WriteExpect<'a, particle_system::ParticleBuilder>,
-        ReadStorage<'a, components::Position>,
+        WriteExpect<'a, EffectQueue>,
         ReadStorage<'a, components::MeleePowerBonus>,
         ReadStorage<'a, components::DefenseBonus>,
         ReadStorage<'a, components::Equipped>,
@@ -26,11 +28,9 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut wants_melee,
             names,
             combat_stats,
-            mut inflict_damage,
             mut invisible,
             mut game_log,
-            mut particle_builder,
-            positions,
+            mut effect_queue,
             melee_power_bonuses,
             defense_bonuses,
             equipped,
@@ -65,18 +65,6 @@ impl<'a> System<'a> for MeleeCombatSystem {
                     }
                 }
 
-                let pos = positions.get(wants_melee.target);
-                if let Some(pos) = pos {
-                    particle_builder.request(
-                        pos.x,
-                        pos.y,
-                        rltk::RGB::named(rltk::ORANGE),
-                        rltk::RGB::named(rltk::BLACK),
-                        rltk::to_cp437('‼'),
-                        130.0,
-                    );
-                }
-
                 let target_stats = combat_stats.get(wants_melee.target).unwrap();
                 if target_stats.hp > 0 {
                     let target_name = names.get(wants_melee.target).unwrap();
@@ -101,14 +89,13 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             &name.name, &target_name.name
                         ));
                     } else {
-                        game_log.entries.push(format!(
-                            "{} hits {}, for {} hp.",
-                            &name.name, &target_name.name, damage
-                        ));
-                        components::SufferDamage::new_damage(
-                            &mut inflict_damage,
-                            wants_melee.target,
-                            damage,
+                        effect_queue.add_effect(
+                            Some(entity),
+                            None,
+                            EffectType::Damage { amount: damage },
+                            Targets::Single {
+                                target: wants_melee.target,
+                            },
                         );
                     }
                 }
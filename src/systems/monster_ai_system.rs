@@ -14,12 +14,22 @@ impl<'a> System<'a> for MonsterAI {
         Entities<'a>,
         WriteStorage<'a, components::Viewshed>,
         WriteStorage<'a, components::Monster>,
+        ReadStorage<'a, components::MyTurn>,
         WriteStorage<'a, components::Position>,
         WriteStorage<'a, components::WantsToMelee>,
         ReadStorage<'a, components::Confusion>,
         ReadStorage<'a, components::Invisible>,
         WriteExpect<'a, particle_system::ParticleBuilder>,
         WriteStorage<'a, components::EntityMoved>,
+        ReadStorage<'a, components::HungerClock>,
+        ReadStorage<'a, components::Diet>,
+        ReadStorage<'a, components::Food>,
+        WriteStorage<'a, components::WantsToEat>,
+        ReadStorage<'a, components::WantsToPickupItem>,
+        ReadStorage<'a, components::WantsToUseItem>,
+        ReadStorage<'a, components::Entangled>,
+        WriteStorage<'a, components::Alerted>,
+        ReadStorage<'a, components::Companion>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -31,20 +41,48 @@ impl<'a> System<'a> for MonsterAI {
             entities,
             mut viewshed,
             mut monster,
+            my_turn,
             mut position,
             mut wants_to_melee,
             confused,
             invisible,
             mut particle_builder,
             mut entities_moved,
+            hunger_clocks,
+            diets,
+            food,
+            mut wants_to_eat,
+            wants_pickup,
+            wants_use,
+            entangled,
+            mut alerted,
+            companions,
         ) = data;
 
         if *run_state != RunState::MonsterTurn {
             return;
         };
 
-        for (entity, viewshed, monster, pos) in
-            (&entities, &mut viewshed, &mut monster, &mut position).join()
+        // Collected up front, since we move monsters around below
+        let food_on_floor: Vec<(Entity, Point, components::Food)> = (&entities, &food, &position)
+            .join()
+            .map(|(item, edible, item_pos)| {
+                (item, Point::new(item_pos.x, item_pos.y), edible.clone())
+            })
+            .collect();
+        let companion_positions: Vec<(Entity, Point)> = (&entities, &companions, &position)
+            .join()
+            .map(|(companion, _, pos)| (companion, Point::new(pos.x, pos.y)))
+            .collect();
+
+        for (entity, viewshed, monster, _my_turn, pos) in (
+            &entities,
+            &mut viewshed,
+            &mut monster,
+            &my_turn,
+            &mut position,
+        )
+            .join()
         {
             let mut can_act = true;
 
@@ -59,49 +97,169 @@ impl<'a> System<'a> for MonsterAI {
                 can_act = false;
             }
 
+            // Already busy with an item this turn
+            if wants_pickup.get(entity).is_some() || wants_use.get(entity).is_some() {
+                can_act = false;
+            }
+
             if can_act {
-                let distance =
-                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-                if distance < 1.5 {
-                    wants_to_melee
-                        .insert(
-                            entity,
-                            components::WantsToMelee {
-                                target: *player_entity,
-                            },
-                        )
-                        .expect("Unable to insert attack");
-                    try_target_player(&mut particle_builder, monster, pos.x, pos.y);
-                } else if viewshed.visible_tiles.contains(&*player_pos) {
-                    // Path to the player
-                    let path = rltk::a_star_search(
-                        map.xy_idx(pos.x, pos.y),
-                        map.xy_idx(player_pos.x, player_pos.y),
-                        &mut *map,
-                    );
-
-                    if path.success && path.steps.len() > 1 {
-                        let mut idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = false;
-                        pos.x = path.steps[1] as i32 % map.width;
-                        pos.y = path.steps[1] as i32 / map.width;
-                        idx = map.xy_idx(pos.x, pos.y);
-                        map.blocked[idx] = true;
-                        viewshed.dirty = true;
-
-                        entities_moved
-                            .insert(entity, components::EntityMoved {})
-                            .expect("Could not insert entity moved");
+                let my_pos = Point::new(pos.x, pos.y);
+                let hunger_state = hunger_clocks.get(entity).map(|clock| clock.state);
+                let is_starving = hunger_state == Some(components::HungerState::Starving);
+                let meal = match hunger_state {
+                    Some(components::HungerState::Hungry)
+                    | Some(components::HungerState::Starving) => find_food(
+                        &food_on_floor,
+                        viewshed,
+                        diets.get(entity),
+                        is_starving,
+                        my_pos,
+                    ),
+                    _ => None,
+                };
+                let can_see_player = viewshed.visible_tiles.contains(&*player_pos);
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(my_pos, *player_pos);
+                let is_stuck = entangled.get(entity).is_some();
+                let adjacent_companion = companion_positions
+                    .iter()
+                    .find(|(_, companion_pos)| {
+                        rltk::DistanceAlg::Pythagoras.distance2d(my_pos, *companion_pos) < 1.5
+                    })
+                    .map(|(companion, _)| *companion);
+
+                // Once the player is in sight there's no need to go looking for them
+                if can_see_player {
+                    alerted.remove(entity);
+                }
+
+                match meal {
+                    Some((food_entity, food_pos)) if food_pos == my_pos => {
+                        wants_to_eat
+                            .insert(entity, components::WantsToEat { food: food_entity })
+                            .expect("Unable to insert want to eat");
+                    }
+                    _ if distance < 1.5 => {
+                        wants_to_melee
+                            .insert(
+                                entity,
+                                components::WantsToMelee {
+                                    target: *player_entity,
+                                },
+                            )
+                            .expect("Unable to insert attack");
+                        try_target_player(&mut particle_builder, monster, pos.x, pos.y);
+                    }
+                    // The player's companions are fair game when they get in the way
+                    _ if adjacent_companion.is_some() => {
+                        wants_to_melee
+                            .insert(
+                                entity,
+                                components::WantsToMelee {
+                                    target: adjacent_companion.unwrap(),
+                                },
+                            )
+                            .expect("Unable to insert attack");
+                    }
+                    // Hungry monsters only go foraging when the player isn't around, but a
+                    // starving one will abandon the chase for a meal
+                    Some((_food_entity, food_pos)) if is_starving || !can_see_player => {
+                        if !is_stuck && move_towards(&mut map, pos, viewshed, food_pos) {
+                            entities_moved
+                                .insert(entity, components::EntityMoved {})
+                                .expect("Could not insert entity moved");
+                        }
+                        stop_target_player(&mut particle_builder, monster, pos.x, pos.y);
                     }
-                    try_target_player(&mut particle_builder, monster, pos.x, pos.y);
-                } else {
-                    stop_target_player(&mut particle_builder, monster, pos.x, pos.y);
+                    _ if can_see_player => {
+                        // Path to the player
+                        if !is_stuck && move_towards(&mut map, pos, viewshed, *player_pos) {
+                            entities_moved
+                                .insert(entity, components::EntityMoved {})
+                                .expect("Could not insert entity moved");
+                        }
+                        try_target_player(&mut particle_builder, monster, pos.x, pos.y);
+                    }
+                    // Something set off an alarm, so go and see what it was
+                    _ if alerted.get(entity).is_some() && !is_stuck => {
+                        let alarm = alerted.get(entity).unwrap();
+                        let alarm_pos = Point::new(alarm.x, alarm.y);
+                        if alarm_pos != my_pos && move_towards(&mut map, pos, viewshed, alarm_pos) {
+                            entities_moved
+                                .insert(entity, components::EntityMoved {})
+                                .expect("Could not insert entity moved");
+                        } else {
+                            // Nothing to see here, or no way to get there
+                            alerted.remove(entity);
+                        }
+                    }
+                    _ => stop_target_player(&mut particle_builder, monster, pos.x, pos.y),
                 }
             }
         }
     }
 }
 
+/// Finds the closest visible food the creature is willing to eat. Starving creatures are
+/// desperate enough to eat anything.
+fn find_food(
+    food_on_floor: &[(Entity, Point, components::Food)],
+    viewshed: &components::Viewshed,
+    diet: Option<&components::Diet>,
+    is_starving: bool,
+    from: Point,
+) -> Option<(Entity, Point)> {
+    let desperate = components::Diet {
+        diet_type: components::DietType::Omnivore,
+    };
+    let diet = match diet {
+        _ if is_starving => &desperate,
+        Some(diet) => diet,
+        None => return None,
+    };
+
+    food_on_floor
+        .iter()
+        .filter(|(_, food_pos, edible)| {
+            diet.can_eat(edible) && viewshed.visible_tiles.contains(food_pos)
+        })
+        .map(|(item, food_pos, _)| {
+            (
+                *item,
+                *food_pos,
+                rltk::DistanceAlg::Pythagoras.distance2d(from, *food_pos),
+            )
+        })
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+        .map(|(item, food_pos, _)| (item, food_pos))
+}
+
+/// Takes one step along the shortest path to the target, returning whether we moved.
+pub fn move_towards(
+    map: &mut Map,
+    pos: &mut components::Position,
+    viewshed: &mut components::Viewshed,
+    target: Point,
+) -> bool {
+    let path = rltk::a_star_search(
+        map.xy_idx(pos.x, pos.y),
+        map.xy_idx(target.x, target.y),
+        &*map,
+    );
+
+    if path.success && path.steps.len() > 1 {
+        let mut idx = map.xy_idx(pos.x, pos.y);
+        map.blocked[idx] = false;
+        pos.x = path.steps[1] as i32 % map.width;
+        pos.y = path.steps[1] as i32 / map.width;
+        idx = map.xy_idx(pos.x, pos.y);
+        map.blocked[idx] = true;
+        viewshed.dirty = true;
+        return true;
+    }
+
+    false
+}
+
 fn try_target_player(
     particle_builder: &mut particle_system::ParticleBuilder,
     monster: &mut components::Monster,
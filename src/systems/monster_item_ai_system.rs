@@ -0,0 +1,198 @@
+use crate::{components, Map, RunState};
+use rltk::Point;
+use specs::prelude::*;
+
+// Monsters reach for a potion once they are down to a third of their health
+const HEAL_THRESHOLD_DIVISOR: i32 = 3;
+
+/// Decides when monsters should use, pick up or equip items. Runs ahead of `MonsterAI`, which
+/// leaves alone any monster that has already spent its turn on an item here.
+pub struct MonsterItemAI {}
+
+impl<'a> System<'a> for MonsterItemAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Map>,
+        ReadExpect<'a, Point>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, RunState>,
+        Entities<'a>,
+        ReadStorage<'a, components::Monster>,
+        ReadStorage<'a, components::MyTurn>,
+        ReadStorage<'a, components::Viewshed>,
+        ReadStorage<'a, components::Position>,
+        ReadStorage<'a, components::CombatStats>,
+        ReadStorage<'a, components::Confusion>,
+        ReadStorage<'a, components::Invisible>,
+        ReadStorage<'a, components::Item>,
+        ReadStorage<'a, components::InInventory>,
+        ReadStorage<'a, components::Equippable>,
+        ReadStorage<'a, components::Equipped>,
+        ReadStorage<'a, components::MeleePowerBonus>,
+        ReadStorage<'a, components::DefenseBonus>,
+        ReadStorage<'a, components::ProvidesHealing>,
+        ReadStorage<'a, components::Ranged>,
+        ReadStorage<'a, components::InflictsDamage>,
+        ReadStorage<'a, components::AreaOfEffect>,
+        ReadStorage<'a, components::ActiveCooldown>,
+        ReadStorage<'a, components::Spell>,
+        WriteStorage<'a, components::WantsToPickupItem>,
+        WriteStorage<'a, components::WantsToUseItem>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            map,
+            player_pos,
+            player_entity,
+            run_state,
+            entities,
+            monsters,
+            my_turn,
+            viewsheds,
+            positions,
+            combat_stats,
+            confused,
+            invisible,
+            items,
+            backpack,
+            equippable,
+            equipped,
+            melee_bonuses,
+            defense_bonuses,
+            healing,
+            ranged,
+            inflicts_damage,
+            aoe,
+            active_cooldowns,
+            spells,
+            mut wants_pickup,
+            mut wants_use,
+        ) = data;
+
+        if *run_state != RunState::MonsterTurn {
+            return;
+        }
+
+        let item_bonus = |item: Entity| -> i32 {
+            melee_bonuses.get(item).map_or(0, |b| b.power)
+                + defense_bonuses.get(item).map_or(0, |b| b.defense)
+        };
+
+        for (entity, _monster, _my_turn, viewshed, pos, stats) in (
+            &entities,
+            &monsters,
+            &my_turn,
+            &viewsheds,
+            &positions,
+            &combat_stats,
+        )
+            .join()
+        {
+            if confused.get(entity).is_some() {
+                continue;
+            }
+
+            let carried: Vec<Entity> = (&entities, &backpack)
+                .join()
+                .filter(|(item, carried)| {
+                    carried.owner == entity && active_cooldowns.get(*item).is_none()
+                })
+                .map(|(item, _)| item)
+                .collect();
+
+            // Drink a potion when badly hurt
+            if stats.hp <= stats.max_hp / HEAL_THRESHOLD_DIVISOR {
+                let potion = carried.iter().find(|item| healing.get(**item).is_some());
+                if let Some(potion) = potion {
+                    wants_use
+                        .insert(
+                            entity,
+                            components::WantsToUseItem {
+                                item: *potion,
+                                target: None,
+                            },
+                        )
+                        .expect("Unable to insert intent");
+                    continue;
+                }
+            }
+
+            // Throw an offensive scroll at the player, as long as we aren't caught in the blast
+            let can_see_player = invisible.get(*player_entity).is_none()
+                && viewshed.visible_tiles.contains(&*player_pos);
+            if can_see_player {
+                let distance =
+                    rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
+                let scroll = carried.iter().find(|item| {
+                    let in_range = ranged
+                        .get(**item)
+                        .map_or(false, |r| distance <= r.range as f32);
+                    let clear_of_blast =
+                        aoe.get(**item).map_or(true, |a| distance > a.radius as f32);
+                    inflicts_damage.get(**item).is_some() && in_range && clear_of_blast
+                });
+                if let Some(scroll) = scroll {
+                    wants_use
+                        .insert(
+                            entity,
+                            components::WantsToUseItem {
+                                item: *scroll,
+                                target: Some(*player_pos),
+                            },
+                        )
+                        .expect("Unable to insert intent");
+                    continue;
+                }
+            }
+
+            // Pick up anything useful we're standing on, wielding it if it beats what we have
+            let idx = map.xy_idx(pos.x, pos.y);
+            for item in map.tile_content[idx].iter() {
+                // Monsters have no mana, so spells are no use to them
+                if items.get(*item).is_none() || spells.get(*item).is_some() {
+                    continue;
+                }
+
+                let is_upgrade = match equippable.get(*item) {
+                    None => false,
+                    Some(can_equip) => {
+                        let current_bonus = (&entities, &equipped)
+                            .join()
+                            .filter(|(_, e)| e.owner == entity && e.slot == can_equip.slot)
+                            .map(|(current, _)| item_bonus(current))
+                            .max()
+                            .unwrap_or(0);
+                        item_bonus(*item) > current_bonus
+                    }
+                };
+                let is_useful = healing.get(*item).is_some()
+                    || (ranged.get(*item).is_some() && inflicts_damage.get(*item).is_some());
+
+                if is_upgrade || is_useful {
+                    wants_pickup
+                        .insert(
+                            entity,
+                            components::WantsToPickupItem {
+                                collected_by: entity,
+                                item: *item,
+                            },
+                        )
+                        .expect("Unable to insert want to pickup");
+                    if is_upgrade {
+                        wants_use
+                            .insert(
+                                entity,
+                                components::WantsToUseItem {
+                                    item: *item,
+                                    target: None,
+                                },
+                            )
+                            .expect("Unable to insert intent");
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::components::*;
-use crate::{map, MAP_COUNT};
+use crate::map;
 #[allow(deprecated)]
 use specs::error::NoError;
 use specs::prelude::*;
@@ -74,7 +74,6 @@ pub fn save_game(ecs: &mut World) {
             WantsToDropItem,
             SerializationHelper,
             Invisible,
-            WantsBeInvisible,
             AppliesInvisiblity,
             Cooldown,
             ActiveCooldown,
@@ -88,6 +87,40 @@ pub fn save_game(ecs: &mut World) {
             MagicMapper,
             HungerClock,
             Food,
+            Diet,
+            WantsToEat,
+            Poisonous,
+            FoodPoisoning,
+            Mana,
+            Spell,
+            KnownSpell,
+            WantsToLearnSpell,
+            WantsToCastSpell,
+            TeleportsRandomly,
+            Trap,
+            Skills,
+            SoundsAlarm,
+            Alerted,
+            DropsALevel,
+            Entangles,
+            Entangled,
+            AutoTravel,
+            AutoExplore,
+            Attributes,
+            Weight,
+            Initiative,
+            RunStats,
+            LastDamagedBy,
+            Artifact,
+            Npc,
+            QuestLog,
+            Companion,
+            CompanionTarget,
+            LightSource,
+            HydrationClock,
+            WaterFlask,
+            WaterSource,
+            WantsToDrink,
             EntryTrigger,
             EntityMoved
         );
@@ -166,7 +199,6 @@ pub fn load_game(ecs: &mut World) {
             WantsToDropItem,
             SerializationHelper,
             Invisible,
-            WantsBeInvisible,
             AppliesInvisiblity,
             Cooldown,
             ActiveCooldown,
@@ -180,6 +212,40 @@ pub fn load_game(ecs: &mut World) {
             MagicMapper,
             HungerClock,
             Food,
+            Diet,
+            WantsToEat,
+            Poisonous,
+            FoodPoisoning,
+            Mana,
+            Spell,
+            KnownSpell,
+            WantsToLearnSpell,
+            WantsToCastSpell,
+            TeleportsRandomly,
+            Trap,
+            Skills,
+            SoundsAlarm,
+            Alerted,
+            DropsALevel,
+            Entangles,
+            Entangled,
+            AutoTravel,
+            AutoExplore,
+            Attributes,
+            Weight,
+            Initiative,
+            RunStats,
+            LastDamagedBy,
+            Artifact,
+            Npc,
+            QuestLog,
+            Companion,
+            CompanionTarget,
+            LightSource,
+            HydrationClock,
+            WaterFlask,
+            WaterSource,
+            WantsToDrink,
             EntryTrigger,
             EntityMoved
         );
@@ -194,7 +260,8 @@ pub fn load_game(ecs: &mut World) {
         for (e, h) in (&entities, &helper).join() {
             let mut worldmap = ecs.write_resource::<map::Map>();
             *worldmap = h.map.clone();
-            worldmap.tile_content = vec![Vec::new(); MAP_COUNT as usize];
+            worldmap.tile_content = vec![Vec::new(); (worldmap.width * worldmap.height) as usize];
+            worldmap.reset_light();
             deleteme = Some(e);
         }
         for (e, _p, pos) in (&entities, &player, &position).join() {
@@ -0,0 +1,90 @@
+use crate::{components, GameLog};
+use specs::prelude::*;
+
+pub struct SpellSystem {}
+
+impl<'a> System<'a> for SpellSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, components::Name>,
+        ReadStorage<'a, components::Spell>,
+        WriteStorage<'a, components::Mana>,
+        WriteStorage<'a, components::KnownSpell>,
+        WriteStorage<'a, components::InInventory>,
+        WriteStorage<'a, components::WantsToLearnSpell>,
+        WriteStorage<'a, components::WantsToCastSpell>,
+        WriteStorage<'a, components::WantsToUseItem>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            mut gamelog,
+            names,
+            spells,
+            mut mana,
+            mut known_spells,
+            mut backpack,
+            mut wants_learn,
+            mut wants_cast,
+            mut wants_use,
+        ) = data;
+
+        for (entity, learn) in (&entities, &wants_learn).join() {
+            backpack.remove(learn.spell);
+            known_spells
+                .insert(learn.spell, components::KnownSpell { owner: entity })
+                .expect("Unable to insert known spell");
+
+            if entity == *player_entity {
+                gamelog.entries.push(format!(
+                    "You commit {} to memory.",
+                    names.get(learn.spell).unwrap().name
+                ));
+            }
+        }
+        wants_learn.clear();
+
+        // Pay for the spell up front, then let the item system apply its effects
+        for (entity, cast, pool) in (&entities, &wants_cast, &mut mana).join() {
+            let spell = spells.get(cast.spell).unwrap();
+            if pool.mana < spell.mana_cost {
+                if entity == *player_entity {
+                    gamelog.entries.push(format!(
+                        "You don't have enough mana to cast {}.",
+                        names.get(cast.spell).unwrap().name
+                    ));
+                }
+                continue;
+            }
+
+            pool.mana -= spell.mana_cost;
+            wants_use
+                .insert(
+                    entity,
+                    components::WantsToUseItem {
+                        item: cast.spell,
+                        target: cast.target,
+                    },
+                )
+                .expect("Unable to insert intent");
+        }
+        wants_cast.clear();
+    }
+}
+
+/// The spells an entity knows, in the order they appear on the hotbar.
+pub fn known_spells(ecs: &World, owner: Entity) -> Vec<Entity> {
+    let entities = ecs.entities();
+    let known_spells = ecs.read_storage::<components::KnownSpell>();
+
+    (&entities, &known_spells)
+        .join()
+        .filter(|(_, known)| known.owner == owner)
+        .map(|(spell, _)| spell)
+        .collect()
+}
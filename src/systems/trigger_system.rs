@@ -1,12 +1,12 @@
 use crate::{
     components::{
-        EntityMoved, EntryTrigger, InflictsDamage, Invisible, Name, Position, SingleActivation,
-        SufferDamage,
+        ActiveCooldown, AreaOfEffect, EntityMoved, EntryTrigger, Invisible, Name, Position,
     },
+    effects::{EffectQueue, EffectType, Targets},
     game_log::GameLog,
     map::Map,
-    systems::particle_system::ParticleBuilder,
 };
+use rltk::Point;
 use specs::prelude::*;
 
 pub struct TriggerSystem {}
@@ -15,36 +15,35 @@ impl<'a> System<'a> for TriggerSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
         ReadExpect<'a, Map>,
+        ReadExpect<'a, Entity>,
         WriteStorage<'a, EntityMoved>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, EntryTrigger>,
+        ReadStorage<'a, ActiveCooldown>,
+        ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, Invisible>,
         ReadStorage<'a, Name>,
         Entities<'a>,
         WriteExpect<'a, GameLog>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteExpect<'a, ParticleBuilder>,
-        WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, SingleActivation>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             map,
+            player_entity,
             mut entity_moved,
             position,
             entry_trigger,
+            active_cooldowns,
+            area_of_effect,
             mut invisibles,
             names,
             entities,
             mut log,
-            inflicts_damage,
-            mut particle_builder,
-            mut suffer_damage,
-            single_activations,
+            mut effect_queue,
         ) = data;
 
-        let mut remove_entities: Vec<Entity> = Vec::new();
         // Iterate the entities that moved and their final position
         for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &position).join() {
             let idx = map.xy_idx(pos.x, pos.y);
@@ -55,44 +54,56 @@ impl<'a> System<'a> for TriggerSystem {
                     match maybe_trigger {
                         None => {}
                         Some(_trigger) => {
-                            // We triggered it
-                            let name = names.get(*entity_id);
-                            if let Some(name) = name {
-                                log.entries.push(format!("{} triggers!", &name.name));
+                            // Traps that have gone off recently are still resetting
+                            if active_cooldowns.get(*entity_id).is_some() {
+                                continue;
                             }
 
-                            // If the trap is damage inflicting, do it
-                            let damage = inflicts_damage.get(*entity_id);
-                            if let Some(damage) = damage {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::ORANGE),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('‼'),
-                                    200.0,
-                                );
-                                SufferDamage::new_damage(&mut suffer_damage, entity, damage.damage);
+                            // We triggered it, and monsters can set them off too
+                            let name = names.get(*entity_id);
+                            if let Some(name) = name {
+                                if entity == *player_entity || map.visible_tiles[idx] {
+                                    log.entries.push(format!("{} triggers!", &name.name));
+                                }
                             }
 
                             invisibles.remove(*entity_id); // The trap is no longer hidden
 
-                            // If it is single activation, it needs to be removed
-                            let single_activation = single_activations.get(*entity_id);
-                            if let Some(_sa) = single_activation {
-                                remove_entities.push(*entity_id);
-                            }
+                            let targets = match area_of_effect.get(*entity_id) {
+                                None => Targets::Single { target: entity },
+                                Some(area) => {
+                                    let mut tiles = rltk::field_of_view(
+                                        Point::new(pos.x, pos.y),
+                                        area.radius,
+                                        &*map,
+                                    );
+                                    tiles.retain(|p| {
+                                        p.x > 0
+                                            && p.x < map.width - 1
+                                            && p.y > 0
+                                            && p.y < map.height - 1
+                                    });
+                                    Targets::Area {
+                                        tiles: tiles.iter().map(|p| map.xy_idx(p.x, p.y)).collect(),
+                                    }
+                                }
+                            };
+
+                            // Whatever the trap does to its victim is handled by the effects queue
+                            effect_queue.add_effect(
+                                None,
+                                Some(*entity_id),
+                                EffectType::TriggerFire {
+                                    trigger: *entity_id,
+                                },
+                                targets,
+                            );
                         }
                     }
                 }
             }
         }
 
-        // Remove any single activation traps
-        for trap in remove_entities.iter() {
-            entities.delete(*trap).expect("Unable to delete trap");
-        }
-
         // Remove all entity movement markers
         entity_moved.clear();
     }
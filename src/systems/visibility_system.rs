@@ -5,6 +5,7 @@ use specs::prelude::*;
 pub struct VisibilitySystem {}
 
 impl<'a> System<'a> for VisibilitySystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteExpect<'a, Map>,
         Entities<'a>,
@@ -15,6 +16,9 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, rltk::RandomNumberGenerator>,
         ReadStorage<'a, components::Name>,
         WriteExpect<'a, GameLog>,
+        ReadStorage<'a, components::Trap>,
+        ReadStorage<'a, components::Skills>,
+        WriteStorage<'a, components::RunStats>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -28,6 +32,9 @@ impl<'a> System<'a> for VisibilitySystem {
             mut rng,
             names,
             mut game_log,
+            traps,
+            skills,
+            mut run_stats,
         ) = data;
 
         for (entity, viewshed, position) in (&entities, &mut viewshed, &pos).join() {
@@ -40,6 +47,13 @@ impl<'a> System<'a> for VisibilitySystem {
                     .visible_tiles
                     .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
 
+                // You can't see into the dark, but you can always make out what's right next to you
+                let origin = Point::new(position.x, position.y);
+                viewshed.visible_tiles.retain(|p| {
+                    map.is_lit(map.xy_idx(p.x, p.y))
+                        || rltk::DistanceAlg::Pythagoras.distance2d(origin, *p) < 1.5
+                });
+
                 // If this is the player, reveal what they can see
                 let _p: Option<&components::Player> = player.get(entity);
                 if let Some(_p) = _p {
@@ -48,15 +62,25 @@ impl<'a> System<'a> for VisibilitySystem {
                     }
                     for vis in viewshed.visible_tiles.iter() {
                         let idx = map.xy_idx(vis.x, vis.y);
+                        if !map.revealed_tiles[idx] {
+                            if let Some(stats) = run_stats.get_mut(entity) {
+                                stats.tiles_explored += 1;
+                            }
+                        }
                         map.revealed_tiles[idx] = true;
                         map.visible_tiles[idx] = true;
 
-                        // Chance to reveal hidden things
+                        // Chance to spot hidden traps, which gets harder the further away they are
                         for e in map.tile_content[idx].iter() {
-                            if *e != entity {
-                                let maybe_hidden = invisibles.get(*e);
-                                if let Some(_maybe_hidden) = maybe_hidden {
-                                    if rng.roll_dice(1, 24) == 1 {
+                            if *e != entity && invisibles.get(*e).is_some() {
+                                if let Some(trap) = traps.get(*e) {
+                                    let distance = rltk::DistanceAlg::Pythagoras
+                                        .distance2d(Point::new(position.x, position.y), *vis)
+                                        as i32;
+                                    let perception = skills.get(entity).map_or(0, |s| s.perception);
+                                    if rng.roll_dice(1, 20) + perception - distance / 2
+                                        >= trap.spot_difficulty
+                                    {
                                         let name = names.get(*e);
                                         if let Some(name) = name {
                                             game_log
@@ -0,0 +1,398 @@
+use super::{
+    components, map, player::try_move_player, settings::Settings,
+    systems::inventory_system::can_carry, GameLog, Map, RunState,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+// How far out the explore map looks for unexplored ground
+const EXPLORE_MAX_DEPTH: f32 = 1000.0;
+
+/// Works out a route to the given tile and sets the player off along it, one step per turn.
+pub fn travel_to(ecs: &mut World, destination: Point) -> RunState {
+    if let Some(monster) = visible_monster(ecs) {
+        log(ecs, format!("You can't travel with a {} in view.", monster));
+        return RunState::AwaitingInput;
+    }
+
+    let path = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        if destination.x < 0
+            || destination.x >= map.width
+            || destination.y < 0
+            || destination.y >= map.height
+        {
+            return RunState::AwaitingInput;
+        }
+        let destination_idx = map.xy_idx(destination.x, destination.y);
+        if !map.revealed_tiles[destination_idx] || map.tiles[destination_idx] == map::TileType::Wall
+        {
+            return RunState::AwaitingInput;
+        }
+
+        let path = rltk::a_star_search(
+            map.xy_idx(player_pos.x, player_pos.y),
+            destination_idx,
+            &*map,
+        );
+        // Don't let the pathfinder give away parts of the map we haven't seen
+        if path.success && path.steps.iter().all(|idx| map.revealed_tiles[*idx]) {
+            Some(path.steps.into_iter().skip(1).collect::<Vec<usize>>())
+        } else {
+            None
+        }
+    };
+
+    match path {
+        Some(path) if !path.is_empty() => {
+            let player_entity = *ecs.fetch::<Entity>();
+            let hp = player_hp(ecs);
+            ecs.write_storage::<components::AutoTravel>()
+                .insert(player_entity, components::AutoTravel { path, last_hp: hp })
+                .expect("Unable to insert auto travel");
+            continue_travel(ecs).unwrap_or(RunState::AwaitingInput)
+        }
+        Some(_) => RunState::AwaitingInput,
+        None => {
+            log(ecs, "You don't know a way there.".to_string());
+            RunState::AwaitingInput
+        }
+    }
+}
+
+pub fn travel_to_stairs(ecs: &mut World) -> RunState {
+    let stairs = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        map.tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, tile)| **tile == map::TileType::DownStairs && map.revealed_tiles[*idx])
+            .map(|(idx, _)| Point::new(idx as i32 % map.width, idx as i32 / map.width))
+            .min_by(|a, b| {
+                let distance_a = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *a);
+                let distance_b = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *b);
+                distance_a.partial_cmp(&distance_b).unwrap()
+            })
+    };
+
+    match stairs {
+        None => {
+            log(ecs, "You haven't found the way down yet.".to_string());
+            RunState::AwaitingInput
+        }
+        Some(stairs) => travel_to(ecs, stairs),
+    }
+}
+
+pub fn start_exploring(ecs: &mut World) -> RunState {
+    if let Some(monster) = visible_monster(ecs) {
+        log(
+            ecs,
+            format!("You can't explore with a {} in view.", monster),
+        );
+        return RunState::AwaitingInput;
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    let explore = components::AutoExplore {
+        last_hp: player_hp(ecs),
+        last_hunger: player_hunger(ecs),
+    };
+    ecs.write_storage::<components::AutoExplore>()
+        .insert(player_entity, explore)
+        .expect("Unable to insert auto explore");
+    continue_travel(ecs).unwrap_or(RunState::AwaitingInput)
+}
+
+/// Takes the next step of whatever travel or exploring the player is doing. Returns `None` once
+/// there's nothing left to do, or something has come up that needs the player's attention.
+pub fn continue_travel(ecs: &mut World) -> Option<RunState> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let travel = ecs
+        .read_storage::<components::AutoTravel>()
+        .get(player_entity)
+        .cloned();
+    if let Some(travel) = travel {
+        return follow_path(ecs, travel);
+    }
+
+    let explore = ecs
+        .read_storage::<components::AutoExplore>()
+        .get(player_entity)
+        .cloned();
+    if let Some(explore) = explore {
+        return explore_step(ecs, explore);
+    }
+
+    None
+}
+
+fn follow_path(ecs: &mut World, travel: components::AutoTravel) -> Option<RunState> {
+    let player_entity = *ecs.fetch::<Entity>();
+
+    let interruption = if let Some(monster) = visible_monster(ecs) {
+        Some(format!("You stop travelling when you spot a {}.", monster))
+    } else if player_hp(ecs) < travel.last_hp {
+        Some("You stop travelling as you are hurt!".to_string())
+    } else {
+        None
+    };
+    if let Some(interruption) = interruption {
+        log(ecs, interruption);
+        stop_travel(ecs);
+        return None;
+    }
+
+    let next_step = match travel.path.first() {
+        None => {
+            stop_travel(ecs);
+            return None;
+        }
+        Some(idx) => *idx,
+    };
+
+    let (blocked, dx, dy) = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        (
+            map.blocked[next_step],
+            next_step as i32 % map.width - player_pos.x,
+            next_step as i32 / map.width - player_pos.y,
+        )
+    };
+    if blocked {
+        log(ecs, "Something blocks your way.".to_string());
+        stop_travel(ecs);
+        return None;
+    }
+    // We've been moved off the path somehow, such as by a teleport trap
+    if dx.abs() > 1 || dy.abs() > 1 {
+        stop_travel(ecs);
+        return None;
+    }
+    try_move_player(dx, dy, ecs);
+
+    // We might not have got anywhere, if we were stuck
+    let arrived = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        map.xy_idx(player_pos.x, player_pos.y) == next_step
+    };
+    let hp = player_hp(ecs);
+    if let Some(travel) = ecs
+        .write_storage::<components::AutoTravel>()
+        .get_mut(player_entity)
+    {
+        if arrived {
+            travel.path.remove(0);
+        }
+        travel.last_hp = hp;
+    }
+
+    // Stop on anything lying on the floor, so the player can decide whether to take it
+    if let Some(item) = item_underfoot(ecs) {
+        log(ecs, format!("You come across a {}.", item));
+        stop_travel(ecs);
+    }
+
+    Some(RunState::PlayerTurn)
+}
+
+fn explore_step(ecs: &mut World, explore: components::AutoExplore) -> Option<RunState> {
+    let player_entity = *ecs.fetch::<Entity>();
+    let hunger = player_hunger(ecs);
+    // Otherwise we stop on each item to let the player decide
+    let pickup = ecs.fetch::<Settings>().auto_explore_pickup;
+
+    let interruption = if let Some(monster) = visible_monster(ecs) {
+        Some(format!("You stop exploring when you spot a {}.", monster))
+    } else if player_hp(ecs) < explore.last_hp {
+        Some("You stop exploring as you are hurt!".to_string())
+    } else if hunger != explore.last_hunger && hunger == Some(components::HungerState::Hungry) {
+        Some("You stop exploring as you are getting hungry.".to_string())
+    } else if hunger != explore.last_hunger && hunger == Some(components::HungerState::Starving) {
+        Some("You stop exploring as you are starving!".to_string())
+    } else {
+        None
+    };
+    if let Some(interruption) = interruption {
+        log(ecs, interruption);
+        stop_travel(ecs);
+        return None;
+    }
+
+    if let Some(explore) = ecs
+        .write_storage::<components::AutoExplore>()
+        .get_mut(player_entity)
+    {
+        explore.last_hunger = hunger;
+    }
+
+    if pickup {
+        let underfoot: Vec<Entity> = {
+            let player_pos = ecs.fetch::<Point>();
+            let entities = ecs.entities();
+            let items = ecs.read_storage::<components::Item>();
+            let positions = ecs.read_storage::<components::Position>();
+            (&entities, &items, &positions)
+                .join()
+                .filter(|(_, _, pos)| pos.x == player_pos.x && pos.y == player_pos.y)
+                .map(|(item, _, _)| item)
+                .collect()
+        };
+        // Leave behind anything too heavy, or we'd keep trying to lift it forever
+        let item = underfoot
+            .into_iter()
+            .find(|item| can_carry(ecs, player_entity, *item));
+        if let Some(item) = item {
+            ecs.write_storage::<components::WantsToPickupItem>()
+                .insert(
+                    player_entity,
+                    components::WantsToPickupItem {
+                        collected_by: player_entity,
+                        item,
+                    },
+                )
+                .expect("Unable to insert want to pickup");
+            return Some(RunState::PlayerTurn);
+        }
+    }
+
+    // Head for the nearest bit of known floor that borders somewhere we haven't seen yet
+    let next_step = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let frontier: Vec<usize> = (0..map.tiles.len())
+            .filter(|idx| is_frontier(&map, *idx))
+            .collect();
+        if frontier.is_empty() {
+            None
+        } else {
+            let dijkstra =
+                rltk::DijkstraMap::new(map.width, map.height, &frontier, &*map, EXPLORE_MAX_DEPTH);
+            rltk::DijkstraMap::find_lowest_exit(
+                &dijkstra,
+                map.xy_idx(player_pos.x, player_pos.y),
+                &*map,
+            )
+        }
+    };
+
+    let next_step = match next_step {
+        None => {
+            log(ecs, "There's nothing left here to explore.".to_string());
+            stop_travel(ecs);
+            return None;
+        }
+        Some(idx) => idx,
+    };
+
+    let (dx, dy) = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        (
+            next_step as i32 % map.width - player_pos.x,
+            next_step as i32 / map.width - player_pos.y,
+        )
+    };
+    try_move_player(dx, dy, ecs);
+
+    let hp = player_hp(ecs);
+    if let Some(explore) = ecs
+        .write_storage::<components::AutoExplore>()
+        .get_mut(player_entity)
+    {
+        explore.last_hp = hp;
+    }
+
+    if !pickup {
+        if let Some(item) = item_underfoot(ecs) {
+            log(ecs, format!("You come across a {}.", item));
+            stop_travel(ecs);
+        }
+    }
+
+    Some(RunState::PlayerTurn)
+}
+
+/// A known, walkable tile next to one we haven't seen yet.
+fn is_frontier(map: &Map, idx: usize) -> bool {
+    if !map.revealed_tiles[idx] || map.tiles[idx] == map::TileType::Wall {
+        return false;
+    }
+
+    let x = idx as i32 % map.width;
+    let y = idx as i32 / map.width;
+    for neighbour_x in x - 1..=x + 1 {
+        for neighbour_y in y - 1..=y + 1 {
+            if neighbour_x < 0
+                || neighbour_x >= map.width
+                || neighbour_y < 0
+                || neighbour_y >= map.height
+            {
+                continue;
+            }
+            if !map.revealed_tiles[map.xy_idx(neighbour_x, neighbour_y)] {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+pub fn stop_travel(ecs: &mut World) {
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<components::AutoTravel>()
+        .remove(player_entity);
+    ecs.write_storage::<components::AutoExplore>()
+        .remove(player_entity);
+}
+
+fn visible_monster(ecs: &World) -> Option<String> {
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let monsters = ecs.read_storage::<components::Monster>();
+    let positions = ecs.read_storage::<components::Position>();
+    let names = ecs.read_storage::<components::Name>();
+    let invisible = ecs.read_storage::<components::Invisible>();
+
+    (&entities, &monsters, &positions, &names)
+        .join()
+        .find(|(entity, _, pos, _)| {
+            map.visible_tiles[map.xy_idx(pos.x, pos.y)] && invisible.get(*entity).is_none()
+        })
+        .map(|(_, _, _, name)| name.name.clone())
+}
+
+fn item_underfoot(ecs: &World) -> Option<String> {
+    let player_pos = ecs.fetch::<Point>();
+    let items = ecs.read_storage::<components::Item>();
+    let positions = ecs.read_storage::<components::Position>();
+    let names = ecs.read_storage::<components::Name>();
+
+    (&items, &positions, &names)
+        .join()
+        .find(|(_, pos, _)| pos.x == player_pos.x && pos.y == player_pos.y)
+        .map(|(_, _, name)| name.name.clone())
+}
+
+fn player_hunger(ecs: &World) -> Option<components::HungerState> {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<components::HungerClock>()
+        .get(*player_entity)
+        .map(|clock| clock.state)
+}
+
+fn player_hp(ecs: &World) -> i32 {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<components::CombatStats>()
+        .get(*player_entity)
+        .map_or(0, |stats| stats.hp)
+}
+
+fn log(ecs: &World, entry: String) {
+    ecs.fetch_mut::<GameLog>().entries.push(entry);
+}